@@ -23,11 +23,12 @@ impl<Node, State, Input> Editor<Node, State, Input> {
     pub fn command<C: Command<State>>(&mut self, cmd: C) {
         cmd.execute(&mut self.state)
     }
-    /// Adds a block to the editor's list of blocks.
+    /// Adds a block to the editor's list of blocks, calling its [`Block::hook`].
     pub fn add_block<B: Block<Node = Node, State = State, Input = Input> + 'static>(
         &mut self,
         block: B,
     ) {
+        block.hook(self);
         self.blocks.push(Box::new(block))
     }
 
@@ -72,6 +73,11 @@ pub trait Block {
     type Node;
     type State;
     /// Called when a block is hooked into an editor.
+    ///
+    /// [`Editor::add_block`] runs the hook as it registers the block, so it must not be
+    /// called again by hand, or the bindings it adds would be registered twice. Earlier
+    /// releases never called it. The fallback block set with [`Editor::set_fallback_block`]
+    /// is not hooked.
     fn hook(&self, editor: &mut Editor<Self::Node, Self::State, Self::Input>) {}
 
     /// Determines whether the block accepts a specific input node.
@@ -119,8 +125,10 @@ pub fn process_nodes<N, S, I, Iter: IntoIterator<Item = I>>(
                 }
             }
             // Fallback for nodes not accepted by any block
-            if !accepted && editor.fallback_block.is_some() {
-                parsed_nodes.push(editor.fallback_block.as_ref().unwrap().parse(editor, node));
+            if !accepted {
+                if let Some(fallback) = &editor.fallback_block {
+                    parsed_nodes.push(fallback.parse(editor, node));
+                }
             }
         }
     }
@@ -189,6 +197,29 @@ mod tests {
         assert_eq!(parsed_nodes, vec![8]); // 4 * 2 = 8
     }
 
+    #[test]
+    fn test_block_hook() {
+        struct HookedBlock;
+
+        impl Block for HookedBlock {
+            type Input = i32;
+            type Node = i32;
+            type State = TestState;
+
+            fn hook(&self, editor: &mut Editor<i32, TestState, i32>) {
+                editor.value += 10;
+            }
+
+            fn parse(&self, _editor: &Editor<i32, TestState, i32>, input: &i32) -> i32 {
+                *input
+            }
+        }
+
+        let mut editor: Editor<i32, TestState, i32> = Editor::new(TestState { value: 0 });
+        editor.add_block(HookedBlock);
+        assert_eq!(editor.state.value, 10);
+    }
+
     #[test]
     fn test_editor_set_fallback_block() {
         let mut editor: Editor<i32, TestState, i32> = Editor::new(TestState { value: 0 });
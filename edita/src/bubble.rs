@@ -0,0 +1,83 @@
+//! A toolbar of mark buttons floating over the selected text.
+use hirola::dom::Dom;
+use hirola::prelude::*;
+use wasm_bindgen::JsCast;
+
+use crate::state::EditorState;
+use crate::suggestion::HIDDEN;
+use crate::toolbar::Toolbar;
+
+/// How wide each button is expected to be, to keep the toolbar inside the editor.
+const BUTTON_WIDTH: f64 = 32.0;
+
+/// How tall the toolbar is expected to be, to place it above the selection.
+const TOOLBAR_HEIGHT: f64 = 36.0;
+
+/// The space between the toolbar and the selection.
+const GAP: f64 = 8.0;
+
+/// The group of the toolbar items shown in the bubble toolbar.
+const GROUP: &str = "marks";
+
+/// Where the bubble toolbar is shown.
+#[derive(Clone)]
+pub(crate) struct BubbleToolbar {
+    style: Mutable<String>,
+}
+
+impl Default for BubbleToolbar {
+    fn default() -> Self {
+        BubbleToolbar {
+            style: Mutable::new(HIDDEN.to_owned()),
+        }
+    }
+}
+
+impl BubbleToolbar {
+    /// Shows the toolbar over the selection, or hides it when no text is selected.
+    pub(crate) fn update(&self, state: &EditorState) {
+        let range = state
+            .selection_range()
+            .filter(|range| !String::from(range.to_string()).trim().is_empty());
+        let Some(range) = range else {
+            self.style.set_neq(HIDDEN.to_owned());
+            return;
+        };
+        let items = state.toolbar_items();
+        let count = items.iter().filter(|item| item.group == GROUP).count();
+        let editor: web_sys::Element = state.node().get().inner_element().unchecked_into();
+        self.style.set_neq(toolbar_style(
+            &range.get_bounding_client_rect(),
+            &editor.get_bounding_client_rect(),
+            count as f64 * BUTTON_WIDTH,
+        ));
+    }
+}
+
+/// Centres the toolbar above the selection, or below it when there is no room above,
+/// kept inside the editor.
+fn toolbar_style(selection: &web_sys::DomRect, editor: &web_sys::DomRect, width: f64) -> String {
+    let center = (selection.left() + selection.right()) / 2.0;
+    let left = (center - width / 2.0)
+        .min(editor.right() - width)
+        .max(editor.left());
+    let above = selection.top() - TOOLBAR_HEIGHT - GAP;
+    let top = match above < editor.top().max(0.0) {
+        true => selection.bottom() + GAP,
+        false => above,
+    };
+    format!("position: fixed; left: {left}px; top: {top}px")
+}
+
+/// The [`Toolbar`] of the `marks` group, floating over selected text.
+///
+/// It follows the selection as [`EditorState::handle_selection_change`] runs.
+pub fn bubble_toolbar(state: &EditorState) -> Dom {
+    use hirola::dom::effects::attr_use::{attr_signal, Signal, UseEffect};
+    let style = state.bubble().style.signal_cloned();
+    html! {
+        <div class="bubble-toolbar" use:signal=attr_signal("style", style)>
+            {Toolbar::new(state).with_groups(&[GROUP])}
+        </div>
+    }
+}
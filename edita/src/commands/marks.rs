@@ -0,0 +1,470 @@
+use std::marker::PhantomData;
+
+use edita_core::Command;
+use wasm_bindgen::JsCast;
+use web_sys::{window, Document, Element, HtmlElement, Node, Range, Text};
+
+use crate::nodes::{sanitize_color, Subscript, Superscript};
+use crate::state::EditorState;
+
+/// The element matching `selectors` around `node`, if it lies inside the editor.
+fn marked_ancestor(state: &EditorState, node: Node, selectors: &str) -> Option<Element> {
+    let element = match node.dyn_into::<Element>() {
+        Ok(element) => element,
+        Err(node) => node.parent_element()?,
+    };
+    let closest = element.closest(selectors).ok()??;
+    state
+        .node()
+        .get()
+        .inner_element()
+        .contains(Some(&closest))
+        .then_some(closest)
+}
+
+/// Replaces an element with its children.
+fn unwrap_element(element: &Element) {
+    let document = window().unwrap().document().unwrap();
+    let fragment = document.create_document_fragment();
+    while let Some(child) = element.first_child() {
+        fragment.append_child(&child).unwrap();
+    }
+    element.replace_with_with_node_1(&fragment).unwrap();
+}
+
+/// An inline node written as an element around its text, which [`ToggleMark`] adds and
+/// removes.
+pub trait MarkElement {
+    /// The tag of new marks, eg. `b`.
+    const TAG: &'static str;
+    /// The elements counting as the mark, eg. `b, strong`.
+    const SELECTORS: &'static str;
+}
+
+/// Removes the mark `M` from the selection when all the selected text has it, or adds it
+/// to the selected text without it.
+///
+/// The selection may run across blocks and start or end inside text nodes, which are split
+/// at its ends. Marks left next to an identical mark or inside the same mark are merged
+/// into it, and emptied marks are dropped.
+pub struct ToggleMark<M>(PhantomData<M>);
+
+impl<M> Default for ToggleMark<M> {
+    fn default() -> Self {
+        ToggleMark(PhantomData)
+    }
+}
+
+impl<M: MarkElement> ToggleMark<M> {
+    pub const fn new() -> Self {
+        ToggleMark(PhantomData)
+    }
+
+    /// Whether all the selected text has the mark, or the caret is inside it.
+    pub fn is_active(state: &EditorState) -> bool {
+        match state.selection_range() {
+            Some(range) if range.collapsed() => state.closest_at_cursor(M::SELECTORS).is_some(),
+            Some(_) => selection_marked(state, M::SELECTORS, |_| true),
+            None => false,
+        }
+    }
+}
+
+impl<M: MarkElement> Command<EditorState> for ToggleMark<M> {
+    fn execute(&self, state: &mut EditorState) {
+        if selection_marked(state, M::SELECTORS, |_| true) {
+            mark_selection(state, M::SELECTORS, |_| true, None);
+        } else {
+            let create = |document: &Document| document.create_element(M::TAG).unwrap();
+            mark_selection(state, M::SELECTORS, |_| false, Some(&create));
+        }
+    }
+}
+
+/// Whether all the selected text is inside an element matching `selectors` for which
+/// `matches` holds.
+fn selection_marked(
+    state: &EditorState,
+    selectors: &str,
+    matches: impl Fn(&Element) -> bool,
+) -> bool {
+    let Some(range) = state.selection_range() else {
+        return false;
+    };
+    let parts = selected_text(&range);
+    !parts.is_empty()
+        && parts.iter().all(|(text, ..)| {
+            marked_ancestor(state, text.clone().into(), selectors)
+                .is_some_and(|mark| matches(&mark))
+        })
+}
+
+/// Takes the selected text out of the marks matching `selectors` for which `replace` holds,
+/// then puts the text left outside such marks in a mark made by `create`, if given, as
+/// described for [`ToggleMark`].
+fn mark_selection(
+    state: &EditorState,
+    selectors: &str,
+    replace: impl Fn(&Element) -> bool,
+    create: Option<&dyn Fn(&Document) -> Element>,
+) {
+    let Some(range) = state.selection_range().filter(|range| !range.collapsed()) else {
+        return;
+    };
+    let blocks = state.selected_blocks();
+    let parts = selected_text(&range);
+    if parts.is_empty() {
+        return;
+    }
+    let marked = |text: &Text| marked_ancestor(state, text.clone().into(), selectors);
+    let texts: Vec<Text> = parts
+        .into_iter()
+        .map(|(text, start, end)| isolate(text, start, end))
+        .collect();
+    let document = window().unwrap().document().unwrap();
+    for text in &texts {
+        while let Some(mark) = marked(text).filter(&replace) {
+            split_around(&mark, text);
+        }
+        if let Some(create) = create.filter(|_| marked(text).is_none()) {
+            let mark = create(&document);
+            let parent = text.parent_node().unwrap();
+            parent.insert_before(&mark, Some(text)).unwrap();
+            mark.append_child(text).unwrap();
+        }
+    }
+    let blocks: Vec<Element> = blocks
+        .into_iter()
+        .filter_map(|block| block.dyn_into().ok())
+        .collect();
+    for block in &blocks {
+        merge_marks(block, selectors);
+    }
+    // Selected before the text nodes are merged, which moves the selection along.
+    let (first, last) = (&texts[0], &texts[texts.len() - 1]);
+    range.set_start(first, 0).unwrap();
+    range.set_end(last, last.length()).unwrap();
+    state.select_range(&range);
+    for block in &blocks {
+        block.normalize();
+    }
+}
+
+/// The parts of the text nodes the range covers, as `(node, start, end)` offsets, leaving
+/// out blank ones such as the whitespace between blocks.
+fn selected_text(range: &Range) -> Vec<(Text, u32, u32)> {
+    fn walk(node: &Node, range: &Range, parts: &mut Vec<(Text, u32, u32)>) {
+        if !range.intersects_node(node).unwrap_or(false) {
+            return;
+        }
+        let Some(text) = node.dyn_ref::<Text>() else {
+            let children = node.child_nodes();
+            for child in (0..children.length()).filter_map(|i| children.get(i)) {
+                walk(&child, range, parts);
+            }
+            return;
+        };
+        let start = match range.start_container().ok().as_ref() == Some(node) {
+            true => range.start_offset().unwrap(),
+            false => 0,
+        };
+        let end = match range.end_container().ok().as_ref() == Some(node) {
+            true => range.end_offset().unwrap(),
+            false => text.length(),
+        };
+        if !utf16_slice(&text.data(), start, end).trim().is_empty() {
+            parts.push((text.clone(), start, end));
+        }
+    }
+    let mut parts = vec![];
+    if let Ok(root) = range.common_ancestor_container() {
+        walk(&root, range, &mut parts);
+    }
+    parts
+}
+
+/// The part `start..end` of `text`, in UTF-16 code units as DOM offsets count them, or
+/// nothing if it is out of bounds.
+fn utf16_slice(text: &str, start: u32, end: u32) -> String {
+    let units: Vec<u16> = text.encode_utf16().collect();
+    let part = units.get(start as usize..end as usize).unwrap_or_default();
+    String::from_utf16_lossy(part)
+}
+
+/// Splits the part `start..end` of a text node off into a node of its own.
+fn isolate(text: Text, start: u32, end: u32) -> Text {
+    if end < text.length() {
+        text.split_text(end).unwrap();
+    }
+    match start > 0 {
+        true => text.split_text(start).unwrap(),
+        false => text,
+    }
+}
+
+/// Takes `node` out of `mark`, the content before and after it staying in copies of the
+/// mark.
+fn split_around(mark: &Element, node: &Node) {
+    let document = window().unwrap().document().unwrap();
+    let range = document.create_range().unwrap();
+    range.set_start(mark, 0).unwrap();
+    range.set_end_before(node).unwrap();
+    let before = range.extract_contents().unwrap();
+    range.set_start_after(node).unwrap();
+    range.set_end(mark, mark.child_nodes().length()).unwrap();
+    let after = range.extract_contents().unwrap();
+    for (fragment, is_before) in [(before, true), (after, false)] {
+        if !fragment.has_child_nodes() {
+            continue;
+        }
+        let copy: Element = mark.clone_node().unwrap().unchecked_into();
+        copy.append_child(&fragment).unwrap();
+        match is_before {
+            true => mark.before_with_node_1(&copy).unwrap(),
+            false => mark.after_with_node_1(&copy).unwrap(),
+        }
+    }
+    unwrap_element(mark);
+}
+
+/// Inside `root`, merges each mark matching `selectors` into an identical mark right before
+/// it or a mark around it, and drops the marks left empty.
+fn merge_marks(root: &Element, selectors: &str) {
+    // The tag and attributes of a mark.
+    let shallow = |element: &Element| {
+        let copy: Element = element.clone_node().unwrap().unchecked_into();
+        copy.outer_html()
+    };
+    let marks = root.query_selector_all(selectors).unwrap();
+    for mark in (0..marks.length()).filter_map(|i| marks.get(i)) {
+        let mark: Element = mark.unchecked_into();
+        if mark.text_content().unwrap_or_default().is_empty() && mark.child_element_count() == 0 {
+            mark.remove();
+            continue;
+        }
+        // A mark without attributes, eg. `<b>` in `<strong>`, adds nothing to the mark
+        // around it, while a coloured one only does when its colour differs.
+        let around = mark
+            .parent_element()
+            .and_then(|parent| parent.closest(selectors).ok().flatten());
+        if around.is_some_and(|around| !mark.has_attributes() || shallow(&around) == shallow(&mark))
+        {
+            unwrap_element(&mark);
+            continue;
+        }
+        let previous = mark
+            .previous_sibling()
+            .and_then(|node| node.dyn_into::<Element>().ok())
+            .filter(|previous| shallow(previous) == shallow(&mark));
+        if let Some(previous) = previous {
+            while let Some(child) = mark.first_child() {
+                previous.append_child(&child).unwrap();
+            }
+            mark.remove();
+        }
+    }
+}
+
+/// Makes the selection subscript, or removes the subscript around it. Superscript is
+/// removed from the selection first, text being either one or the other.
+pub struct ToggleSubscript;
+
+impl Command<EditorState> for ToggleSubscript {
+    fn execute(&self, state: &mut EditorState) {
+        mark_selection(state, "sup", |_| true, None);
+        state.execute(ToggleMark::<Subscript>::new());
+    }
+}
+
+/// Makes the selection superscript, or removes the superscript around it. Subscript is
+/// removed from the selection first, text being either one or the other.
+pub struct ToggleSuperscript;
+
+impl Command<EditorState> for ToggleSuperscript {
+    fn execute(&self, state: &mut EditorState) {
+        mark_selection(state, "sub", |_| true, None);
+        state.execute(ToggleMark::<Superscript>::new());
+    }
+}
+
+/// Highlights the selection, in `color` or the reader's default highlight colour, or
+/// removes the highlight from it when all of it is highlighted already, in `color` if one
+/// is given. Highlights in another colour are replaced rather than nested.
+#[derive(Clone, Debug, Default)]
+pub struct ToggleHighlight {
+    pub color: Option<String>,
+}
+
+impl Command<EditorState> for ToggleHighlight {
+    fn execute(&self, state: &mut EditorState) {
+        let color = self.color.as_deref().and_then(sanitize_color);
+        let same = |mark: &Element| color.is_none() || mark.get_attribute("data-color") == color;
+        if selection_marked(state, "mark", same) {
+            mark_selection(state, "mark", |_| true, None);
+            return;
+        }
+        let create = |document: &Document| {
+            let mark = document.create_element("mark").unwrap();
+            if let Some(color) = &color {
+                mark.set_attribute("data-color", color).unwrap();
+                mark.set_attribute("style", &format!("background-color: {color}"))
+                    .unwrap();
+            }
+            mark
+        };
+        mark_selection(state, "mark", |mark| !same(mark), Some(&create));
+    }
+}
+
+/// The elements giving text a colour, leaving out spans with only a background colour.
+const TEXT_COLOR: &str =
+    "span[style^='color'], span[style*=' color'], span[style*=';color'], font[color]";
+
+/// The colour an element of [`TEXT_COLOR`] gives its text, as the browser writes it.
+fn text_color(element: &Element) -> String {
+    match element.dyn_ref::<HtmlElement>() {
+        Some(element) if element.tag_name() != "FONT" => {
+            element.style().get_property_value("color").unwrap()
+        }
+        _ => element.get_attribute("color").unwrap_or_default(),
+    }
+}
+
+/// Colours the selection, replacing other colours in it, or removes the colour from it
+/// when given `None`.
+///
+/// The colour is skipped when it is not a valid css colour.
+pub struct SetTextColor(pub Option<String>);
+
+impl Command<EditorState> for SetTextColor {
+    fn execute(&self, state: &mut EditorState) {
+        let Some(color) = &self.0 else {
+            mark_selection(state, TEXT_COLOR, |_| true, None);
+            return;
+        };
+        let Some(color) = sanitize_color(color) else {
+            return;
+        };
+        let create = |document: &Document| {
+            let span = document.create_element("span").unwrap();
+            span.set_attribute("style", &format!("color: {color}"))
+                .unwrap();
+            span
+        };
+        let document = window().unwrap().document().unwrap();
+        let wanted = text_color(&create(&document));
+        mark_selection(
+            state,
+            TEXT_COLOR,
+            |span| text_color(span) != wanted,
+            Some(&create),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_utf16_slice() {
+        // The emoji takes two code units, the accented letter one.
+        let text = "a😀é b";
+        assert_eq!(utf16_slice(text, 1, 4), "😀é");
+        assert_eq!(utf16_slice(text, 4, 6), " b");
+        assert_eq!(utf16_slice(text, 5, 5), "");
+        assert_eq!(utf16_slice(text, 2, 9), "");
+    }
+}
+
+#[cfg(all(test, target_arch = "wasm32"))]
+mod dom_tests {
+    use super::*;
+    use crate::nodes::Bold;
+    use crate::testing::{content, editor, select};
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    fn bold(state: &EditorState) {
+        state.execute(ToggleMark::<Bold>::new());
+    }
+
+    #[wasm_bindgen_test]
+    fn test_bolding_twice_does_not_nest() {
+        let state = editor("<p>hello world</p>");
+        select(&state, "world", "world");
+        bold(&state);
+        assert_eq!(content(&state), "<p>hello <b>world</b></p>");
+        select(&state, "hello", "world");
+        assert!(!ToggleMark::<Bold>::is_active(&state));
+        bold(&state);
+        assert_eq!(content(&state), "<p><b>hello world</b></p>");
+        assert!(ToggleMark::<Bold>::is_active(&state));
+        bold(&state);
+        assert_eq!(content(&state), "<p>hello world</p>");
+    }
+
+    #[wasm_bindgen_test]
+    fn test_unbolding_part_of_a_mark_splits_it() {
+        let state = editor("<p><b>hello world</b></p>");
+        select(&state, "lo wo", "lo wo");
+        bold(&state);
+        assert_eq!(content(&state), "<p><b>hel</b>lo wo<b>rld</b></p>");
+    }
+
+    #[wasm_bindgen_test]
+    fn test_bolding_across_blocks() {
+        let state = editor("<p>one</p><p>two</p>");
+        select(&state, "ne", "tw");
+        bold(&state);
+        assert_eq!(content(&state), "<p>o<b>ne</b></p><p><b>tw</b>o</p>");
+    }
+
+    #[wasm_bindgen_test]
+    fn test_adjacent_marks_are_merged() {
+        let state = editor("<p><b>a</b>b<b>c</b></p>");
+        select(&state, "b", "b");
+        bold(&state);
+        assert_eq!(content(&state), "<p><b>abc</b></p>");
+    }
+
+    #[wasm_bindgen_test]
+    fn test_highlighting_in_another_colour_replaces_it() {
+        let state = editor(
+            r#"<p><mark data-color="red" style="background-color: red">hello world</mark></p>"#,
+        );
+        let highlight = |color: &str| ToggleHighlight {
+            color: Some(color.to_owned()),
+        };
+        select(&state, "world", "world");
+        state.execute(highlight("blue"));
+        assert_eq!(
+            content(&state),
+            r#"<p><mark data-color="red" style="background-color: red">hello </mark><mark data-color="blue" style="background-color: blue">world</mark></p>"#
+        );
+        select(&state, "hello", "world");
+        state.execute(highlight("blue"));
+        assert_eq!(
+            content(&state),
+            r#"<p><mark data-color="blue" style="background-color: blue">hello world</mark></p>"#
+        );
+        state.execute(highlight("blue"));
+        assert_eq!(content(&state), "<p>hello world</p>");
+    }
+
+    #[wasm_bindgen_test]
+    fn test_recolouring_text_replaces_its_colour() {
+        let state = editor(r#"<p><span style="color: red">hello world</span></p>"#);
+        select(&state, "hello", "world");
+        state.execute(SetTextColor(Some("blue".to_owned())));
+        assert_eq!(
+            content(&state),
+            r#"<p><span style="color: blue">hello world</span></p>"#
+        );
+        select(&state, "world", "world");
+        state.execute(SetTextColor(None));
+        assert_eq!(
+            content(&state),
+            r#"<p><span style="color: blue">hello </span>world</p>"#
+        );
+    }
+}
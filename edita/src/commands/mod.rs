@@ -1 +1 @@
-pub mod bold;
\ No newline at end of file
+pub mod marks;
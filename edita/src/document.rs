@@ -0,0 +1,347 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::nodes::EditorNode;
+
+/// The version of the document format produced by this release.
+///
+/// Bump this whenever the serialized shape of an existing node changes and
+/// register a [`Migration`] from the previous version in [`Migrations::default`].
+pub const FORMAT_VERSION: u32 = 3;
+
+/// Schemas of the previous format versions, frozen when the version was bumped.
+const FROZEN_SCHEMAS: &[(u32, &str)] = &[
+    (1, include_str!("../schemas/v1.json")),
+    (2, include_str!("../schemas/v2.json")),
+];
+
+/// A versioned envelope around the editor's nodes.
+#[derive(Clone, Serialize, Deserialize, JsonSchema, Debug)]
+pub struct Document {
+    /// The format version the content is written in.
+    pub version: u32,
+    /// The names of all the node types used in the content.
+    pub node_types: BTreeSet<String>,
+    pub content: Vec<EditorNode>,
+}
+
+impl Document {
+    /// Wraps nodes in an envelope of the current format version.
+    pub fn new(content: Vec<EditorNode>) -> Self {
+        let mut node_types = BTreeSet::new();
+        for node in &content {
+            node.collect_node_types(&mut node_types);
+        }
+        Document {
+            version: FORMAT_VERSION,
+            node_types,
+            content,
+        }
+    }
+
+    pub fn to_json(&self) -> Result<String, DocumentError> {
+        Ok(serde_json::to_string(self)?)
+    }
+
+    /// Parses a document, upgrading it with the default migrations if needed.
+    pub fn from_json(json: &str) -> Result<Self, DocumentError> {
+        Self::from_value(serde_json::from_str(json)?, &Migrations::default())
+    }
+
+    /// Loads a document from a json value, upgrading older versions step by step.
+    ///
+    /// A bare array of nodes, as produced by serializing [`EditorExt::export`](crate::EditorExt::export)
+    /// directly, is treated as version 1 content.
+    pub fn from_value(value: Value, migrations: &Migrations) -> Result<Self, DocumentError> {
+        let (mut version, mut content) = match value {
+            Value::Array(_) => (1, value),
+            Value::Object(mut envelope) => {
+                let version = envelope
+                    .get("version")
+                    .and_then(Value::as_u64)
+                    .ok_or(DocumentError::MissingVersion)?;
+                let version = u32::try_from(version).map_err(|_| DocumentError::MissingVersion)?;
+                let content = envelope.remove("content").unwrap_or(Value::Array(vec![]));
+                (version, content)
+            }
+            _ => return Err(DocumentError::MissingVersion),
+        };
+        if version > FORMAT_VERSION {
+            return Err(DocumentError::UnsupportedVersion(version));
+        }
+        while version < FORMAT_VERSION {
+            let migration = migrations
+                .get(version)
+                .ok_or(DocumentError::MissingMigration(version))?;
+            content = migration.migrate(content)?;
+            version += 1;
+        }
+        let content: Vec<EditorNode> = serde_json::from_value(content)?;
+        Ok(Document::new(content))
+    }
+
+    /// Returns the JSON Schema describing documents of a given format version.
+    pub fn json_schema(version: u32) -> Option<Value> {
+        if version == FORMAT_VERSION {
+            return serde_json::to_value(schemars::schema_for!(Document)).ok();
+        }
+        FROZEN_SCHEMAS
+            .iter()
+            .find(|(v, _)| *v == version)
+            .and_then(|(_, schema)| serde_json::from_str(schema).ok())
+    }
+}
+
+/// Upgrades the content of a document from one format version to the next.
+pub trait Migration {
+    /// Receives the `content` array written in version `n` and returns it in version `n + 1`.
+    fn migrate(&self, content: Value) -> Result<Value, DocumentError>;
+}
+
+impl<F> Migration for F
+where
+    F: Fn(Value) -> Result<Value, DocumentError>,
+{
+    fn migrate(&self, content: Value) -> Result<Value, DocumentError> {
+        self(content)
+    }
+}
+
+/// A registry of migrations keyed by the version they upgrade from.
+pub struct Migrations {
+    migrations: BTreeMap<u32, Box<dyn Migration>>,
+}
+
+impl Migrations {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Migrations {
+            migrations: BTreeMap::new(),
+        }
+    }
+
+    /// Registers a migration that upgrades content from version `from` to `from + 1`.
+    pub fn register<M: Migration + 'static>(&mut self, from: u32, migration: M) {
+        self.migrations.insert(from, Box::new(migration));
+    }
+
+    pub fn get(&self, from: u32) -> Option<&dyn Migration> {
+        self.migrations.get(&from).map(|m| m.as_ref())
+    }
+}
+
+impl Default for Migrations {
+    /// The migrations shipped with edita for its own nodes.
+    fn default() -> Self {
+        let mut migrations = Migrations::new();
+        migrations.register(1, list_item_content);
+        migrations.register(2, block_quote_content);
+        migrations
+    }
+}
+
+/// Version 2 replaced the `text` of list items with block `content`, the text becoming a
+/// paragraph.
+fn list_item_content(mut content: Value) -> Result<Value, DocumentError> {
+    fn upgrade(value: &mut Value) -> Result<(), DocumentError> {
+        match value {
+            Value::Array(values) => values.iter_mut().try_for_each(upgrade),
+            Value::Object(object) => object.iter_mut().try_for_each(|(key, value)| {
+                match (key.as_str(), value.get_mut("list")) {
+                    ("ListItem", _) => text_to_content(value, "list item"),
+                    ("BulletList" | "OrderedList", Some(Value::Array(items))) => items
+                        .iter_mut()
+                        .try_for_each(|item| text_to_content(item, "list item")),
+                    _ => upgrade(value),
+                }
+            }),
+            _ => Ok(()),
+        }
+    }
+    upgrade(&mut content)?;
+    Ok(content)
+}
+
+/// Version 3 replaced the `text` of block quotes with block `content`, the text becoming a
+/// paragraph.
+fn block_quote_content(mut content: Value) -> Result<Value, DocumentError> {
+    fn upgrade(value: &mut Value) -> Result<(), DocumentError> {
+        match value {
+            Value::Array(values) => values.iter_mut().try_for_each(upgrade),
+            Value::Object(object) => {
+                object
+                    .iter_mut()
+                    .try_for_each(|(key, value)| match key.as_str() {
+                        "BlockQuote" => text_to_content(value, "block quote"),
+                        _ => upgrade(value),
+                    })
+            }
+            _ => Ok(()),
+        }
+    }
+    upgrade(&mut content)?;
+    Ok(content)
+}
+
+/// Replaces the `text` field of a node with `content` holding the text as a paragraph.
+fn text_to_content(node: &mut Value, name: &str) -> Result<(), DocumentError> {
+    let text = node
+        .as_object_mut()
+        .and_then(|node| node.remove("text"))
+        .ok_or_else(|| DocumentError::Migration(format!("{name} without text")))?;
+    let elements = match text.as_str() {
+        Some("") => json!([]),
+        _ => json!([{ "Text": { "text": text } }]),
+    };
+    node["content"] = json!([{ "Paragraph": { "elements": elements } }]);
+    Ok(())
+}
+
+impl fmt::Debug for Migrations {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Migrations")
+            .field("from", &self.migrations.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+#[derive(Debug)]
+pub enum DocumentError {
+    Json(serde_json::Error),
+    /// The input has no readable `version` field, or it does not fit a `u32`.
+    MissingVersion,
+    /// The document was written by a newer release of edita.
+    UnsupportedVersion(u32),
+    /// No migration is registered to upgrade from this version.
+    MissingMigration(u32),
+    /// A migration could not upgrade the content.
+    Migration(String),
+}
+
+impl fmt::Display for DocumentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DocumentError::Json(e) => write!(f, "invalid document: {e}"),
+            DocumentError::MissingVersion => write!(f, "document has no format version"),
+            DocumentError::UnsupportedVersion(v) => {
+                write!(f, "document version {v} is newer than {FORMAT_VERSION}")
+            }
+            DocumentError::MissingMigration(v) => {
+                write!(f, "no migration registered from version {v}")
+            }
+            DocumentError::Migration(message) => write!(f, "migration failed: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for DocumentError {}
+
+impl From<serde_json::Error> for DocumentError {
+    fn from(value: serde_json::Error) -> Self {
+        DocumentError::Json(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nodes::{Header, Paragraph, TextNode};
+
+    fn sample() -> Vec<EditorNode> {
+        vec![
+            EditorNode::Heading(Header {
+                text: "Title".to_owned(),
+                level: 1,
+            }),
+            EditorNode::Paragraph(Paragraph {
+                elements: vec![EditorNode::Text(TextNode {
+                    text: "Body".to_owned(),
+                })],
+            }),
+        ]
+    }
+
+    #[test]
+    fn test_document_round_trip() {
+        let json = Document::new(sample()).to_json().unwrap();
+        let document = Document::from_json(&json).unwrap();
+        assert_eq!(document.version, FORMAT_VERSION);
+        assert_eq!(
+            document.node_types.into_iter().collect::<Vec<_>>(),
+            vec!["Heading", "Paragraph", "Text"]
+        );
+        assert_eq!(document.content.len(), 2);
+    }
+
+    #[test]
+    fn test_bare_array_is_accepted() {
+        let value = serde_json::to_value(sample()).unwrap();
+        let document = Document::from_value(value, &Migrations::default()).unwrap();
+        assert_eq!(document.content.len(), 2);
+    }
+
+    #[test]
+    fn test_migrations_run_in_order() {
+        let mut migrations = Migrations::default();
+        migrations.register(0, |content: Value| {
+            let mut nodes = content.as_array().cloned().unwrap_or_default();
+            nodes.push(json!({ "Text": { "text": "added" } }));
+            Ok(Value::Array(nodes))
+        });
+        let old = json!({ "version": 0, "content": [] });
+        let document = Document::from_value(old, &migrations).unwrap();
+        assert_eq!(document.content.len(), 1);
+
+        let missing =
+            Document::from_value(json!({ "version": 0, "content": [] }), &Migrations::new());
+        assert!(matches!(missing, Err(DocumentError::MissingMigration(0))));
+    }
+
+    #[test]
+    fn test_list_items_get_content() {
+        let old = json!({ "version": 1, "content": [
+            { "BulletList": { "list": [{ "text": "one" }, { "text": "" }] } },
+            { "Details": { "open": true, "summary": [], "content": [
+                { "OrderedList": { "list": [{ "text": "two" }] } }
+            ] } }
+        ] });
+        let document = Document::from_value(old, &Migrations::default()).unwrap();
+        let text: Vec<_> = document.content.iter().map(EditorNode::text).collect();
+        assert_eq!(text, ["one\n", "\ntwo"]);
+    }
+
+    #[test]
+    fn test_block_quotes_get_content() {
+        let old = json!({ "version": 2, "content": [
+            { "BulletList": { "list": [{ "content": [{ "BlockQuote": { "text": "quoted" } }] }] } }
+        ] });
+        let document = Document::from_value(old, &Migrations::default()).unwrap();
+        assert_eq!(document.content[0].text(), "quoted");
+        assert!(document.node_types.contains("Paragraph"));
+    }
+
+    #[test]
+    fn test_newer_version_is_rejected() {
+        let newer = json!({ "version": FORMAT_VERSION + 1, "content": [] });
+        assert!(matches!(
+            Document::from_value(newer, &Migrations::default()),
+            Err(DocumentError::UnsupportedVersion(_))
+        ));
+        let overflowing = json!({ "version": u64::from(u32::MAX) + 2, "content": [] });
+        assert!(matches!(
+            Document::from_value(overflowing, &Migrations::default()),
+            Err(DocumentError::MissingVersion)
+        ));
+    }
+
+    #[test]
+    fn test_schema_for_every_version() {
+        for version in 1..=FORMAT_VERSION {
+            assert!(Document::json_schema(version).is_some(), "v{version}");
+        }
+    }
+}
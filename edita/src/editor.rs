@@ -6,17 +6,19 @@ use hirola::dom::XEffect;
 use hirola::prelude::*;
 use hirola::signal::LocalBoxSignal;
 use hirola::signal::SignalExt;
-use serde::Serialize;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 use wasm_bindgen::JsCast;
 use web_sys::HtmlElement;
 
-use crate::nodes::EditorNode;
+use crate::document::Document;
+use crate::nodes::{table_of_contents, EditorNode, TocEntry};
 use crate::state::EditorState;
 
 pub struct HtmlBlock;
 
-#[derive(Clone, Serialize, Debug)]
-pub struct HtmlNode(String);
+#[derive(Clone, Serialize, Deserialize, JsonSchema, Debug)]
+pub struct HtmlNode(pub String);
 
 impl crate::nodes::Node for HtmlNode {
     fn render(&self) -> hirola::dom::Dom {
@@ -44,7 +46,21 @@ impl Block for HtmlBlock {
 
 pub trait EditorExt {
     fn export(&self) -> Vec<EditorNode>;
-    fn signal(&self) -> LocalBoxSignal<Vec<EditorNode>>;
+    /// Exports the nodes wrapped in a versioned [`Document`].
+    fn document(&self) -> Document {
+        Document::new(self.export())
+    }
+    fn signal(&self) -> LocalBoxSignal<'_, Vec<EditorNode>>;
+    /// The heading tree of the current content.
+    fn table_of_contents(&self) -> Vec<TocEntry> {
+        table_of_contents(&self.export())
+    }
+    /// The heading tree, updated as the content changes.
+    fn table_of_contents_signal(&self) -> LocalBoxSignal<'_, Vec<TocEntry>> {
+        self.signal()
+            .map(|nodes| table_of_contents(&nodes))
+            .boxed_local()
+    }
 }
 
 impl EditorExt for Editor<EditorNode, EditorState, web_sys::Node> {
@@ -58,7 +74,7 @@ impl EditorExt for Editor<EditorNode, EditorState, web_sys::Node> {
         process_nodes(self, nodes)
     }
 
-    fn signal(&self) -> LocalBoxSignal<Vec<EditorNode>> {
+    fn signal(&self) -> LocalBoxSignal<'_, Vec<EditorNode>> {
         self.notify
             .signal()
             .map(move |_| self.export())
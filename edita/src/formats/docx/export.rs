@@ -0,0 +1,534 @@
+use std::io::{Cursor, Write};
+
+use zip::write::SimpleFileOptions;
+use zip::ZipWriter;
+
+use super::{
+    decode_data_url, escape_xml, ImageFormat, EMU_PER_PIXEL, NS_R, NS_W, NS_W14, TASK_INDENT,
+};
+use crate::editor::HtmlNode;
+use crate::formats::html::text_content;
+use crate::formats::{hex_color, inline_parts, Mark};
+use crate::nodes::{
+    BlockQuote, EditorNode, Highlight, Image, Link, ListItem, NumberingStyle, Table, TaskItem,
+    TextColor,
+};
+
+/// The widest an image may be on an A4 page with default margins, in EMUs.
+const MAX_IMAGE_WIDTH: u64 = 6 * 914400;
+
+/// Fetches the bytes of an image from its `src`.
+///
+/// Exporting never touches the network on its own: images whose bytes cannot be
+/// loaded are written as their alt text.
+pub trait AssetLoader {
+    fn load(&self, src: &str) -> Option<Vec<u8>>;
+}
+
+impl<F> AssetLoader for F
+where
+    F: Fn(&str) -> Option<Vec<u8>>,
+{
+    fn load(&self, src: &str) -> Option<Vec<u8>> {
+        self(src)
+    }
+}
+
+/// Writes nodes to a .docx file, embedding images given as `data:` urls.
+pub fn to_docx(nodes: &[EditorNode]) -> std::io::Result<Vec<u8>> {
+    to_docx_with(nodes, &decode_data_url)
+}
+
+/// Writes nodes to a .docx file, loading image bytes through `loader`.
+pub fn to_docx_with(nodes: &[EditorNode], loader: &dyn AssetLoader) -> std::io::Result<Vec<u8>> {
+    let mut writer = DocxWriter {
+        loader,
+        body: String::new(),
+        media: vec![],
+        ordered_lists: vec![],
+        links: vec![],
+    };
+    for node in nodes {
+        writer.block(node);
+    }
+    writer.finish()
+}
+
+struct Media {
+    name: String,
+    format: ImageFormat,
+    bytes: Vec<u8>,
+}
+
+struct DocxWriter<'a> {
+    loader: &'a dyn AssetLoader,
+    body: String,
+    media: Vec<Media>,
+    /// Every ordered list gets its own numbering instance, with its style and first
+    /// number, so that it restarts at its start.
+    ordered_lists: Vec<(NumberingStyle, i64)>,
+    /// The targets of external links, each getting a relationship of its own.
+    links: Vec<String>,
+}
+
+impl DocxWriter<'_> {
+    fn paragraph(&mut self, properties: &str, runs: &str) {
+        self.body.push_str("<w:p>");
+        if !properties.is_empty() {
+            self.body.push_str(&format!("<w:pPr>{properties}</w:pPr>"));
+        }
+        self.body.push_str(runs);
+        self.body.push_str("</w:p>");
+    }
+
+    fn block(&mut self, node: &EditorNode) {
+        match node {
+            EditorNode::HorizontalRule(_) => self.paragraph(
+                r#"<w:pBdr><w:bottom w:val="single" w:sz="6" w:space="1" w:color="auto"/></w:pBdr>"#,
+                "",
+            ),
+            EditorNode::Html(HtmlNode(html)) => self.paragraph("", &run(&text_content(html), None)),
+            EditorNode::Paragraph(paragraph) => {
+                let runs = self.runs(&paragraph.elements);
+                self.paragraph("", &runs)
+            }
+            EditorNode::Text(_)
+            | EditorNode::Bold(_)
+            | EditorNode::Italic(_)
+            | EditorNode::InlineCode(_)
+            | EditorNode::Underline(_)
+            | EditorNode::Strike(_)
+            | EditorNode::Subscript(_)
+            | EditorNode::Superscript(_)
+            | EditorNode::Highlight(_)
+            | EditorNode::TextColor(_)
+            | EditorNode::Link(_)
+            | EditorNode::Emoji(_)
+            | EditorNode::Mention(_)
+            | EditorNode::HardBreak(_) => {
+                let runs = self.runs(std::slice::from_ref(node));
+                self.paragraph("", &runs)
+            }
+            EditorNode::Heading(header) => self.paragraph(
+                &format!(r#"<w:pStyle w:val="Heading{}"/>"#, header.level.clamp(1, 6)),
+                &run(&header.text, None),
+            ),
+            EditorNode::CodeBlock(block) => self.paragraph(
+                r#"<w:pStyle w:val="SourceCode"/>"#,
+                &run(block.code.trim_end_matches('\n'), None),
+            ),
+            EditorNode::Image(image) => {
+                let runs = self.image(image);
+                self.paragraph("", &runs)
+            }
+            EditorNode::BlockQuote(quote) => self.quote(quote),
+            EditorNode::ListItem(item) => self.list_item(BULLET_NUM_ID, item, 0),
+            EditorNode::BulletList(_) | EditorNode::OrderedList(_) => self.list(node, 0),
+            EditorNode::TaskItem(item) => self.task(item, 0),
+            EditorNode::TaskList(list) => {
+                for item in &list.list {
+                    self.task(item, 0);
+                }
+            }
+            EditorNode::Table(table) => self.table(table),
+            // Word has no collapsible blocks, the summary becomes a bold line above the body.
+            EditorNode::Details(details) => {
+                let summary: String = details.summary.iter().map(EditorNode::text).collect();
+                self.paragraph("", &run(&summary, Some(Mark::Bold)));
+                for node in &details.content {
+                    self.block(node);
+                }
+            }
+        }
+    }
+
+    /// Writes the paragraphs of a quote in the `Quote` style. Word cannot nest quotes, so
+    /// nested quotes are flattened, and other blocks are written as they are.
+    fn quote(&mut self, quote: &BlockQuote) {
+        for node in &quote.content {
+            match node {
+                EditorNode::Paragraph(paragraph) => {
+                    let runs = self.runs(&paragraph.elements);
+                    self.paragraph(r#"<w:pStyle w:val="Quote"/>"#, &runs)
+                }
+                EditorNode::BlockQuote(nested) => self.quote(nested),
+                node => self.block(node),
+            }
+        }
+    }
+
+    /// Writes a table, row spans becoming `vMerge` cells in the rows they cover.
+    fn table(&mut self, table: &Table) {
+        let grid = table.grid();
+        let width = grid.first().map_or(0, Vec::len);
+        let headers = table.rows.iter().take_while(|row| row.is_header()).count();
+        self.body.push_str(
+            r#"<w:tbl><w:tblPr><w:tblStyle w:val="TableGrid"/><w:tblW w:w="0" w:type="auto"/></w:tblPr><w:tblGrid>"#,
+        );
+        for _ in 0..width {
+            self.body.push_str("<w:gridCol/>");
+        }
+        self.body.push_str("</w:tblGrid>");
+        for (r, slots) in grid.iter().enumerate() {
+            self.body.push_str("<w:tr>");
+            if r < headers {
+                self.body.push_str("<w:trPr><w:tblHeader/></w:trPr>");
+            }
+            for (c, slot) in slots.iter().enumerate() {
+                let Some((row, i)) = *slot else {
+                    self.body.push_str("<w:tc><w:p/></w:tc>");
+                    continue;
+                };
+                let Some((top, left)) = table.slot((row, i)) else {
+                    continue;
+                };
+                if left != c {
+                    continue;
+                }
+                let cell = &table.rows[row].cells[i];
+                let mut properties = String::new();
+                if cell.colspan > 1 {
+                    properties.push_str(&format!(r#"<w:gridSpan w:val="{}"/>"#, cell.colspan));
+                }
+                if top < r {
+                    properties.push_str("<w:vMerge/>");
+                } else if cell.rowspan > 1 {
+                    properties.push_str(r#"<w:vMerge w:val="restart"/>"#);
+                }
+                self.body.push_str("<w:tc>");
+                if !properties.is_empty() {
+                    self.body
+                        .push_str(&format!("<w:tcPr>{properties}</w:tcPr>"));
+                }
+                let start = self.body.len();
+                if top == r {
+                    for node in &cell.content {
+                        self.block(node);
+                    }
+                }
+                // Word requires every cell to end with a paragraph.
+                if !self.body[start..].ends_with("</w:p>") {
+                    self.body.push_str("<w:p/>");
+                }
+                self.body.push_str("</w:tc>");
+            }
+            self.body.push_str("</w:tr>");
+        }
+        self.body.push_str("</w:tbl>");
+    }
+
+    fn runs(&mut self, nodes: &[EditorNode]) -> String {
+        let mut runs = String::new();
+        for node in nodes {
+            match (inline_parts(node), node) {
+                (Some((text, mark)), _) => runs.push_str(&run(text, mark)),
+                (None, EditorNode::HardBreak(_)) => runs.push_str("<w:r><w:br/></w:r>"),
+                (None, EditorNode::Html(HtmlNode(html))) => {
+                    runs.push_str(&run(&text_content(html), None))
+                }
+                (None, EditorNode::Image(image)) => runs.push_str(&self.image(image)),
+                (None, EditorNode::Link(link)) => runs.push_str(&self.link(link)),
+                // Word only highlights in a few named colours, so other colours shade the
+                // run instead. Colours that are not hex codes fall back to the default.
+                (None, EditorNode::Highlight(Highlight { text, color })) => {
+                    let properties = match color.as_deref().and_then(hex_color) {
+                        Some(fill) => {
+                            format!(r#"<w:shd w:val="clear" w:color="auto" w:fill="{fill}"/>"#)
+                        }
+                        None => r#"<w:highlight w:val="yellow"/>"#.to_owned(),
+                    };
+                    runs.push_str(&styled_run(text, &properties))
+                }
+                (None, EditorNode::TextColor(TextColor { text, color })) => {
+                    let properties = hex_color(color)
+                        .map(|color| format!(r#"<w:color w:val="{color}"/>"#))
+                        .unwrap_or_default();
+                    runs.push_str(&styled_run(text, &properties))
+                }
+                (None, other) => runs.push_str(&run(&other.text(), None)),
+            }
+        }
+        runs
+    }
+
+    /// Writes a link as a hyperlink, to a bookmark when its href is a `#fragment` and to
+    /// an external relationship otherwise.
+    fn link(&mut self, link: &Link) -> String {
+        let target = match link.href.strip_prefix('#') {
+            Some(anchor) => format!(r#"w:anchor="{}""#, escape_xml(anchor)),
+            None => {
+                self.links.push(link.href.clone());
+                format!(r#"r:id="rIdLink{}""#, self.links.len())
+            }
+        };
+        let tooltip = match link.title.as_str() {
+            "" => String::new(),
+            title => format!(r#" w:tooltip="{}""#, escape_xml(title)),
+        };
+        let run = styled_run(&link.text, r#"<w:rStyle w:val="Hyperlink"/>"#);
+        format!("<w:hyperlink {target}{tooltip}>{run}</w:hyperlink>")
+    }
+
+    /// Writes a list at a nesting `depth`, each ordered list getting a numbering instance
+    /// of its own so that it counts from its start. Word lists cannot count down, so
+    /// reversed lists are written counting up from their first number.
+    fn list(&mut self, list: &EditorNode, depth: usize) {
+        let (num_id, items) = match list {
+            EditorNode::BulletList(list) => (BULLET_NUM_ID, &list.list),
+            EditorNode::OrderedList(list) => {
+                self.ordered_lists.push((list.style, list.first_number()));
+                (
+                    FIRST_ORDERED_NUM_ID + self.ordered_lists.len() - 1,
+                    &list.list,
+                )
+            }
+            _ => return,
+        };
+        for item in items {
+            self.list_item(num_id, item, depth);
+        }
+    }
+
+    fn list_item(&mut self, num_id: usize, item: &ListItem, depth: usize) {
+        let runs = self.runs(&item.inline());
+        self.paragraph(
+            &format!(
+                r#"<w:pStyle w:val="ListParagraph"/><w:numPr><w:ilvl w:val="{depth}"/><w:numId w:val="{num_id}"/></w:numPr>"#
+            ),
+            &runs,
+        );
+        for list in item.sublists() {
+            self.list(list, depth + 1);
+        }
+    }
+
+    /// Writes a task and its subtasks, which are indented by [`TASK_INDENT`] per level.
+    fn task(&mut self, item: &TaskItem, depth: u32) {
+        let (checked, glyph) = if item.checked {
+            ("1", "\u{2612}")
+        } else {
+            ("0", "\u{2610}")
+        };
+        let checkbox = format!(
+            r#"<w:sdt><w:sdtPr><w14:checkbox><w14:checked w14:val="{checked}"/><w14:checkedState w14:val="2612" w14:font="MS Gothic"/><w14:uncheckedState w14:val="2610" w14:font="MS Gothic"/></w14:checkbox></w:sdtPr><w:sdtContent><w:r><w:rPr><w:rFonts w:ascii="MS Gothic" w:hAnsi="MS Gothic" w:eastAsia="MS Gothic"/></w:rPr><w:t>{glyph}</w:t></w:r></w:sdtContent></w:sdt>"#
+        );
+        let properties = match depth {
+            0 => String::new(),
+            depth => format!(r#"<w:ind w:left="{}"/>"#, TASK_INDENT * depth),
+        };
+        self.paragraph(
+            &properties,
+            &format!("{checkbox}{}", run(&format!(" {}", item.text), None)),
+        );
+        for child in &item.children {
+            self.task(child, depth + 1);
+        }
+    }
+
+    fn image(&mut self, image: &Image) -> String {
+        let loaded = self
+            .loader
+            .load(&image.src)
+            .and_then(|bytes| Some((ImageFormat::sniff(&bytes)?, bytes)));
+        let Some((format, bytes)) = loaded else {
+            return run(&image.alt, Some(Mark::Italic));
+        };
+        let (width, height) = format.dimensions(&bytes).unwrap_or((400, 300));
+        let mut cx = width as u64 * EMU_PER_PIXEL;
+        let mut cy = height as u64 * EMU_PER_PIXEL;
+        if cx > MAX_IMAGE_WIDTH {
+            cy = cy * MAX_IMAGE_WIDTH / cx;
+            cx = MAX_IMAGE_WIDTH;
+        }
+        let id = self.media.len() + 1;
+        let name = format!("image{id}.{}", format.extension);
+        let alt = escape_xml(&image.alt);
+        self.media.push(Media {
+            name: name.clone(),
+            format,
+            bytes,
+        });
+        format!(
+            r#"<w:r><w:drawing><wp:inline distT="0" distB="0" distL="0" distR="0"><wp:extent cx="{cx}" cy="{cy}"/><wp:docPr id="{id}" name="Picture {id}" descr="{alt}"/><a:graphic><a:graphicData uri="http://schemas.openxmlformats.org/drawingml/2006/picture"><pic:pic><pic:nvPicPr><pic:cNvPr id="{id}" name="{name}" descr="{alt}"/><pic:cNvPicPr/></pic:nvPicPr><pic:blipFill><a:blip r:embed="rIdImage{id}"/><a:stretch><a:fillRect/></a:stretch></pic:blipFill><pic:spPr><a:xfrm><a:off x="0" y="0"/><a:ext cx="{cx}" cy="{cy}"/></a:xfrm><a:prstGeom prst="rect"><a:avLst/></a:prstGeom></pic:spPr></pic:pic></a:graphicData></a:graphic></wp:inline></w:drawing></w:r>"#
+        )
+    }
+
+    fn finish(self) -> std::io::Result<Vec<u8>> {
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        let options = SimpleFileOptions::default();
+
+        let mut content_types = String::from(
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?><Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types"><Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/><Default Extension="xml" ContentType="application/xml"/>"#,
+        );
+        let mut formats: Vec<ImageFormat> = vec![];
+        for media in &self.media {
+            if !formats.contains(&media.format) {
+                formats.push(media.format);
+            }
+        }
+        for format in formats {
+            content_types.push_str(&format!(
+                r#"<Default Extension="{}" ContentType="{}"/>"#,
+                format.extension, format.content_type
+            ));
+        }
+        content_types.push_str(r#"<Override PartName="/word/document.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.document.main+xml"/><Override PartName="/word/styles.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.styles+xml"/><Override PartName="/word/numbering.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.numbering+xml"/></Types>"#);
+        zip.start_file("[Content_Types].xml", options)?;
+        zip.write_all(content_types.as_bytes())?;
+
+        zip.start_file("_rels/.rels", options)?;
+        zip.write_all(br#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?><Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="word/document.xml"/></Relationships>"#)?;
+
+        let mut relationships = String::from(
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?><Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rIdStyles" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/styles" Target="styles.xml"/><Relationship Id="rIdNumbering" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/numbering" Target="numbering.xml"/>"#,
+        );
+        for (index, media) in self.media.iter().enumerate() {
+            relationships.push_str(&format!(
+                r#"<Relationship Id="rIdImage{}" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/image" Target="media/{}"/>"#,
+                index + 1,
+                media.name
+            ));
+        }
+        for (index, target) in self.links.iter().enumerate() {
+            relationships.push_str(&format!(
+                r#"<Relationship Id="rIdLink{}" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/hyperlink" Target="{}" TargetMode="External"/>"#,
+                index + 1,
+                escape_xml(target)
+            ));
+        }
+        relationships.push_str("</Relationships>");
+        zip.start_file("word/_rels/document.xml.rels", options)?;
+        zip.write_all(relationships.as_bytes())?;
+
+        zip.start_file("word/document.xml", options)?;
+        zip.write_all(format!(
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?><w:document xmlns:w="{NS_W}" xmlns:r="{NS_R}" xmlns:w14="{NS_W14}" xmlns:mc="http://schemas.openxmlformats.org/markup-compatibility/2006" xmlns:wp="http://schemas.openxmlformats.org/drawingml/2006/wordprocessingDrawing" xmlns:a="http://schemas.openxmlformats.org/drawingml/2006/main" xmlns:pic="http://schemas.openxmlformats.org/drawingml/2006/picture" mc:Ignorable="w14"><w:body>{}<w:sectPr><w:pgSz w:w="11906" w:h="16838"/><w:pgMar w:top="1440" w:right="1440" w:bottom="1440" w:left="1440" w:header="708" w:footer="708" w:gutter="0"/></w:sectPr></w:body></w:document>"#,
+            self.body
+        ).as_bytes())?;
+
+        zip.start_file("word/styles.xml", options)?;
+        zip.write_all(styles().as_bytes())?;
+
+        zip.start_file("word/numbering.xml", options)?;
+        zip.write_all(numbering(&self.ordered_lists).as_bytes())?;
+
+        for media in &self.media {
+            zip.start_file(format!("word/media/{}", media.name), options)?;
+            zip.write_all(&media.bytes)?;
+        }
+        Ok(zip.finish()?.into_inner())
+    }
+}
+
+const BULLET_NUM_ID: usize = 1;
+const FIRST_ORDERED_NUM_ID: usize = 2;
+
+fn run(text: &str, mark: Option<Mark>) -> String {
+    let properties = match mark {
+        Some(Mark::Bold) => "<w:b/>",
+        Some(Mark::Italic) => "<w:i/>",
+        Some(Mark::Code) => r#"<w:rStyle w:val="CodeChar"/>"#,
+        Some(Mark::Underline) => r#"<w:u w:val="single"/>"#,
+        Some(Mark::Strike) => "<w:strike/>",
+        Some(Mark::Subscript) => r#"<w:vertAlign w:val="subscript"/>"#,
+        Some(Mark::Superscript) => r#"<w:vertAlign w:val="superscript"/>"#,
+        Some(Mark::Highlight) => r#"<w:highlight w:val="yellow"/>"#,
+        None => "",
+    };
+    styled_run(text, properties)
+}
+
+/// Writes text as a run with the given run `properties`.
+fn styled_run(text: &str, properties: &str) -> String {
+    if text.is_empty() {
+        return String::new();
+    }
+    let mut run = String::from("<w:r>");
+    if !properties.is_empty() {
+        run.push_str(&format!("<w:rPr>{properties}</w:rPr>"));
+    }
+    for (index, line) in text.split('\n').enumerate() {
+        if index > 0 {
+            run.push_str("<w:br/>");
+        }
+        run.push_str(&format!(
+            r#"<w:t xml:space="preserve">{}</w:t>"#,
+            escape_xml(line)
+        ));
+    }
+    run.push_str("</w:r>");
+    run
+}
+
+fn styles() -> String {
+    let mut styles = format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?><w:styles xmlns:w="{NS_W}"><w:docDefaults><w:rPrDefault><w:rPr><w:rFonts w:ascii="Calibri" w:hAnsi="Calibri" w:eastAsia="Calibri" w:cs="Calibri"/><w:sz w:val="22"/></w:rPr></w:rPrDefault><w:pPrDefault><w:pPr><w:spacing w:after="160" w:line="259" w:lineRule="auto"/></w:pPr></w:pPrDefault></w:docDefaults><w:style w:type="paragraph" w:default="1" w:styleId="Normal"><w:name w:val="Normal"/><w:qFormat/></w:style>"#
+    );
+    let sizes = [32, 28, 26, 24, 22, 22];
+    for (index, size) in sizes.iter().enumerate() {
+        let level = index + 1;
+        styles.push_str(&format!(
+            r#"<w:style w:type="paragraph" w:styleId="Heading{level}"><w:name w:val="heading {level}"/><w:basedOn w:val="Normal"/><w:next w:val="Normal"/><w:qFormat/><w:pPr><w:keepNext/><w:spacing w:before="240" w:after="60"/><w:outlineLvl w:val="{index}"/></w:pPr><w:rPr><w:b/><w:sz w:val="{size}"/></w:rPr></w:style>"#
+        ));
+    }
+    styles.push_str(r#"<w:style w:type="paragraph" w:styleId="Quote"><w:name w:val="Quote"/><w:basedOn w:val="Normal"/><w:next w:val="Normal"/><w:qFormat/><w:pPr><w:ind w:left="864" w:right="864"/></w:pPr><w:rPr><w:i/><w:color w:val="404040"/></w:rPr></w:style><w:style w:type="paragraph" w:styleId="ListParagraph"><w:name w:val="List Paragraph"/><w:basedOn w:val="Normal"/><w:qFormat/><w:pPr><w:ind w:left="720"/><w:contextualSpacing/></w:pPr></w:style><w:style w:type="paragraph" w:styleId="SourceCode"><w:name w:val="Source Code"/><w:basedOn w:val="Normal"/><w:qFormat/><w:pPr><w:spacing w:after="0" w:line="240" w:lineRule="auto"/><w:shd w:val="clear" w:color="auto" w:fill="F2F2F2"/></w:pPr><w:rPr><w:rFonts w:ascii="Courier New" w:hAnsi="Courier New" w:cs="Courier New"/></w:rPr></w:style><w:style w:type="character" w:styleId="CodeChar"><w:name w:val="Code Char"/><w:rPr><w:rFonts w:ascii="Courier New" w:hAnsi="Courier New" w:cs="Courier New"/><w:shd w:val="clear" w:color="auto" w:fill="F2F2F2"/></w:rPr></w:style><w:style w:type="character" w:styleId="Hyperlink"><w:name w:val="Hyperlink"/><w:rPr><w:color w:val="0563C1"/><w:u w:val="single"/></w:rPr></w:style><w:style w:type="table" w:styleId="TableGrid"><w:name w:val="Table Grid"/><w:tblPr><w:tblBorders><w:top w:val="single" w:sz="4" w:space="0" w:color="auto"/><w:left w:val="single" w:sz="4" w:space="0" w:color="auto"/><w:bottom w:val="single" w:sz="4" w:space="0" w:color="auto"/><w:right w:val="single" w:sz="4" w:space="0" w:color="auto"/><w:insideH w:val="single" w:sz="4" w:space="0" w:color="auto"/><w:insideV w:val="single" w:sz="4" w:space="0" w:color="auto"/></w:tblBorders><w:tblCellMar><w:left w:w="108" w:type="dxa"/><w:right w:w="108" w:type="dxa"/></w:tblCellMar></w:tblPr></w:style></w:styles>"#);
+    styles
+}
+
+/// The Word number format of a numbering style.
+fn num_fmt(style: NumberingStyle) -> &'static str {
+    match style {
+        NumberingStyle::Decimal => "decimal",
+        NumberingStyle::LowerAlpha => "lowerLetter",
+        NumberingStyle::UpperAlpha => "upperLetter",
+        NumberingStyle::LowerRoman => "lowerRoman",
+        NumberingStyle::UpperRoman => "upperRoman",
+    }
+}
+
+fn numbering(ordered_lists: &[(NumberingStyle, i64)]) -> String {
+    // Nested lists use the deeper levels, indented a step further each.
+    let levels = |format: &str, text: &dyn Fn(usize) -> String| {
+        (0..9)
+            .map(|ilvl| {
+                format!(
+                    r#"<w:lvl w:ilvl="{ilvl}"><w:start w:val="1"/><w:numFmt w:val="{format}"/><w:lvlText w:val="{}"/><w:lvlJc w:val="left"/><w:pPr><w:ind w:left="{}" w:hanging="360"/></w:pPr></w:lvl>"#,
+                    text(ilvl),
+                    720 * (ilvl + 1)
+                )
+            })
+            .collect::<String>()
+    };
+    let mut numbering = format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?><w:numbering xmlns:w="{NS_W}"><w:abstractNum w:abstractNumId="0"><w:multiLevelType w:val="hybridMultilevel"/>{}</w:abstractNum>"#,
+        levels("bullet", &|_| "\u{2022}".to_owned()),
+    );
+    // Abstract numberings 1 and up hold the format of each ordered list, in order.
+    for (index, (style, _)) in ordered_lists.iter().enumerate() {
+        numbering.push_str(&format!(
+            r#"<w:abstractNum w:abstractNumId="{}"><w:multiLevelType w:val="hybridMultilevel"/>{}</w:abstractNum>"#,
+            index + 1,
+            levels(num_fmt(*style), &|ilvl| format!("%{}.", ilvl + 1)),
+        ));
+    }
+    numbering.push_str(&format!(
+        r#"<w:num w:numId="{BULLET_NUM_ID}"><w:abstractNumId w:val="0"/></w:num>"#
+    ));
+    for (index, (_, start)) in ordered_lists.iter().enumerate() {
+        let restart: String = (0..9)
+            .map(|ilvl| {
+                format!(
+                    r#"<w:lvlOverride w:ilvl="{ilvl}"><w:startOverride w:val="{}"/></w:lvlOverride>"#,
+                    start.max(&0)
+                )
+            })
+            .collect();
+        numbering.push_str(&format!(
+            r#"<w:num w:numId="{}"><w:abstractNumId w:val="{}"/>{restart}</w:num>"#,
+            FIRST_ORDERED_NUM_ID + index,
+            index + 1
+        ));
+    }
+    numbering.push_str("</w:numbering>");
+    numbering
+}
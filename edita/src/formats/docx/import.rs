@@ -0,0 +1,824 @@
+use std::collections::HashMap;
+use std::io::{Cursor, Read};
+
+use base64::Engine;
+use roxmltree::{Document, Node};
+use zip::ZipArchive;
+
+use super::{ImageFormat, NS_R, NS_W, NS_W14, TASK_INDENT};
+use crate::formats::{inline_parts, ImportError, Mark};
+use crate::nodes::{
+    sanitize_color, sanitize_href, BlockQuote, BulletList, CodeBlock, EditorNode, HardBreak,
+    Header, Highlight, Image, Link, ListItem, NumberingStyle, OrderedList, Paragraph, Table,
+    TableCell, TableRow, TaskItem, TaskList, TextColor,
+};
+
+const NS_A: &str = "http://schemas.openxmlformats.org/drawingml/2006/main";
+const NS_WP: &str = "http://schemas.openxmlformats.org/drawingml/2006/wordprocessingDrawing";
+
+const CHECKED: char = '\u{2612}';
+const UNCHECKED: char = '\u{2610}';
+
+/// Receives the pictures found while importing a document and returns the `src` to use for them.
+pub trait AssetSink {
+    fn store(&mut self, name: &str, content_type: &str, bytes: Vec<u8>) -> String;
+}
+
+impl<F> AssetSink for F
+where
+    F: FnMut(&str, &str, Vec<u8>) -> String,
+{
+    fn store(&mut self, name: &str, content_type: &str, bytes: Vec<u8>) -> String {
+        self(name, content_type, bytes)
+    }
+}
+
+/// Reads a .docx file, inlining its pictures as `data:` urls.
+pub fn from_docx(bytes: &[u8]) -> Result<Vec<EditorNode>, ImportError> {
+    let mut data_urls = |_: &str, content_type: &str, bytes: Vec<u8>| {
+        format!(
+            "data:{content_type};base64,{}",
+            base64::engine::general_purpose::STANDARD.encode(bytes)
+        )
+    };
+    from_docx_with(bytes, &mut data_urls)
+}
+
+/// Reads a .docx file, handing its pictures to `sink`.
+///
+/// Content other than paragraphs, tables and content controls is skipped. Text keeps only
+/// its strongest formatting, and hyperlinks keep their text without its formatting.
+pub fn from_docx_with(
+    bytes: &[u8],
+    sink: &mut dyn AssetSink,
+) -> Result<Vec<EditorNode>, ImportError> {
+    let mut archive =
+        ZipArchive::new(Cursor::new(bytes)).map_err(|e| ImportError::Invalid(e.to_string()))?;
+    let document = read_part(&mut archive, "word/document.xml")?
+        .ok_or_else(|| ImportError::Invalid("missing word/document.xml".to_owned()))?;
+    let mut reader = DocxReader {
+        styles: HashMap::new(),
+        bullets: HashMap::new(),
+        levels: HashMap::new(),
+        relationships: HashMap::new(),
+        last_list: None,
+        archive,
+        sink,
+    };
+    reader.load_styles()?;
+    reader.load_numbering()?;
+    reader.load_relationships()?;
+
+    let xml = parse(&document)?;
+    let body = xml
+        .root_element()
+        .children()
+        .find(|n| n.has_tag_name((NS_W, "body")))
+        .ok_or_else(|| ImportError::Invalid("document without body".to_owned()))?;
+    let mut nodes = vec![];
+    reader.blocks(body, &mut nodes)?;
+    Ok(nodes)
+}
+
+fn read_part(
+    archive: &mut ZipArchive<Cursor<&[u8]>>,
+    name: &str,
+) -> Result<Option<String>, ImportError> {
+    let mut file = match archive.by_name(name) {
+        Ok(file) => file,
+        Err(zip::result::ZipError::FileNotFound) => return Ok(None),
+        Err(e) => return Err(ImportError::Invalid(e.to_string())),
+    };
+    let mut content = String::new();
+    file.read_to_string(&mut content)
+        .map_err(|e| ImportError::Invalid(e.to_string()))?;
+    Ok(Some(content))
+}
+
+fn parse(xml: &str) -> Result<Document<'_>, ImportError> {
+    Document::parse(xml).map_err(|e| ImportError::Invalid(e.to_string()))
+}
+
+fn w_attr<'a>(node: Node<'a, '_>, name: &str) -> Option<&'a str> {
+    node.attribute((NS_W, name))
+}
+
+fn child<'a, 'input>(node: Node<'a, 'input>, name: &str) -> Option<Node<'a, 'input>> {
+    node.children().find(|n| n.has_tag_name((NS_W, name)))
+}
+
+/// Whether a toggle property such as `<w:b/>` or `<w:b w:val="0"/>` is switched on.
+fn toggled(properties: Option<Node>, name: &str) -> bool {
+    properties
+        .and_then(|p| child(p, name))
+        .is_some_and(|n| !matches!(w_attr(n, "val"), Some("0" | "false" | "off")))
+}
+
+#[derive(Default)]
+struct Style {
+    name: String,
+    based_on: Option<String>,
+    outline_level: Option<u8>,
+    num_id: Option<String>,
+    monospace: bool,
+}
+
+/// The role of a paragraph, derived from its style and properties.
+enum Kind {
+    Paragraph,
+    Heading(u8),
+    Quote,
+    Code,
+    List {
+        num_id: String,
+        bullet: bool,
+        level: u32,
+    },
+    Task {
+        checked: bool,
+        depth: u32,
+    },
+}
+
+struct DocxReader<'a> {
+    archive: ZipArchive<Cursor<&'a [u8]>>,
+    sink: &'a mut dyn AssetSink,
+    styles: HashMap<String, Style>,
+    /// Whether each numbering instance draws bullets rather than numbers.
+    bullets: HashMap<String, bool>,
+    /// The numbering style and first number of each level of the numbering instances.
+    levels: HashMap<(String, u32), (NumberingStyle, i64)>,
+    relationships: HashMap<String, String>,
+    /// The position in the output and numbering instance of the list being filled.
+    last_list: Option<(usize, String)>,
+}
+
+impl DocxReader<'_> {
+    fn load_styles(&mut self) -> Result<(), ImportError> {
+        let Some(xml) = read_part(&mut self.archive, "word/styles.xml")? else {
+            return Ok(());
+        };
+        for style in parse(&xml)?
+            .descendants()
+            .filter(|n| n.has_tag_name((NS_W, "style")))
+        {
+            let Some(id) = w_attr(style, "styleId") else {
+                continue;
+            };
+            let paragraph = child(style, "pPr");
+            let run = child(style, "rPr");
+            let font = run
+                .and_then(|r| child(r, "rFonts"))
+                .and_then(|f| w_attr(f, "ascii"))
+                .unwrap_or_default();
+            self.styles.insert(
+                id.to_owned(),
+                Style {
+                    name: child(style, "name")
+                        .and_then(|n| w_attr(n, "val"))
+                        .unwrap_or(id)
+                        .to_lowercase(),
+                    based_on: child(style, "basedOn")
+                        .and_then(|n| w_attr(n, "val"))
+                        .map(str::to_owned),
+                    outline_level: paragraph
+                        .and_then(|p| child(p, "outlineLvl"))
+                        .and_then(|n| w_attr(n, "val"))
+                        .and_then(|v| v.parse().ok()),
+                    num_id: paragraph
+                        .and_then(|p| child(p, "numPr"))
+                        .and_then(|n| child(n, "numId"))
+                        .and_then(|n| w_attr(n, "val"))
+                        .map(str::to_owned),
+                    monospace: matches!(font, "Courier New" | "Consolas" | "Courier" | "Menlo"),
+                },
+            );
+        }
+        Ok(())
+    }
+
+    fn load_numbering(&mut self) -> Result<(), ImportError> {
+        let Some(xml) = read_part(&mut self.archive, "word/numbering.xml")? else {
+            return Ok(());
+        };
+        let xml = parse(&xml)?;
+        let mut abstract_bullets = HashMap::new();
+        let mut abstract_levels = HashMap::new();
+        for abstract_num in xml
+            .descendants()
+            .filter(|n| n.has_tag_name((NS_W, "abstractNum")))
+        {
+            let Some(id) = w_attr(abstract_num, "abstractNumId") else {
+                continue;
+            };
+            let mut levels = HashMap::new();
+            for lvl in abstract_num
+                .children()
+                .filter(|n| n.has_tag_name((NS_W, "lvl")))
+            {
+                let Some(ilvl) = w_attr(lvl, "ilvl").and_then(|v| v.parse::<u32>().ok()) else {
+                    continue;
+                };
+                let format = child(lvl, "numFmt")
+                    .and_then(|f| w_attr(f, "val"))
+                    .unwrap_or_default();
+                if ilvl == 0 {
+                    abstract_bullets.insert(id, format == "bullet");
+                }
+                let style = match format {
+                    "lowerLetter" => NumberingStyle::LowerAlpha,
+                    "upperLetter" => NumberingStyle::UpperAlpha,
+                    "lowerRoman" => NumberingStyle::LowerRoman,
+                    "upperRoman" => NumberingStyle::UpperRoman,
+                    _ => NumberingStyle::Decimal,
+                };
+                let start = child(lvl, "start")
+                    .and_then(|n| w_attr(n, "val"))
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(1);
+                levels.insert(ilvl, (style, start));
+            }
+            abstract_levels.insert(id, levels);
+        }
+        for num in xml.descendants().filter(|n| n.has_tag_name((NS_W, "num"))) {
+            let abstract_id = child(num, "abstractNumId").and_then(|n| w_attr(n, "val"));
+            let (Some(id), Some(abstract_id)) = (w_attr(num, "numId"), abstract_id) else {
+                continue;
+            };
+            let bullet = abstract_bullets.get(abstract_id).copied().unwrap_or(true);
+            self.bullets.insert(id.to_owned(), bullet);
+            let mut levels = abstract_levels
+                .get(abstract_id)
+                .cloned()
+                .unwrap_or_default();
+            for level_override in num
+                .children()
+                .filter(|n| n.has_tag_name((NS_W, "lvlOverride")))
+            {
+                let ilvl = w_attr(level_override, "ilvl").and_then(|v| v.parse().ok());
+                let start = child(level_override, "startOverride")
+                    .and_then(|n| w_attr(n, "val"))
+                    .and_then(|v| v.parse().ok());
+                if let (Some(ilvl), Some(start)) = (ilvl, start) {
+                    levels.entry(ilvl).or_insert((NumberingStyle::Decimal, 1)).1 = start;
+                }
+            }
+            for (ilvl, level) in levels {
+                self.levels.insert((id.to_owned(), ilvl), level);
+            }
+        }
+        Ok(())
+    }
+
+    fn load_relationships(&mut self) -> Result<(), ImportError> {
+        let Some(xml) = read_part(&mut self.archive, "word/_rels/document.xml.rels")? else {
+            return Ok(());
+        };
+        for relationship in parse(&xml)?
+            .descendants()
+            .filter(|n| n.tag_name().name() == "Relationship")
+        {
+            if let (Some(id), Some(target)) = (
+                relationship.attribute("Id"),
+                relationship.attribute("Target"),
+            ) {
+                self.relationships.insert(id.to_owned(), target.to_owned());
+            }
+        }
+        Ok(())
+    }
+
+    /// Follows a style and the styles it is based on until `f` returns a value.
+    fn resolve_style<T>(&self, id: &str, f: impl Fn(&Style) -> Option<T>) -> Option<T> {
+        let mut current = self.styles.get(id);
+        for _ in 0..16 {
+            let style = current?;
+            if let Some(value) = f(style) {
+                return Some(value);
+            }
+            current = style.based_on.as_ref().and_then(|id| self.styles.get(id));
+        }
+        None
+    }
+
+    fn blocks(&mut self, parent: Node, nodes: &mut Vec<EditorNode>) -> Result<(), ImportError> {
+        for node in parent.children().filter(Node::is_element) {
+            if node.tag_name().namespace() != Some(NS_W) {
+                continue;
+            }
+            match node.tag_name().name() {
+                "p" => self.paragraph(node, nodes)?,
+                "sdt" => {
+                    if let Some(content) = child(node, "sdtContent") {
+                        self.blocks(content, nodes)?;
+                    }
+                }
+                "tbl" => {
+                    let table = self.table(node)?;
+                    nodes.push(EditorNode::Table(table));
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads a table, rebuilding row spans from the `vMerge` continuation cells.
+    fn table(&mut self, table: Node) -> Result<Table, ImportError> {
+        let mut rows: Vec<TableRow> = vec![];
+        // The cell started in each grid column, to be stretched by the rows below.
+        let mut columns: Vec<Option<(usize, usize)>> = vec![];
+        for row in table.children().filter(|n| n.has_tag_name((NS_W, "tr"))) {
+            let header = toggled(child(row, "trPr"), "tblHeader");
+            let r = rows.len();
+            let mut cells = vec![];
+            let mut column = 0;
+            for cell in row.children().filter(|n| n.has_tag_name((NS_W, "tc"))) {
+                let properties = child(cell, "tcPr");
+                let colspan = properties
+                    .and_then(|p| child(p, "gridSpan"))
+                    .and_then(|n| w_attr(n, "val"))
+                    .and_then(|v| v.parse::<u32>().ok())
+                    .unwrap_or(1)
+                    .max(1);
+                let merge = properties.and_then(|p| child(p, "vMerge"));
+                let continued = merge
+                    .filter(|merge| w_attr(*merge, "val").is_none_or(|val| val == "continue"))
+                    .and_then(|_| columns.get(column).copied().flatten());
+                if let Some((above, i)) = continued {
+                    rows[above].cells[i].rowspan += 1;
+                } else {
+                    self.last_list = None;
+                    let mut content = vec![];
+                    self.blocks(cell, &mut content)?;
+                    self.last_list = None;
+                    if let [EditorNode::Paragraph(paragraph)] = content.as_slice() {
+                        if paragraph.elements.is_empty() {
+                            content.clear();
+                        }
+                    }
+                    let index = (r, cells.len());
+                    if columns.len() < column + colspan as usize {
+                        columns.resize(column + colspan as usize, None);
+                    }
+                    columns[column..column + colspan as usize].fill(Some(index));
+                    cells.push(TableCell {
+                        header,
+                        colspan,
+                        rowspan: 1,
+                        content,
+                    });
+                }
+                column += colspan as usize;
+            }
+            rows.push(TableRow { cells });
+        }
+        Ok(Table { rows })
+    }
+
+    fn kind(&self, paragraph: Node) -> Kind {
+        let properties = child(paragraph, "pPr");
+        if let Some(checked) = checkbox(paragraph) {
+            // Subtasks are told apart by their indent alone.
+            let depth = properties
+                .and_then(|p| child(p, "ind"))
+                .and_then(|n| w_attr(n, "left").or_else(|| w_attr(n, "start")))
+                .and_then(|v| v.parse::<u32>().ok())
+                .map_or(0, |indent| (indent + TASK_INDENT / 2) / TASK_INDENT);
+            return Kind::Task { checked, depth };
+        }
+        let style = properties
+            .and_then(|p| child(p, "pStyle"))
+            .and_then(|s| w_attr(s, "val"))
+            .unwrap_or("Normal");
+        let num_pr = properties.and_then(|p| child(p, "numPr"));
+        let num_id = num_pr
+            .and_then(|n| child(n, "numId"))
+            .and_then(|n| w_attr(n, "val"))
+            .map(str::to_owned)
+            .or_else(|| self.resolve_style(style, |s| s.num_id.clone()));
+        if let Some(num_id) = num_id.filter(|id| id != "0") {
+            let bullet = self.bullets.get(&num_id).copied().unwrap_or(true);
+            let level = num_pr
+                .and_then(|n| child(n, "ilvl"))
+                .and_then(|n| w_attr(n, "val"))
+                .and_then(|v| v.parse().ok())
+                .unwrap_or_default();
+            return Kind::List {
+                num_id,
+                bullet,
+                level,
+            };
+        }
+        let outline_level = properties
+            .and_then(|p| child(p, "outlineLvl"))
+            .and_then(|n| w_attr(n, "val"))
+            .and_then(|v| v.parse::<u8>().ok())
+            .or_else(|| self.resolve_style(style, |s| s.outline_level));
+        if let Some(level) = outline_level.filter(|level| *level < 9) {
+            return Kind::Heading((level + 1).min(6));
+        }
+        let name = self
+            .styles
+            .get(style)
+            .map(|s| s.name.clone())
+            .unwrap_or_else(|| style.to_lowercase());
+        if let Some(level) = name
+            .strip_prefix("heading")
+            .and_then(|level| level.trim().parse::<u8>().ok())
+        {
+            return Kind::Heading(level.clamp(1, 6));
+        }
+        match name.as_str() {
+            "title" => Kind::Heading(1),
+            "subtitle" => Kind::Heading(2),
+            "quote" | "intense quote" => Kind::Quote,
+            "source code" | "html preformatted" | "code" => Kind::Code,
+            _ => Kind::Paragraph,
+        }
+    }
+
+    fn paragraph(
+        &mut self,
+        paragraph: Node,
+        nodes: &mut Vec<EditorNode>,
+    ) -> Result<(), ImportError> {
+        let kind = self.kind(paragraph);
+        let mut elements = vec![];
+        self.runs(paragraph, None, &mut elements)?;
+        // Headings, code and tasks hold plain text, their pictures follow them as blocks.
+        let images = match kind {
+            Kind::Heading(_) | Kind::Code | Kind::Task { .. } => {
+                let (images, rest) = elements
+                    .into_iter()
+                    .partition(|node| matches!(node, EditorNode::Image(_)));
+                elements = rest;
+                images
+            }
+            _ => vec![],
+        };
+        let text = || elements.iter().map(EditorNode::text).collect::<String>();
+        match kind {
+            Kind::Heading(level) => nodes.push(EditorNode::Heading(Header {
+                text: text().replace('\n', " "),
+                level,
+            })),
+            // Consecutive quote paragraphs form a single quote.
+            Kind::Quote => {
+                let paragraph = EditorNode::Paragraph(Paragraph { elements });
+                match nodes.last_mut() {
+                    Some(EditorNode::BlockQuote(quote)) => quote.content.push(paragraph),
+                    _ => nodes.push(EditorNode::BlockQuote(BlockQuote {
+                        content: vec![paragraph],
+                    })),
+                }
+            }
+            Kind::Code => nodes.push(EditorNode::CodeBlock(CodeBlock {
+                language: None,
+                code: text(),
+            })),
+            Kind::Task { checked, depth } => {
+                let text = text()
+                    .trim_start_matches([CHECKED, UNCHECKED])
+                    .trim_start()
+                    .to_owned();
+                let item = TaskItem {
+                    text,
+                    checked,
+                    children: vec![],
+                };
+                match nodes.last_mut() {
+                    Some(EditorNode::TaskList(list)) => {
+                        // A subtask goes under the last task above it, however deep.
+                        let mut siblings = &mut list.list;
+                        for _ in 0..depth {
+                            if siblings.is_empty() {
+                                break;
+                            }
+                            siblings = &mut siblings.last_mut().unwrap().children;
+                        }
+                        siblings.push(item)
+                    }
+                    _ => nodes.push(EditorNode::TaskList(TaskList { list: vec![item] })),
+                }
+            }
+            Kind::List {
+                num_id,
+                bullet,
+                level,
+            } => {
+                let item = ListItem {
+                    content: vec![EditorNode::Paragraph(Paragraph { elements })],
+                };
+                // Consecutive paragraphs of the same numbering instance form one list, deeper
+                // levels going in a list inside the last item above them.
+                let continues = matches!(
+                    &self.last_list,
+                    Some((index, id)) if index + 1 == nodes.len() && (*id == num_id || level > 0)
+                );
+                let siblings = nested_blocks(nodes, if continues { level } else { 0 });
+                let same_kind = match siblings.last_mut() {
+                    Some(EditorNode::BulletList(list)) if bullet => Some(&mut list.list),
+                    Some(EditorNode::OrderedList(list)) if !bullet => Some(&mut list.list),
+                    _ => None,
+                };
+                match same_kind {
+                    Some(list) if continues => list.push(item),
+                    _ if bullet => {
+                        siblings.push(EditorNode::BulletList(BulletList { list: vec![item] }))
+                    }
+                    _ => {
+                        let (style, start) = self
+                            .levels
+                            .get(&(num_id.clone(), level))
+                            .copied()
+                            .unwrap_or((NumberingStyle::Decimal, 1));
+                        siblings.push(EditorNode::OrderedList(OrderedList {
+                            list: vec![item],
+                            start: Some(start).filter(|start| *start != 1),
+                            style,
+                            reversed: false,
+                        }))
+                    }
+                }
+                if !continues {
+                    self.last_list = Some((nodes.len() - 1, num_id));
+                }
+            }
+            Kind::Paragraph => match elements.as_slice() {
+                [EditorNode::Image(_)] => nodes.extend(elements),
+                _ => nodes.push(EditorNode::Paragraph(Paragraph { elements })),
+            },
+        }
+        nodes.extend(images);
+        Ok(())
+    }
+
+    fn runs(
+        &mut self,
+        parent: Node,
+        inherited: Option<Mark>,
+        elements: &mut Vec<EditorNode>,
+    ) -> Result<(), ImportError> {
+        for node in parent.children().filter(Node::is_element) {
+            if node.tag_name().namespace() != Some(NS_W) {
+                continue;
+            }
+            match node.tag_name().name() {
+                "r" => self.run(node, inherited, elements)?,
+                "hyperlink" => self.hyperlink(node, inherited, elements)?,
+                "ins" | "smartTag" | "fldSimple" => self.runs(node, inherited, elements)?,
+                "sdt" => {
+                    if let Some(content) = child(node, "sdtContent") {
+                        self.runs(content, inherited, elements)?;
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads a hyperlink as links holding the text of its runs, split around its pictures
+    /// and line breaks, or as the runs alone when its target is missing or not allowed.
+    ///
+    /// Links hold plain text, so the formatting of the runs is not kept.
+    fn hyperlink(
+        &mut self,
+        hyperlink: Node,
+        inherited: Option<Mark>,
+        elements: &mut Vec<EditorNode>,
+    ) -> Result<(), ImportError> {
+        let mut runs = vec![];
+        self.runs(hyperlink, inherited, &mut runs)?;
+        let href = match (
+            hyperlink.attribute((NS_R, "id")),
+            w_attr(hyperlink, "anchor"),
+        ) {
+            (Some(id), _) => self.relationships.get(id).cloned(),
+            (None, Some(anchor)) => Some(format!("#{anchor}")),
+            (None, None) => None,
+        };
+        let Some(href) = href.and_then(|href| sanitize_href(&href)) else {
+            elements.extend(runs);
+            return Ok(());
+        };
+        let mut text = String::new();
+        let push_link = |text: &mut String, elements: &mut Vec<EditorNode>| {
+            if !text.is_empty() {
+                elements.push(EditorNode::Link(Link {
+                    text: std::mem::take(text),
+                    href: href.clone(),
+                    title: w_attr(hyperlink, "tooltip").unwrap_or_default().to_owned(),
+                    target: w_attr(hyperlink, "tgtFrame").unwrap_or_default().to_owned(),
+                }));
+            }
+        };
+        for run in runs {
+            match run {
+                EditorNode::Image(_) | EditorNode::HardBreak(_) => {
+                    push_link(&mut text, elements);
+                    elements.push(run);
+                }
+                run => text.push_str(&run.text()),
+            }
+        }
+        push_link(&mut text, elements);
+        Ok(())
+    }
+
+    fn run(
+        &mut self,
+        run: Node,
+        inherited: Option<Mark>,
+        elements: &mut Vec<EditorNode>,
+    ) -> Result<(), ImportError> {
+        let properties = child(run, "rPr");
+        let style = properties
+            .and_then(|p| child(p, "rStyle"))
+            .and_then(|s| w_attr(s, "val"));
+        let monospace = properties
+            .and_then(|p| child(p, "rFonts"))
+            .and_then(|f| w_attr(f, "ascii"))
+            .is_some_and(|font| matches!(font, "Courier New" | "Consolas" | "Courier" | "Menlo"))
+            || style.is_some_and(|id| {
+                self.resolve_style(id, |s| s.monospace.then_some(()))
+                    .is_some()
+                    || self.styles.get(id).is_some_and(|s| s.name.contains("code"))
+            });
+        let mut mark = inherited;
+        if toggled(properties, "i") {
+            mark = mark.max(Some(Mark::Italic));
+        }
+        if toggled(properties, "b") {
+            mark = mark.max(Some(Mark::Bold));
+        }
+        if monospace {
+            mark = mark.max(Some(Mark::Code));
+        }
+        let property = |name: &str| {
+            properties
+                .and_then(|p| child(p, name))
+                .and_then(|n| w_attr(n, "val"))
+        };
+        if property("u").is_some_and(|underline| underline != "none") {
+            mark = mark.max(Some(Mark::Underline));
+        }
+        if toggled(properties, "strike") || toggled(properties, "dstrike") {
+            mark = mark.max(Some(Mark::Strike));
+        }
+        match property("vertAlign") {
+            Some("subscript") => mark = mark.max(Some(Mark::Subscript)),
+            Some("superscript") => mark = mark.max(Some(Mark::Superscript)),
+            _ => {}
+        }
+        let hex = |color: &str| sanitize_color(&format!("#{color}"));
+        // Shading is how other colours than Word's few named highlights are written.
+        let shading = properties
+            .and_then(|p| child(p, "shd"))
+            .and_then(|shd| w_attr(shd, "fill"))
+            .filter(|fill| *fill != "auto")
+            .and_then(hex);
+        if shading.is_some() || property("highlight").is_some_and(|h| h != "none") {
+            mark = mark.max(Some(Mark::Highlight));
+        }
+        let color = property("color").filter(|c| *c != "auto").and_then(hex);
+        let text_node = |text: &str| {
+            let text = text.to_owned();
+            match (mark, &shading, &color) {
+                (Some(Mark::Highlight), Some(fill), _) => EditorNode::Highlight(Highlight {
+                    text,
+                    color: Some(fill.clone()),
+                }),
+                (None, _, Some(color)) => EditorNode::TextColor(TextColor {
+                    text,
+                    color: color.clone(),
+                }),
+                _ => Mark::node(mark, text),
+            }
+        };
+        for node in run.children().filter(Node::is_element) {
+            match (node.tag_name().namespace(), node.tag_name().name()) {
+                (Some(NS_W), "t") => {
+                    push_text(elements, text_node(node.text().unwrap_or_default()))
+                }
+                (Some(NS_W), "tab") => push_text(elements, text_node("\t")),
+                (Some(NS_W), "br" | "cr") => elements.push(EditorNode::HardBreak(HardBreak)),
+                (Some(NS_W), "drawing") => {
+                    if let Some(image) = self.drawing(node)? {
+                        elements.push(EditorNode::Image(image));
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn drawing(&mut self, drawing: Node) -> Result<Option<Image>, ImportError> {
+        let Some(embed) = drawing
+            .descendants()
+            .find(|n| n.has_tag_name((NS_A, "blip")))
+            .and_then(|blip| blip.attribute((NS_R, "embed")))
+        else {
+            return Ok(None);
+        };
+        let Some(target) = self.relationships.get(embed).cloned() else {
+            return Ok(None);
+        };
+        let path = match target.strip_prefix('/') {
+            Some(absolute) => absolute.to_owned(),
+            None => format!("word/{target}"),
+        };
+        let mut bytes = vec![];
+        match self.archive.by_name(&path) {
+            Ok(mut file) => file
+                .read_to_end(&mut bytes)
+                .map_err(|e| ImportError::Invalid(e.to_string()))?,
+            Err(_) => return Ok(None),
+        };
+        let content_type = ImageFormat::sniff(&bytes)
+            .map(|format| format.content_type)
+            .unwrap_or("application/octet-stream");
+        let name = path.rsplit('/').next().unwrap_or(&path).to_owned();
+        let alt = drawing
+            .descendants()
+            .find(|n| n.has_tag_name((NS_WP, "docPr")))
+            .and_then(|doc_pr| doc_pr.attribute("descr"))
+            .unwrap_or_default()
+            .to_owned();
+        Ok(Some(Image {
+            src: self.sink.store(&name, content_type, bytes),
+            alt,
+            ..Default::default()
+        }))
+    }
+}
+
+/// The blocks of the last list item `depth` lists down, or as deep as the lists go.
+fn nested_blocks(nodes: &mut Vec<EditorNode>, depth: u32) -> &mut Vec<EditorNode> {
+    fn last_item(nodes: &mut [EditorNode]) -> Option<&mut ListItem> {
+        match nodes.last_mut() {
+            Some(EditorNode::BulletList(BulletList { list }))
+            | Some(EditorNode::OrderedList(OrderedList { list, .. })) => list.last_mut(),
+            _ => None,
+        }
+    }
+    if depth == 0 || last_item(nodes).is_none() {
+        return nodes;
+    }
+    nested_blocks(&mut last_item(nodes).unwrap().content, depth - 1)
+}
+
+/// Reads the state of a checkbox starting the paragraph, either a Word content control
+/// or a ballot box glyph.
+fn checkbox(paragraph: Node) -> Option<bool> {
+    if let Some(checkbox) = paragraph
+        .descendants()
+        .find(|n| n.has_tag_name((NS_W14, "checkbox")))
+    {
+        let checked = checkbox
+            .children()
+            .find(|n| n.has_tag_name((NS_W14, "checked")))
+            .and_then(|n| n.attribute((NS_W14, "val")));
+        return Some(matches!(checked, Some("1" | "true")));
+    }
+    let first = paragraph
+        .descendants()
+        .find(|n| n.has_tag_name((NS_W, "t")))
+        .and_then(|t| t.text())?
+        .chars()
+        .next()?;
+    match first {
+        CHECKED => Some(true),
+        UNCHECKED => Some(false),
+        _ => None,
+    }
+}
+
+/// Appends a text node, merging it into the previous node when both carry the same mark
+/// and colour since Word splits text into many runs.
+fn push_text(elements: &mut Vec<EditorNode>, node: EditorNode) {
+    if node.text().is_empty() {
+        return;
+    }
+    match (elements.last_mut(), &node) {
+        (Some(EditorNode::Highlight(last)), EditorNode::Highlight(next))
+            if last.color == next.color =>
+        {
+            last.text.push_str(&next.text)
+        }
+        (Some(EditorNode::TextColor(last)), EditorNode::TextColor(next))
+            if last.color == next.color =>
+        {
+            last.text.push_str(&next.text)
+        }
+        (Some(last), next) => match (inline_parts(last), inline_parts(next)) {
+            (Some((previous, mark)), Some((text, next_mark))) if mark == next_mark => {
+                *last = Mark::node(mark, format!("{previous}{text}"))
+            }
+            _ => elements.push(node),
+        },
+        (None, _) => elements.push(node),
+    }
+}
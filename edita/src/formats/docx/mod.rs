@@ -0,0 +1,315 @@
+//! Reading and writing Word documents (Office Open XML).
+mod export;
+mod import;
+
+use base64::Engine;
+
+pub use self::export::{to_docx, to_docx_with, AssetLoader};
+pub use self::import::{from_docx, from_docx_with, AssetSink};
+
+const NS_W: &str = "http://schemas.openxmlformats.org/wordprocessingml/2006/main";
+const NS_R: &str = "http://schemas.openxmlformats.org/officeDocument/2006/relationships";
+const NS_W14: &str = "http://schemas.microsoft.com/office/word/2010/wordml";
+
+/// Pixels are converted to English Metric Units at 96 dpi.
+const EMU_PER_PIXEL: u64 = 9525;
+
+/// The left indent of each level of subtasks, in twentieths of a point.
+const TASK_INDENT: u32 = 720;
+
+/// A picture format that can be embedded in a document.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct ImageFormat {
+    pub extension: &'static str,
+    pub content_type: &'static str,
+}
+
+const PNG: ImageFormat = ImageFormat {
+    extension: "png",
+    content_type: "image/png",
+};
+const JPEG: ImageFormat = ImageFormat {
+    extension: "jpeg",
+    content_type: "image/jpeg",
+};
+const GIF: ImageFormat = ImageFormat {
+    extension: "gif",
+    content_type: "image/gif",
+};
+
+impl ImageFormat {
+    /// Recognises a picture from its magic bytes.
+    pub(crate) fn sniff(bytes: &[u8]) -> Option<ImageFormat> {
+        if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+            Some(PNG)
+        } else if bytes.starts_with(&[0xff, 0xd8]) {
+            Some(JPEG)
+        } else if bytes.starts_with(b"GIF8") {
+            Some(GIF)
+        } else {
+            None
+        }
+    }
+
+    /// Reads the pixel dimensions from the picture's header.
+    pub(crate) fn dimensions(&self, bytes: &[u8]) -> Option<(u32, u32)> {
+        let be32 = |at: usize| -> Option<u32> {
+            Some(u32::from_be_bytes(bytes.get(at..at + 4)?.try_into().ok()?))
+        };
+        let be16 = |at: usize| -> Option<u32> {
+            Some(u16::from_be_bytes(bytes.get(at..at + 2)?.try_into().ok()?) as u32)
+        };
+        let le16 = |at: usize| -> Option<u32> {
+            Some(u16::from_le_bytes(bytes.get(at..at + 2)?.try_into().ok()?) as u32)
+        };
+        match self.extension {
+            "png" => Some((be32(16)?, be32(20)?)),
+            "gif" => Some((le16(6)?, le16(8)?)),
+            _ => {
+                // Walk the JPEG segments until a start-of-frame marker.
+                let mut at = 2;
+                while at + 9 < bytes.len() {
+                    if bytes[at] != 0xff {
+                        return None;
+                    }
+                    let marker = bytes[at + 1];
+                    if matches!(marker, 0xc0..=0xcf) && !matches!(marker, 0xc4 | 0xc8 | 0xcc) {
+                        return Some((be16(at + 7)?, be16(at + 5)?));
+                    }
+                    at += 2 + be16(at + 2)? as usize;
+                }
+                None
+            }
+        }
+    }
+}
+
+/// Decodes a base64 `data:` url into its bytes.
+pub(crate) fn decode_data_url(src: &str) -> Option<Vec<u8>> {
+    let (header, data) = src.strip_prefix("data:")?.split_once(',')?;
+    if !header.ends_with(";base64") {
+        return None;
+    }
+    base64::engine::general_purpose::STANDARD.decode(data).ok()
+}
+
+/// Escapes text for use in xml content and attribute values.
+pub(crate) fn escape_xml(text: &str) -> String {
+    crate::formats::html::escape(text).replace('\'', "&apos;")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, Read};
+
+    use super::*;
+    use crate::nodes::{
+        Bold, BulletList, CodeBlock, EditorNode, Header, Highlight, Image, Link, ListItem,
+        NumberingStyle, OrderedList, Paragraph, Subscript, Table, TaskItem, TaskList, TextColor,
+        TextNode,
+    };
+
+    /// The signature and IHDR chunk of a 2x1 png, enough to be sniffed and measured.
+    fn png() -> Vec<u8> {
+        let mut png = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR".to_vec();
+        png.extend_from_slice(&2u32.to_be_bytes());
+        png.extend_from_slice(&1u32.to_be_bytes());
+        png.extend_from_slice(&[8, 6, 0, 0, 0]);
+        png
+    }
+
+    #[test]
+    fn test_docx_export() {
+        let src = format!(
+            "data:image/png;base64,{}",
+            base64::engine::general_purpose::STANDARD.encode(png())
+        );
+        let nodes = vec![
+            EditorNode::Heading(Header {
+                text: "Title & more".to_owned(),
+                level: 2,
+            }),
+            EditorNode::TaskList(TaskList {
+                list: vec![TaskItem {
+                    text: "done".to_owned(),
+                    checked: true,
+                    ..Default::default()
+                }],
+            }),
+            EditorNode::Image(Image::new(&src, "A")),
+        ];
+        let bytes = to_docx(&nodes).unwrap();
+        let mut archive = zip::ZipArchive::new(Cursor::new(bytes)).unwrap();
+        let mut document = String::new();
+        archive
+            .by_name("word/document.xml")
+            .unwrap()
+            .read_to_string(&mut document)
+            .unwrap();
+        assert!(document.contains(r#"<w:pStyle w:val="Heading2"/>"#));
+        assert!(document.contains("Title &amp; more"));
+        assert!(document.contains(r#"<w14:checked w14:val="1"/>"#));
+        assert!(document.contains(r#"<wp:extent cx="19050" cy="9525"/>"#));
+        assert!(archive.by_name("word/media/image1.png").is_ok());
+    }
+
+    #[test]
+    fn test_docx_round_trip() {
+        let src = format!(
+            "data:image/png;base64,{}",
+            base64::engine::general_purpose::STANDARD.encode(png())
+        );
+        let list = |items: &[&str]| OrderedList {
+            list: items.iter().map(|text| ListItem::from_text(text)).collect(),
+            ..Default::default()
+        };
+        let mut nested = list(&["one", "two"]);
+        nested.list[0]
+            .content
+            .push(EditorNode::BulletList(BulletList {
+                list: vec![ListItem::from_text("a")],
+            }));
+        let mut table = Table::new(3, 2, true);
+        table.rows[0].cells[0].content = vec![EditorNode::Paragraph(Paragraph {
+            elements: vec![EditorNode::Text(TextNode {
+                text: "Name".to_owned(),
+            })],
+        })];
+        table.merge((1, 0), (2, 0));
+        let nodes = vec![
+            EditorNode::Heading(Header {
+                text: "Title".to_owned(),
+                level: 3,
+            }),
+            EditorNode::Paragraph(Paragraph {
+                elements: vec![
+                    EditorNode::Text(TextNode {
+                        text: "Hello ".to_owned(),
+                    }),
+                    EditorNode::Bold(Bold {
+                        text: "world".to_owned(),
+                    }),
+                    EditorNode::Link(Link {
+                        title: "Docs".to_owned(),
+                        ..Link::new("docs", "https://a.io/?a=1&b=2")
+                    }),
+                    EditorNode::Subscript(Subscript {
+                        text: "2".to_owned(),
+                    }),
+                    EditorNode::Highlight(Highlight {
+                        text: "key".to_owned(),
+                        color: Some("#FFEE00".to_owned()),
+                    }),
+                    EditorNode::TextColor(TextColor {
+                        text: "red".to_owned(),
+                        color: "#C00000".to_owned(),
+                    }),
+                ],
+            }),
+            EditorNode::OrderedList(nested),
+            EditorNode::OrderedList(OrderedList {
+                start: Some(4),
+                style: NumberingStyle::LowerRoman,
+                ..list(&["three"])
+            }),
+            EditorNode::TaskList(TaskList {
+                list: vec![TaskItem {
+                    text: "todo".to_owned(),
+                    checked: false,
+                    children: vec![TaskItem {
+                        text: "subtask".to_owned(),
+                        checked: true,
+                        ..Default::default()
+                    }],
+                }],
+            }),
+            EditorNode::Image(Image::new(&src, "A")),
+            EditorNode::CodeBlock(CodeBlock {
+                language: None,
+                code: "fn main() {\n    run();\n}".to_owned(),
+            }),
+            EditorNode::Table(table),
+        ];
+        let imported = from_docx(&to_docx(&nodes).unwrap()).unwrap();
+        assert_eq!(imported.len(), nodes.len());
+        assert!(matches!(&imported[0], EditorNode::Heading(h) if h.level == 3));
+        assert!(
+            matches!(&imported[1], EditorNode::Paragraph(p) if matches!(&p.elements[1], EditorNode::Bold(b) if b.text == "world"))
+        );
+        assert!(
+            matches!(&imported[1], EditorNode::Paragraph(p) if matches!(&p.elements[2], EditorNode::Link(l) if l.title == "Docs" && l.href == "https://a.io/?a=1&b=2"))
+        );
+        let EditorNode::Paragraph(paragraph) = &imported[1] else {
+            panic!("expected a paragraph, got {:?}", imported[1]);
+        };
+        assert!(matches!(&paragraph.elements[3], EditorNode::Subscript(s) if s.text == "2"));
+        assert!(
+            matches!(&paragraph.elements[4], EditorNode::Highlight(h) if h.color.as_deref() == Some("#FFEE00"))
+        );
+        assert!(matches!(&paragraph.elements[5], EditorNode::TextColor(c) if c.color == "#C00000"));
+        assert!(matches!(&imported[2], EditorNode::OrderedList(l) if l.list.len() == 2));
+        assert_eq!(imported[2].text(), "one\na\ntwo");
+        assert!(matches!(&imported[3], EditorNode::OrderedList(l) if l.marker(0) == "iv."));
+        assert!(
+            matches!(&imported[4], EditorNode::TaskList(l) if !l.list[0].checked && l.list[0].text == "todo")
+        );
+        assert!(
+            matches!(&imported[4], EditorNode::TaskList(l) if l.list.len() == 1 && l.list[0].children[0].checked)
+        );
+        assert!(matches!(&imported[5], EditorNode::Image(i) if i.src == src));
+        assert!(matches!(&imported[6], EditorNode::CodeBlock(c) if c.code.contains("\n    run")));
+        let EditorNode::Table(table) = &imported[7] else {
+            panic!("expected a table, got {:?}", imported[7]);
+        };
+        assert!(table.rows[0].is_header() && !table.rows[1].is_header());
+        assert_eq!(imported[7].text(), "Name\t\n\t\n");
+        assert_eq!(table.rows[1].cells[0].rowspan, 2);
+        assert_eq!(table.rows[2].cells.len(), 1);
+    }
+
+    #[test]
+    fn test_pictures_and_breaks_in_headings_and_links() {
+        use std::io::Write;
+        use zip::write::SimpleFileOptions;
+
+        let drawing = r#"<w:r><w:drawing><wp:inline><wp:docPr id="1" name="p" descr="A"/><a:graphic><a:graphicData><a:blip r:embed="rImg"/></a:graphicData></a:graphic></wp:inline></w:drawing></w:r>"#;
+        let document = format!(
+            r#"<w:document xmlns:w="{NS_W}" xmlns:r="{NS_R}" xmlns:a="http://schemas.openxmlformats.org/drawingml/2006/main" xmlns:wp="http://schemas.openxmlformats.org/drawingml/2006/wordprocessingDrawing"><w:body>
+            <w:p><w:pPr><w:pStyle w:val="Heading1"/></w:pPr><w:r><w:t>Top</w:t><w:br/><w:t>line</w:t></w:r>{drawing}</w:p>
+            <w:p><w:hyperlink w:anchor="end"><w:r><w:rPr><w:b/></w:rPr><w:t>see</w:t></w:r>{drawing}<w:r><w:t>more</w:t></w:r></w:hyperlink></w:p>
+            </w:body></w:document>"#
+        );
+        let relationships =
+            r#"<Relationships><Relationship Id="rImg" Target="media/image1.png"/></Relationships>"#;
+        let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, bytes) in [
+            ("word/document.xml", document.into_bytes()),
+            (
+                "word/_rels/document.xml.rels",
+                relationships.as_bytes().to_vec(),
+            ),
+            ("word/media/image1.png", png()),
+        ] {
+            zip.start_file(name, SimpleFileOptions::default()).unwrap();
+            zip.write_all(&bytes).unwrap();
+        }
+        let bytes = zip.finish().unwrap().into_inner();
+
+        let imported = from_docx(&bytes).unwrap();
+        assert_eq!(imported.len(), 3);
+        assert!(matches!(&imported[0], EditorNode::Heading(h) if h.text == "Top line"));
+        assert!(matches!(&imported[1], EditorNode::Image(i) if i.alt == "A"));
+        let EditorNode::Paragraph(paragraph) = &imported[2] else {
+            panic!("expected a paragraph, got {:?}", imported[2]);
+        };
+        let types: Vec<_> = paragraph
+            .elements
+            .iter()
+            .map(EditorNode::node_type)
+            .collect();
+        assert_eq!(types, ["Link", "Image", "Link"]);
+        assert!(
+            matches!(&paragraph.elements[2], EditorNode::Link(l) if l.text == "more" && l.href == "#end")
+        );
+    }
+}
@@ -0,0 +1,543 @@
+//! Conversion between edita nodes and Editor.js output data.
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::editor::HtmlNode;
+use crate::formats::html::{escape, inline_from_html, inline_to_html};
+use crate::formats::{inline_parts, ImportError};
+use crate::nodes::{
+    BlockQuote, BulletList, CodeBlock, Details, EditorNode, Header, HorizontalRule, Image,
+    ListItem, NumberingStyle, OrderedList, Paragraph, Table, TableCell, TableRow, TaskItem,
+    TaskList,
+};
+
+/// The data saved by Editor.js, eg. `{"time": 0, "blocks": [...], "version": "2.28.0"}`.
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq)]
+pub struct EditorJsData {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time: Option<u64>,
+    pub blocks: Vec<EditorJsBlock>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+}
+
+/// A single Editor.js block, eg. `{"type": "header", "data": {"text": "..", "level": 2}}`.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct EditorJsBlock {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(rename = "type")]
+    pub kind: String,
+    #[serde(default)]
+    pub data: Value,
+}
+
+impl EditorJsBlock {
+    fn new(kind: &str, data: Value) -> Self {
+        EditorJsBlock {
+            id: None,
+            kind: kind.to_owned(),
+            data,
+        }
+    }
+
+    fn str(&self, name: &str) -> &str {
+        self.data
+            .get(name)
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+    }
+}
+
+/// Converts Editor.js data into edita nodes.
+///
+/// Blocks from tools edita does not know about are reported as [`ImportError::Unsupported`].
+pub fn from_editorjs(data: &EditorJsData) -> Result<Vec<EditorNode>, ImportError> {
+    let mut blocks = data.blocks.iter();
+    let mut nodes = vec![];
+    while let Some(block) = blocks.next() {
+        nodes.push(import_block(block, &mut blocks)?);
+    }
+    Ok(nodes)
+}
+
+/// Imports a block, taking the blocks nested in it from `rest`.
+fn import_block(
+    block: &EditorJsBlock,
+    rest: &mut std::slice::Iter<EditorJsBlock>,
+) -> Result<EditorNode, ImportError> {
+    let node = match block.kind.as_str() {
+        "paragraph" => EditorNode::Paragraph(Paragraph {
+            elements: inline_from_html(block.str("text"))?,
+        }),
+        "header" => EditorNode::Heading(Header {
+            text: plain_text(block.str("text"))?,
+            level: block
+                .data
+                .get("level")
+                .and_then(Value::as_u64)
+                .unwrap_or(1)
+                .clamp(1, 6) as u8,
+        }),
+        "quote" => EditorNode::BlockQuote(BlockQuote {
+            content: vec![EditorNode::Paragraph(Paragraph {
+                elements: inline_from_html(block.str("text"))?,
+            })],
+        }),
+        "image" | "simpleImage" => EditorNode::Image(Image {
+            src: block
+                .data
+                .pointer("/file/url")
+                .or_else(|| block.data.get("url"))
+                .and_then(Value::as_str)
+                .ok_or_else(|| ImportError::Invalid("image block without url".to_owned()))?
+                .to_owned(),
+            alt: plain_text(block.str("caption"))?,
+            ..Default::default()
+        }),
+        "list" => import_list(block)?,
+        "checklist" => EditorNode::TaskList(TaskList {
+            list: items(block)?
+                .iter()
+                .map(|item| {
+                    Ok(TaskItem {
+                        text: plain_text(
+                            item.get("text").and_then(Value::as_str).unwrap_or_default(),
+                        )?,
+                        checked: item
+                            .get("checked")
+                            .and_then(Value::as_bool)
+                            .unwrap_or_default(),
+                        children: vec![],
+                    })
+                })
+                .collect::<Result<_, _>>()?,
+        }),
+        "code" => EditorNode::CodeBlock(CodeBlock {
+            language: block
+                .data
+                .get("language")
+                .and_then(Value::as_str)
+                .map(str::to_owned),
+            code: block.str("code").to_owned(),
+        }),
+        "delimiter" => EditorNode::HorizontalRule(HorizontalRule),
+        "table" => import_table(block)?,
+        "toggle" => {
+            let items = block
+                .data
+                .get("items")
+                .and_then(Value::as_u64)
+                .unwrap_or_default();
+            let content = (0..items)
+                .map(|_| {
+                    let nested = rest.next().ok_or_else(|| {
+                        ImportError::Invalid("toggle block without its items".to_owned())
+                    })?;
+                    import_block(nested, rest)
+                })
+                .collect::<Result<_, _>>()?;
+            EditorNode::Details(Details {
+                open: block.str("status") == "open",
+                summary: inline_from_html(block.str("text"))?,
+                content,
+            })
+        }
+        "raw" => EditorNode::Html(HtmlNode(block.str("html").to_owned())),
+        other => return Err(ImportError::Unsupported(format!("block {other}"))),
+    };
+    Ok(node)
+}
+
+fn items(block: &EditorJsBlock) -> Result<&Vec<Value>, ImportError> {
+    block
+        .data
+        .get("items")
+        .and_then(Value::as_array)
+        .ok_or_else(|| ImportError::Invalid(format!("{} block without items", block.kind)))
+}
+
+/// Handles both the flat string items of the original list tool and the
+/// `{"content": .., "meta": .., "items": ..}` objects of the nested list tool.
+fn import_list(block: &EditorJsBlock) -> Result<EditorNode, ImportError> {
+    let items = items(block)?;
+    match block.str("style") {
+        "checklist" => Ok(EditorNode::TaskList(TaskList {
+            list: items
+                .iter()
+                .map(import_nested_task)
+                .collect::<Result<_, _>>()?,
+        })),
+        "ordered" => {
+            let mut node = import_list_items("ordered", items)?;
+            if let (EditorNode::OrderedList(list), Some(meta)) = (&mut node, block.data.get("meta"))
+            {
+                list.start = meta
+                    .get("start")
+                    .and_then(Value::as_i64)
+                    .filter(|start| *start != 1);
+                list.style = match meta.get("counterType").and_then(Value::as_str) {
+                    Some("numeric") | None => NumberingStyle::Decimal,
+                    Some(other) => NumberingStyle::from_css(other).ok_or_else(|| {
+                        ImportError::Unsupported(format!("list counter type {other}"))
+                    })?,
+                };
+            }
+            Ok(node)
+        }
+        style => import_list_items(style, items),
+    }
+}
+
+/// Reads list items, their nested items becoming a list of the same style inside them.
+fn import_list_items(style: &str, items: &[Value]) -> Result<EditorNode, ImportError> {
+    let list = items
+        .iter()
+        .map(|item| {
+            let html = item
+                .as_str()
+                .or_else(|| item.get("content").and_then(Value::as_str))
+                .unwrap_or_default();
+            let mut content = vec![EditorNode::Paragraph(Paragraph {
+                elements: inline_from_html(html)?,
+            })];
+            let nested = item.get("items").and_then(Value::as_array);
+            if let Some(nested) = nested.filter(|nested| !nested.is_empty()) {
+                content.push(import_list_items(style, nested)?);
+            }
+            Ok(ListItem { content })
+        })
+        .collect::<Result<_, _>>()?;
+    Ok(match style {
+        "ordered" => EditorNode::OrderedList(OrderedList {
+            list,
+            ..Default::default()
+        }),
+        _ => EditorNode::BulletList(BulletList { list }),
+    })
+}
+
+/// Reads a checklist item of the nested list tool along with its subtasks.
+fn import_nested_task(item: &Value) -> Result<TaskItem, ImportError> {
+    Ok(TaskItem {
+        text: plain_text(
+            item.as_str()
+                .or_else(|| item.get("content").and_then(Value::as_str))
+                .unwrap_or_default(),
+        )?,
+        checked: item
+            .pointer("/meta/checked")
+            .and_then(Value::as_bool)
+            .unwrap_or_default(),
+        children: item
+            .get("items")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .map(import_nested_task)
+            .collect::<Result<_, _>>()?,
+    })
+}
+
+fn import_table(block: &EditorJsBlock) -> Result<EditorNode, ImportError> {
+    let with_headings = block
+        .data
+        .get("withHeadings")
+        .and_then(Value::as_bool)
+        .unwrap_or_default();
+    let rows = block
+        .data
+        .get("content")
+        .and_then(Value::as_array)
+        .ok_or_else(|| ImportError::Invalid("table block without content".to_owned()))?;
+    let rows = rows
+        .iter()
+        .enumerate()
+        .map(|(r, row)| {
+            let cells = row.as_array().map(Vec::as_slice).unwrap_or_default();
+            Ok(TableRow {
+                cells: cells
+                    .iter()
+                    .map(|cell| {
+                        let elements = inline_from_html(cell.as_str().unwrap_or_default())?;
+                        let content = if elements.is_empty() {
+                            vec![]
+                        } else {
+                            vec![EditorNode::Paragraph(Paragraph { elements })]
+                        };
+                        Ok(TableCell::new(with_headings && r == 0, content))
+                    })
+                    .collect::<Result<_, ImportError>>()?,
+            })
+        })
+        .collect::<Result<_, ImportError>>()?;
+    Ok(EditorNode::Table(Table { rows }))
+}
+
+/// Reduces an inline html string to its text for nodes that only hold plain text.
+fn plain_text(html: &str) -> Result<String, ImportError> {
+    Ok(inline_from_html(html)?
+        .iter()
+        .map(|node| match inline_parts(node) {
+            Some((text, _)) => text.to_owned(),
+            None => node.text(),
+        })
+        .collect())
+}
+
+/// Converts edita nodes into Editor.js data.
+///
+/// Opaque html nodes are written as blocks of the `raw` tool. The table tool has no merged
+/// cells, so tables are written as their grid with the slots covered by a span left empty.
+/// Collapsible sections are written as blocks of the toggle tool, followed by the `items`
+/// blocks of their body.
+pub fn to_editorjs(nodes: &[EditorNode]) -> EditorJsData {
+    let mut blocks = vec![];
+    for node in nodes {
+        export_node(node, &mut blocks);
+    }
+    EditorJsData {
+        time: None,
+        blocks,
+        version: None,
+    }
+}
+
+fn export_node(node: &EditorNode, blocks: &mut Vec<EditorJsBlock>) {
+    blocks.push(export_block(node));
+    if let EditorNode::Details(details) = node {
+        for node in &details.content {
+            export_node(node, blocks);
+        }
+    }
+}
+
+fn export_block(node: &EditorNode) -> EditorJsBlock {
+    match node {
+        EditorNode::HorizontalRule(_) => EditorJsBlock::new("delimiter", json!({})),
+        EditorNode::Html(HtmlNode(html)) => EditorJsBlock::new("raw", json!({ "html": html })),
+        EditorNode::Paragraph(paragraph) => EditorJsBlock::new(
+            "paragraph",
+            json!({ "text": inline_to_html(&paragraph.elements) }),
+        ),
+        EditorNode::Text(_)
+        | EditorNode::Bold(_)
+        | EditorNode::Italic(_)
+        | EditorNode::InlineCode(_)
+        | EditorNode::Underline(_)
+        | EditorNode::Strike(_)
+        | EditorNode::Subscript(_)
+        | EditorNode::Superscript(_)
+        | EditorNode::Highlight(_)
+        | EditorNode::TextColor(_)
+        | EditorNode::Link(_)
+        | EditorNode::Emoji(_)
+        | EditorNode::Mention(_)
+        | EditorNode::HardBreak(_) => EditorJsBlock::new(
+            "paragraph",
+            json!({ "text": inline_to_html(std::slice::from_ref(node)) }),
+        ),
+        EditorNode::Heading(header) => EditorJsBlock::new(
+            "header",
+            json!({ "text": escape(&header.text), "level": header.level }),
+        ),
+        EditorNode::CodeBlock(block) => {
+            let mut data = json!({ "code": block.code });
+            if let Some(language) = &block.language {
+                data["language"] = json!(language);
+            }
+            EditorJsBlock::new("code", data)
+        }
+        EditorNode::Image(image) => EditorJsBlock::new(
+            "image",
+            json!({
+                "file": { "url": image.src },
+                "caption": escape(&image.alt),
+                "withBorder": false,
+                "stretched": false,
+                "withBackground": false,
+            }),
+        ),
+        EditorNode::BlockQuote(quote) => EditorJsBlock::new(
+            "quote",
+            json!({ "text": inline_to_html(&quote.inline()), "caption": "", "alignment": "left" }),
+        ),
+        EditorNode::ListItem(item) => {
+            export_list("unordered", std::slice::from_ref(item), json!({}))
+        }
+        EditorNode::BulletList(list) => export_list("unordered", &list.list, json!({})),
+        EditorNode::OrderedList(list) => export_list("ordered", &list.list, ordered_meta(list)),
+        EditorNode::TaskItem(item) => export_checklist(std::slice::from_ref(item)),
+        EditorNode::TaskList(list) => export_checklist(&list.list),
+        EditorNode::Table(table) => export_table(table),
+        EditorNode::Details(details) => EditorJsBlock::new(
+            "toggle",
+            json!({
+                "text": inline_to_html(&details.summary),
+                "status": if details.open { "open" } else { "closed" },
+                "items": details.content.len(),
+            }),
+        ),
+    }
+}
+
+fn export_table(table: &Table) -> EditorJsBlock {
+    let content: Vec<Vec<String>> = table
+        .grid()
+        .iter()
+        .enumerate()
+        .map(|(r, slots)| {
+            slots
+                .iter()
+                .enumerate()
+                .map(|(c, slot)| match *slot {
+                    Some((row, i)) if table.slot((row, i)) == Some((r, c)) => {
+                        cell_html(&table.rows[row].cells[i])
+                    }
+                    _ => String::new(),
+                })
+                .collect()
+        })
+        .collect();
+    let with_headings = table.rows.first().is_some_and(TableRow::is_header);
+    EditorJsBlock::new(
+        "table",
+        json!({ "withHeadings": with_headings, "content": content }),
+    )
+}
+
+fn cell_html(cell: &TableCell) -> String {
+    cell.content
+        .iter()
+        .map(|node| match node {
+            EditorNode::Paragraph(paragraph) => inline_to_html(&paragraph.elements),
+            node => inline_to_html(std::slice::from_ref(node)),
+        })
+        .collect::<Vec<_>>()
+        .join("<br>")
+}
+
+/// The start number and counter type of the nested list tool, left empty for lists numbered
+/// from 1 in decimal. The tool cannot count down, so reversed lists lose their direction.
+fn ordered_meta(list: &OrderedList) -> Value {
+    if list.style.is_decimal() && list.first_number() == 1 {
+        return json!({});
+    }
+    let counter_type = if list.style.is_decimal() {
+        "numeric"
+    } else {
+        list.style.css()
+    };
+    json!({ "start": list.first_number(), "counterType": counter_type })
+}
+
+/// Uses the flat items of the original list tool unless there are nested lists or numbering
+/// options, which are written in the shape of the nested list tool.
+fn export_list(style: &str, items: &[ListItem], meta: Value) -> EditorJsBlock {
+    let has_meta = meta.as_object().is_some_and(|meta| !meta.is_empty());
+    if has_meta || items.iter().any(|item| item.sublists().next().is_some()) {
+        let items: Vec<Value> = items.iter().map(export_nested_item).collect();
+        let mut data = json!({ "style": style, "items": items });
+        if has_meta {
+            data["meta"] = meta;
+        }
+        return EditorJsBlock::new("list", data);
+    }
+    let items: Vec<String> = items
+        .iter()
+        .map(|item| inline_to_html(&item.inline()))
+        .collect();
+    EditorJsBlock::new("list", json!({ "style": style, "items": items }))
+}
+
+/// The nested list tool has a single style per block, so nested lists take the style of
+/// the outer one.
+fn export_nested_item(item: &ListItem) -> Value {
+    let items: Vec<Value> = item
+        .sublists()
+        .flat_map(|list| match list {
+            EditorNode::BulletList(BulletList { list })
+            | EditorNode::OrderedList(OrderedList { list, .. }) => list.as_slice(),
+            _ => &[],
+        })
+        .map(export_nested_item)
+        .collect();
+    json!({
+        "content": inline_to_html(&item.inline()),
+        "meta": {},
+        "items": items,
+    })
+}
+
+/// Uses the checklist tool unless there are subtasks, which only the checklist style of the
+/// nested list tool can hold.
+fn export_checklist(items: &[TaskItem]) -> EditorJsBlock {
+    if items.iter().any(|item| !item.children.is_empty()) {
+        let items: Vec<Value> = items.iter().map(export_nested_task).collect();
+        return EditorJsBlock::new("list", json!({ "style": "checklist", "items": items }));
+    }
+    let items: Vec<Value> = items
+        .iter()
+        .map(|item| json!({ "text": escape(&item.text), "checked": item.checked }))
+        .collect();
+    EditorJsBlock::new("checklist", json!({ "items": items }))
+}
+
+fn export_nested_task(item: &TaskItem) -> Value {
+    let items: Vec<Value> = item.children.iter().map(export_nested_task).collect();
+    json!({
+        "content": escape(&item.text),
+        "meta": { "checked": item.checked },
+        "items": items,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DATA: &str = r#"{
+        "time": 1700000000000,
+        "blocks": [
+            { "id": "a", "type": "header", "data": { "text": "Title", "level": 2 } },
+            { "id": "b", "type": "paragraph", "data": { "text": "Hello <b>world</b>" } },
+            { "id": "c", "type": "list", "data": { "style": "ordered", "meta": { "start": 2, "counterType": "upper-roman" }, "items": [
+                { "content": "one", "meta": {}, "items": [{ "content": "nested", "meta": {}, "items": [] }] },
+                { "content": "two", "meta": {}, "items": [] }
+            ] } },
+            { "id": "d", "type": "checklist", "data": { "items": [{ "text": "done", "checked": true }] } },
+            { "id": "e", "type": "quote", "data": { "text": "Quoted", "caption": "", "alignment": "left" } },
+            { "id": "f", "type": "image", "data": { "file": { "url": "a.png" }, "caption": "A" } },
+            { "id": "g", "type": "toggle", "data": { "text": "More", "status": "open", "items": 1 } },
+            { "id": "h", "type": "paragraph", "data": { "text": "Hidden" } }
+        ],
+        "version": "2.28.0"
+    }"#;
+
+    #[test]
+    fn test_editorjs_round_trip() {
+        let data: EditorJsData = serde_json::from_str(DATA).unwrap();
+        let nodes = from_editorjs(&data).unwrap();
+        assert_eq!(nodes.len(), 7);
+        assert!(matches!(&nodes[2], EditorNode::OrderedList(l) if l.marker(1) == "III."));
+        assert!(matches!(&nodes[6], EditorNode::Details(d) if d.open && d.content.len() == 1));
+        let exported = to_editorjs(&nodes);
+        assert_eq!(
+            exported.blocks[1].data,
+            json!({ "text": "Hello <b>world</b>" })
+        );
+        assert_eq!(exported.blocks[2].data, data.blocks[2].data);
+        assert_eq!(exported.blocks.len(), data.blocks.len());
+        assert_eq!(from_editorjs(&exported).unwrap().len(), nodes.len());
+    }
+
+    #[test]
+    fn test_unknown_block_is_reported() {
+        let data: EditorJsData =
+            serde_json::from_str(r#"{ "blocks": [{ "type": "embed", "data": {} }] }"#).unwrap();
+        match from_editorjs(&data) {
+            Err(ImportError::Unsupported(kind)) => assert_eq!(kind, "block embed"),
+            other => panic!("expected an unsupported block, got {other:?}"),
+        }
+    }
+}
@@ -0,0 +1,620 @@
+//! Export of edita nodes to html, and helpers for formats that embed inline html in their
+//! text fields.
+use crate::editor::HtmlNode;
+use crate::formats::{inline_parts, ImportError, Mark};
+use crate::nodes::{
+    sanitize_color, sanitize_href, EditorNode, Emoji, HardBreak, HeadingAnchors, Highlight, Image,
+    Link, Mention, OrderedList, TableCell, TaskItem, TextColor,
+};
+
+/// Writes nodes as an html fragment, in the markup the editor renders them with.
+///
+/// Headings get unique ids, repeated slugs getting a `-1`, `-2` suffix as in the editor.
+pub fn to_html(nodes: &[EditorNode]) -> String {
+    blocks_to_html(nodes, &mut HeadingAnchors::default())
+}
+
+fn blocks_to_html(nodes: &[EditorNode], anchors: &mut HeadingAnchors) -> String {
+    let mut html = String::new();
+    for node in nodes {
+        block_to_html(node, &mut html, anchors);
+    }
+    html
+}
+
+fn block_to_html(node: &EditorNode, html: &mut String, anchors: &mut HeadingAnchors) {
+    match node {
+        EditorNode::Html(HtmlNode(raw)) => html.push_str(raw),
+        EditorNode::Paragraph(paragraph) => {
+            html.push_str(&format!("<p>{}</p>", inline_to_html(&paragraph.elements)))
+        }
+        EditorNode::Text(_)
+        | EditorNode::Bold(_)
+        | EditorNode::Italic(_)
+        | EditorNode::InlineCode(_)
+        | EditorNode::Underline(_)
+        | EditorNode::Strike(_)
+        | EditorNode::Subscript(_)
+        | EditorNode::Superscript(_)
+        | EditorNode::Highlight(_)
+        | EditorNode::TextColor(_)
+        | EditorNode::Link(_)
+        | EditorNode::Emoji(_)
+        | EditorNode::Mention(_)
+        | EditorNode::HardBreak(_) => html.push_str(&inline_to_html(std::slice::from_ref(node))),
+        EditorNode::Heading(header) => html.push_str(&format!(
+            r#"<{tag} id="{}">{}</{tag}>"#,
+            escape(&anchors.next(&header.text)),
+            escape(&header.text),
+            tag = header.tag()
+        )),
+        EditorNode::CodeBlock(block) => {
+            let class = block
+                .language
+                .as_ref()
+                .map(|language| format!(r#" class="language-{}""#, escape(language)))
+                .unwrap_or_default();
+            html.push_str(&format!(
+                "<pre><code{class}>{}</code></pre>",
+                escape(&block.code)
+            ));
+        }
+        EditorNode::HorizontalRule(_) => html.push_str("<hr>"),
+        EditorNode::Image(image) => image_to_html(image, html),
+        EditorNode::BlockQuote(quote) => html.push_str(&format!(
+            "<blockquote>{}</blockquote>",
+            blocks_to_html(&quote.content, anchors)
+        )),
+        EditorNode::ListItem(item) => {
+            html.push_str("<li>");
+            html.push_str(&blocks_to_html(&item.content, anchors));
+            html.push_str("</li>");
+        }
+        EditorNode::BulletList(list) => {
+            html.push_str("<ul>");
+            for item in &list.list {
+                html.push_str(&format!(
+                    "<li>{}</li>",
+                    blocks_to_html(&item.content, anchors)
+                ));
+            }
+            html.push_str("</ul>");
+        }
+        EditorNode::OrderedList(list) => {
+            html.push_str(&format!("<ol{}>", ordered_list_attributes(list)));
+            for item in &list.list {
+                html.push_str(&format!(
+                    "<li>{}</li>",
+                    blocks_to_html(&item.content, anchors)
+                ));
+            }
+            html.push_str("</ol>");
+        }
+        EditorNode::TaskItem(item) => task_to_html(item, html),
+        EditorNode::TaskList(list) => {
+            html.push_str(r#"<ul data-type="taskList">"#);
+            for item in &list.list {
+                task_to_html(item, html);
+            }
+            html.push_str("</ul>");
+        }
+        EditorNode::Table(table) => {
+            let headers = table.rows.iter().take_while(|row| row.is_header()).count();
+            let (head, body) = table.rows.split_at(headers);
+            html.push_str("<table>");
+            for (section, rows) in [("thead", head), ("tbody", body)] {
+                if rows.is_empty() {
+                    continue;
+                }
+                html.push_str(&format!("<{section}>"));
+                for row in rows {
+                    html.push_str("<tr>");
+                    for cell in &row.cells {
+                        cell_to_html(cell, html, anchors);
+                    }
+                    html.push_str("</tr>");
+                }
+                html.push_str(&format!("</{section}>"));
+            }
+            html.push_str("</table>");
+        }
+        EditorNode::Details(details) => html.push_str(&format!(
+            "<details{}><summary>{}</summary>{}</details>",
+            if details.open { " open" } else { "" },
+            inline_to_html(&details.summary),
+            blocks_to_html(&details.content, anchors)
+        )),
+    }
+}
+
+/// The `start`, `type` and `reversed` attributes of an `<ol>`, each left out when it has
+/// its default value.
+fn ordered_list_attributes(list: &OrderedList) -> String {
+    let mut attributes = String::new();
+    if let Some(start) = list.start {
+        attributes.push_str(&format!(r#" start="{start}""#));
+    }
+    if !list.style.is_decimal() {
+        attributes.push_str(&format!(r#" type="{}""#, list.style.type_attribute()));
+    }
+    if list.reversed {
+        attributes.push_str(" reversed");
+    }
+    attributes
+}
+
+/// Writes an image as `<img>`, in a `<figure>` when it has a caption.
+fn image_to_html(image: &Image, html: &mut String) {
+    let mut attributes = format!(
+        r#" src="{}" alt="{}""#,
+        escape(&image.src),
+        escape(&image.alt)
+    );
+    if !image.title.is_empty() {
+        attributes.push_str(&format!(r#" title="{}""#, escape(&image.title)));
+    }
+    for (name, size) in [("width", image.width), ("height", image.height)] {
+        if let Some(size) = size {
+            attributes.push_str(&format!(r#" {name}="{size}""#));
+        }
+    }
+    let align = image
+        .align
+        .map(|align| format!(r#" data-align="{}""#, align.attribute()))
+        .unwrap_or_default();
+    if image.caption.is_empty() {
+        html.push_str(&format!("<img{attributes}{align}>"));
+    } else {
+        html.push_str(&format!(
+            "<figure{align}><img{attributes}><figcaption>{}</figcaption></figure>",
+            inline_to_html(&image.caption)
+        ));
+    }
+}
+
+fn task_to_html(item: &TaskItem, html: &mut String) {
+    html.push_str(&format!(
+        r#"<li data-type="taskItem" data-checked="{}"><label><input type="checkbox"{}></label><div><p>{}</p>"#,
+        item.checked,
+        if item.checked { " checked" } else { "" },
+        escape(&item.text)
+    ));
+    if !item.children.is_empty() {
+        html.push_str(r#"<ul data-type="taskList">"#);
+        for child in &item.children {
+            task_to_html(child, html);
+        }
+        html.push_str("</ul>");
+    }
+    html.push_str("</div></li>");
+}
+
+fn cell_to_html(cell: &TableCell, html: &mut String, anchors: &mut HeadingAnchors) {
+    let tag = if cell.header { "th" } else { "td" };
+    let mut attributes = String::new();
+    if cell.colspan > 1 {
+        attributes.push_str(&format!(r#" colspan="{}""#, cell.colspan));
+    }
+    if cell.rowspan > 1 {
+        attributes.push_str(&format!(r#" rowspan="{}""#, cell.rowspan));
+    }
+    html.push_str(&format!(
+        "<{tag}{attributes}>{}</{tag}>",
+        blocks_to_html(&cell.content, anchors)
+    ));
+}
+
+pub(crate) fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+pub(crate) fn unescape(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        unescaped.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let entity = rest.find(';').map(|end| &rest[1..end]);
+        let decoded = entity.and_then(|entity| match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "nbsp" => Some('\u{a0}'),
+            _ => entity
+                .strip_prefix("#x")
+                .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                .or_else(|| entity.strip_prefix('#').and_then(|dec| dec.parse().ok()))
+                .and_then(char::from_u32),
+        });
+        match (entity, decoded) {
+            (Some(entity), Some(c)) => {
+                unescaped.push(c);
+                rest = &rest[entity.len() + 2..];
+            }
+            _ => {
+                unescaped.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    unescaped.push_str(rest);
+    unescaped
+}
+
+/// Strips the tags from an html fragment, keeping its text.
+pub(crate) fn text_content(html: &str) -> String {
+    let mut text = String::new();
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        text.push_str(&rest[..start]);
+        rest = rest[start..]
+            .find('>')
+            .map_or("", |end| &rest[start + end + 1..]);
+    }
+    text.push_str(rest);
+    unescape(&text)
+}
+
+/// Writes inline nodes as an html fragment.
+pub(crate) fn inline_to_html(nodes: &[EditorNode]) -> String {
+    let mut html = String::new();
+    for node in nodes {
+        match node {
+            EditorNode::Emoji(emoji) => {
+                html.push_str(&format!(
+                    r#"<span data-type="emoji" data-shortcode="{}">{}</span>"#,
+                    escape(&emoji.shortcode),
+                    escape(&emoji.emoji)
+                ));
+                continue;
+            }
+            EditorNode::Mention(mention) => {
+                html.push_str(&format!(
+                    r#"<span data-type="mention" data-id="{}" data-label="{}">{}</span>"#,
+                    escape(&mention.id),
+                    escape(&mention.label),
+                    escape(&mention.text())
+                ));
+                continue;
+            }
+            EditorNode::HardBreak(_) => {
+                html.push_str("<br>");
+                continue;
+            }
+            EditorNode::Link(link) => {
+                html.push_str(&link_to_html(link));
+                continue;
+            }
+            EditorNode::Highlight(Highlight {
+                text,
+                color: Some(color),
+            }) => {
+                // Nodes can be built or deserialized with any colour, so it is checked
+                // again before going into the style attribute.
+                match sanitize_color(color) {
+                    Some(color) => html.push_str(&format!(
+                        r#"<mark data-color="{color}" style="background-color: {color}">{}</mark>"#,
+                        escape(text),
+                        color = escape(&color)
+                    )),
+                    None => html.push_str(&format!("<mark>{}</mark>", escape(text))),
+                }
+                continue;
+            }
+            EditorNode::TextColor(TextColor { text, color }) => {
+                match sanitize_color(color) {
+                    Some(color) => html.push_str(&format!(
+                        r#"<span style="color: {}">{}</span>"#,
+                        escape(&color),
+                        escape(text)
+                    )),
+                    None => html.push_str(&escape(text)),
+                }
+                continue;
+            }
+            _ => {}
+        }
+        match inline_parts(node) {
+            Some((text, None)) => html.push_str(&escape(text)),
+            Some((text, Some(mark))) => {
+                let tag = match mark {
+                    Mark::Subscript => "sub",
+                    Mark::Superscript => "sup",
+                    Mark::Strike => "s",
+                    Mark::Underline => "u",
+                    Mark::Highlight => "mark",
+                    Mark::Italic => "i",
+                    Mark::Bold => "b",
+                    Mark::Code => "code",
+                };
+                html.push_str(&format!("<{tag}>{}</{tag}>", escape(text)));
+            }
+            None => {
+                if let EditorNode::Html(HtmlNode(raw)) = node {
+                    html.push_str(raw);
+                }
+            }
+        }
+    }
+    html
+}
+
+/// Writes a link as an `<a>` element, opening pages in new tabs without access to the
+/// editor's window.
+pub(crate) fn link_to_html(link: &Link) -> String {
+    let mut attributes = format!(r#" href="{}""#, escape(&link.href));
+    if !link.title.is_empty() {
+        attributes.push_str(&format!(r#" title="{}""#, escape(&link.title)));
+    }
+    if !link.target.is_empty() {
+        attributes.push_str(&format!(
+            r#" target="{}" rel="noopener noreferrer""#,
+            escape(&link.target)
+        ));
+    }
+    format!("<a{attributes}>{}</a>", escape(&link.text))
+}
+
+/// The unescaped value of a double quoted attribute of an html tag.
+fn attribute(tag: &str, name: &str) -> Option<String> {
+    let mut rest = tag;
+    while let Some(start) = rest.find(&format!("{name}=\"")) {
+        let before = rest[..start].chars().last();
+        rest = &rest[start + name.len() + 2..];
+        if before.is_some_and(char::is_whitespace) {
+            return rest.split_once('"').map(|(value, _)| unescape(value));
+        }
+    }
+    None
+}
+
+/// The value of a property of an inline `style` attribute.
+fn style_property(style: &str, name: &str) -> Option<String> {
+    style.split(';').find_map(|declaration| {
+        let (property, value) = declaration.split_once(':')?;
+        property
+            .trim()
+            .eq_ignore_ascii_case(name)
+            .then(|| value.trim().to_owned())
+    })
+}
+
+/// A colour set by an inline tag.
+#[derive(Clone)]
+enum Color {
+    Highlight(String),
+    Text(String),
+}
+
+/// Parses an html fragment made of text and inline formatting tags.
+///
+/// Links hold plain text, so formatting inside them is dropped, as are links whose href is
+/// not allowed. A colour only applies to text without a stronger mark than a highlight.
+pub(crate) fn inline_from_html(html: &str) -> Result<Vec<EditorNode>, ImportError> {
+    let mut nodes = vec![];
+    let mut marks: Vec<Option<Mark>> = vec![];
+    let mut colors: Vec<Option<Color>> = vec![];
+    let mut link: Option<Link> = None;
+    let mut rest = html;
+    fn flush(
+        text: &str,
+        marks: &[Option<Mark>],
+        colors: &[Option<Color>],
+        link: &mut Option<Link>,
+        nodes: &mut Vec<EditorNode>,
+    ) {
+        if let Some(link) = link {
+            link.text.push_str(&unescape(text));
+        } else if !text.is_empty() {
+            let text = unescape(text);
+            let mark = marks.iter().copied().max().flatten();
+            let color = colors.iter().rev().flatten().next().cloned();
+            nodes.push(match color {
+                Some(Color::Highlight(color)) if mark <= Some(Mark::Highlight) => {
+                    EditorNode::Highlight(Highlight {
+                        text,
+                        color: Some(color),
+                    })
+                }
+                Some(Color::Text(color)) if mark.is_none() => {
+                    EditorNode::TextColor(TextColor { text, color })
+                }
+                _ => Mark::node(mark, text),
+            });
+        }
+    }
+    while let Some(start) = rest.find('<') {
+        flush(&rest[..start], &marks, &colors, &mut link, &mut nodes);
+        let end = rest[start..]
+            .find('>')
+            .map(|end| start + end)
+            .ok_or_else(|| ImportError::Invalid(format!("unterminated tag in {html}")))?;
+        let tag = rest[start + 1..end].trim_end_matches('/').trim();
+        rest = &rest[end + 1..];
+        let closing = tag.starts_with('/');
+        let name = tag
+            .trim_start_matches('/')
+            .split_whitespace()
+            .next()
+            .unwrap_or_default()
+            .to_lowercase();
+        let mut color = None;
+        let mark = match name.as_str() {
+            "br" => {
+                nodes.push(EditorNode::HardBreak(HardBreak));
+                continue;
+            }
+            "b" | "strong" => Some(Mark::Bold),
+            "i" | "em" => Some(Mark::Italic),
+            "code" => Some(Mark::Code),
+            "u" | "ins" => Some(Mark::Underline),
+            "s" | "strike" | "del" => Some(Mark::Strike),
+            "sub" => Some(Mark::Subscript),
+            "sup" => Some(Mark::Superscript),
+            "mark" => {
+                color = attribute(tag, "data-color")
+                    .and_then(|color| sanitize_color(&color))
+                    .map(Color::Highlight);
+                Some(Mark::Highlight)
+            }
+            "span" if !closing && tag.contains(r#"data-type="emoji""#) => {
+                let end = rest
+                    .find("</span>")
+                    .ok_or_else(|| ImportError::Invalid(format!("unterminated emoji in {html}")))?;
+                let shortcode = tag
+                    .split_once(r#"data-shortcode=""#)
+                    .and_then(|(_, value)| value.split_once('"'))
+                    .map_or("", |(shortcode, _)| shortcode);
+                nodes.push(EditorNode::Emoji(Emoji {
+                    shortcode: unescape(shortcode),
+                    emoji: unescape(&rest[..end]),
+                }));
+                rest = &rest[end + "</span>".len()..];
+                continue;
+            }
+            "span" if !closing && tag.contains(r#"data-type="mention""#) => {
+                let end = rest.find("</span>").ok_or_else(|| {
+                    ImportError::Invalid(format!("unterminated mention in {html}"))
+                })?;
+                let label = attribute(tag, "data-label")
+                    .unwrap_or_else(|| unescape(rest[..end].trim_start_matches('@')));
+                nodes.push(EditorNode::Mention(Mention {
+                    id: attribute(tag, "data-id").unwrap_or_default(),
+                    label,
+                }));
+                rest = &rest[end + "</span>".len()..];
+                continue;
+            }
+            "a" if closing => {
+                if let Some(link) = link.take().filter(|link| !link.text.is_empty()) {
+                    nodes.push(EditorNode::Link(link));
+                }
+                continue;
+            }
+            "a" => {
+                let href = attribute(tag, "href").and_then(|href| sanitize_href(&href));
+                link = href.map(|href| Link {
+                    href,
+                    title: attribute(tag, "title").unwrap_or_default(),
+                    target: attribute(tag, "target").unwrap_or_default(),
+                    ..Default::default()
+                });
+                continue;
+            }
+            "span" => {
+                color = attribute(tag, "style")
+                    .and_then(|style| style_property(&style, "color"))
+                    .and_then(|color| sanitize_color(&color))
+                    .map(Color::Text);
+                None
+            }
+            other => return Err(ImportError::Unsupported(format!("inline tag <{other}>"))),
+        };
+        if closing {
+            marks.pop();
+            colors.pop();
+        } else {
+            marks.push(mark);
+            colors.push(color);
+        }
+    }
+    flush(rest, &marks, &colors, &mut link, &mut nodes);
+    if let Some(link) = link.filter(|link| !link.text.is_empty()) {
+        nodes.push(EditorNode::Link(link));
+    }
+    Ok(nodes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nodes::{BlockQuote, Header, ListItem, NumberingStyle};
+
+    #[test]
+    fn test_inline_html_round_trip() {
+        let html = r#"a &amp; <b>b</b><br><em>c</em><span data-type="emoji" data-shortcode="tada">🎉</span><span data-type="mention" data-id="u1" data-label="ada">@ada</span>"#;
+        let nodes = inline_from_html(html).unwrap();
+        assert_eq!(nodes.len(), 6);
+        assert_eq!(
+            inline_to_html(&nodes),
+            html.replace("<em>c</em>", "<i>c</i>")
+        );
+        assert!(inline_from_html("<blink>x</blink>").is_err());
+    }
+
+    #[test]
+    fn test_inline_html_marks() {
+        let html = r##"<u>a</u><s>b</s><sub>c</sub><sup>d</sup><mark>e</mark><mark data-color="#ff0" style="background-color: #ff0">f</mark><span style="color: red">g</span>"##;
+        let nodes = inline_from_html(html).unwrap();
+        assert_eq!(nodes.len(), 7);
+        assert_eq!(inline_to_html(&nodes), html);
+        let nested = inline_from_html(r#"<span style="color: red"><b>x</b></span>"#).unwrap();
+        assert!(matches!(&nested[0], EditorNode::Bold(_)));
+        let hostile = [
+            EditorNode::Highlight(Highlight {
+                text: "a".to_owned(),
+                color: Some("red; background: url(x)".to_owned()),
+            }),
+            EditorNode::TextColor(TextColor {
+                text: "b".to_owned(),
+                color: "red\" onmouseover=\"x".to_owned(),
+            }),
+        ];
+        assert_eq!(inline_to_html(&hostile), "<mark>a</mark>b");
+    }
+
+    #[test]
+    fn test_inline_html_links() {
+        let html = r#"see <a href="https://a.io/?q=1&amp;r=2" title="A" target="_blank" rel="noopener noreferrer">a</a>"#;
+        let nodes = inline_from_html(html).unwrap();
+        assert!(
+            matches!(&nodes[1], EditorNode::Link(link) if link.href == "https://a.io/?q=1&r=2" && link.target == "_blank")
+        );
+        assert_eq!(inline_to_html(&nodes), html);
+        let unsafe_link = inline_from_html(r#"<a href="javascript:alert(1)">x</a>"#).unwrap();
+        assert!(matches!(&unsafe_link[0], EditorNode::Text(text) if text.text == "x"));
+    }
+
+    #[test]
+    fn test_ordered_list_html() {
+        let list = OrderedList {
+            list: vec![ListItem::from_text("a & b")],
+            start: Some(3),
+            style: NumberingStyle::UpperAlpha,
+            reversed: true,
+        };
+        assert_eq!(
+            to_html(&[EditorNode::OrderedList(list)]),
+            r#"<ol start="3" type="A" reversed><li><p>a &amp; b</p></li></ol>"#
+        );
+    }
+
+    #[test]
+    fn test_repeated_headings_get_unique_ids() {
+        let heading = |text: &str| {
+            EditorNode::Heading(Header {
+                text: text.to_owned(),
+                level: 2,
+            })
+        };
+        let quote = EditorNode::BlockQuote(BlockQuote {
+            content: vec![heading("Notes")],
+        });
+        assert_eq!(
+            to_html(&[heading("Notes"), quote, heading("Notes")]),
+            r#"<h2 id="notes">Notes</h2><blockquote><h2 id="notes-1">Notes</h2></blockquote><h2 id="notes-2">Notes</h2>"#
+        );
+    }
+}
@@ -0,0 +1,537 @@
+//! Export of edita nodes to LaTeX.
+use crate::editor::HtmlNode;
+use crate::formats::html::text_content;
+use crate::formats::{hex_color, inline_parts, Mark};
+use crate::nodes::{EditorNode, Highlight, Image, ListItem, Table, TableCell, TaskItem, TextColor};
+
+const PREAMBLE: &str = r"\documentclass{article}
+\usepackage[T1]{fontenc}
+\usepackage[utf8]{inputenc}
+\usepackage{graphicx}
+\usepackage{amssymb}
+\usepackage{multirow}
+\usepackage{hyperref}
+\usepackage{xcolor}
+\usepackage[normalem]{ulem}
+\usepackage{fancyvrb}
+";
+
+/// The colours `xcolor` names without loading extra colour sets.
+const XCOLOR_NAMES: &[&str] = &[
+    "black",
+    "blue",
+    "brown",
+    "cyan",
+    "darkgray",
+    "gray",
+    "green",
+    "lightgray",
+    "lime",
+    "magenta",
+    "olive",
+    "orange",
+    "pink",
+    "purple",
+    "red",
+    "teal",
+    "violet",
+    "white",
+    "yellow",
+];
+
+/// Writes nodes as a LaTeX fragment to be included in a document.
+///
+/// The fragment relies on the `graphicx`, `amssymb`, `multirow`, `hyperref`, `xcolor`,
+/// `ulem` and `fancyvrb` packages. Image sources are used as file paths, images embedded as
+/// `data:` urls are written as their caption and remote images as a comment with their url
+/// followed by their caption. Colours other than hex codes and the basic `xcolor`
+/// names are dropped, highlights falling back to yellow.
+pub fn to_latex(nodes: &[EditorNode]) -> String {
+    let mut latex = String::new();
+    for node in nodes {
+        block(node, &mut latex, false);
+    }
+    latex
+}
+
+/// Writes nodes as a standalone `article` document.
+pub fn to_latex_document(nodes: &[EditorNode]) -> String {
+    format!(
+        "{PREAMBLE}\n\\begin{{document}}\n\n{}\\end{{document}}\n",
+        to_latex(nodes)
+    )
+}
+
+/// Escapes the characters that have a special meaning in LaTeX.
+pub fn escape_latex(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str(r"\textbackslash{}"),
+            '~' => escaped.push_str(r"\textasciitilde{}"),
+            '^' => escaped.push_str(r"\textasciicircum{}"),
+            '#' | '$' | '%' | '&' | '_' | '{' | '}' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\n' => escaped.push_str("\\newline\n"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// The `xcolor` model and value of a css colour, eg. `[HTML]{FF0000}` for `#f00`.
+fn xcolor(color: &str) -> Option<String> {
+    if color.starts_with('#') {
+        return hex_color(color).map(|hex| format!("[HTML]{{{hex}}}"));
+    }
+    let name = color.to_ascii_lowercase();
+    XCOLOR_NAMES
+        .contains(&name.as_str())
+        .then(|| format!("{{{name}}}"))
+}
+
+/// Escapes the characters of a url that `\href` does not take as they are.
+fn escape_url(url: &str) -> String {
+    let mut escaped = String::with_capacity(url.len());
+    for c in url.chars() {
+        match c {
+            '#' | '%' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\\' => escaped.push_str(r"\%5C"),
+            '{' => escaped.push_str(r"\%7B"),
+            '}' => escaped.push_str(r"\%7D"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Writes a block node, `nested` telling whether it is inside an environment such as a
+/// `quote`, where floats are not allowed.
+fn block(node: &EditorNode, latex: &mut String, nested: bool) {
+    match node {
+        EditorNode::HorizontalRule(_) => latex.push_str("\\noindent\\rule{\\linewidth}{0.4pt}\n\n"),
+        EditorNode::Html(HtmlNode(html)) => {
+            latex.push_str(&escape_latex(&text_content(html)));
+            latex.push_str("\n\n");
+        }
+        EditorNode::Paragraph(paragraph) => {
+            latex.push_str(&inline(&paragraph.elements));
+            latex.push_str("\n\n");
+        }
+        EditorNode::Text(_)
+        | EditorNode::Bold(_)
+        | EditorNode::Italic(_)
+        | EditorNode::InlineCode(_)
+        | EditorNode::Underline(_)
+        | EditorNode::Strike(_)
+        | EditorNode::Subscript(_)
+        | EditorNode::Superscript(_)
+        | EditorNode::Highlight(_)
+        | EditorNode::TextColor(_)
+        | EditorNode::Link(_)
+        | EditorNode::Emoji(_)
+        | EditorNode::Mention(_)
+        | EditorNode::HardBreak(_) => {
+            latex.push_str(&inline(std::slice::from_ref(node)));
+            latex.push_str("\n\n");
+        }
+        EditorNode::Heading(header) => {
+            let command = match header.level {
+                0 | 1 => "section",
+                2 => "subsection",
+                3 => "subsubsection",
+                4 => "paragraph",
+                _ => "subparagraph",
+            };
+            latex.push_str(&format!(
+                "\\{command}{{{}}}\n\n",
+                escape_latex(&header.text)
+            ));
+        }
+        EditorNode::CodeBlock(block) => code_block(&block.code, latex),
+        EditorNode::Image(image) if nested => centered_image(image, latex),
+        EditorNode::Image(image) => figure(image, latex),
+        EditorNode::BlockQuote(quote) => {
+            latex.push_str("\\begin{quote}\n");
+            for node in &quote.content {
+                block(node, latex, true);
+            }
+            latex.push_str("\\end{quote}\n\n");
+        }
+        EditorNode::ListItem(item) => {
+            list_items("itemize", std::slice::from_ref(item), latex);
+            latex.push('\n');
+        }
+        EditorNode::BulletList(_) | EditorNode::OrderedList(_) => {
+            list(node, latex);
+            latex.push('\n');
+        }
+        EditorNode::TaskItem(item) => tasks(std::slice::from_ref(item), latex),
+        EditorNode::TaskList(task_list) => tasks(&task_list.list, latex),
+        EditorNode::Table(table) => tabular(table, latex),
+        // LaTeX pages cannot collapse, so the section is always shown expanded.
+        EditorNode::Details(details) => {
+            latex.push_str(&format!("\\textbf{{{}}}\n\n", inline(&details.summary)));
+            for node in &details.content {
+                block(node, latex, nested);
+            }
+        }
+    }
+}
+
+fn inline(nodes: &[EditorNode]) -> String {
+    let mut latex = String::new();
+    for node in nodes {
+        match (inline_parts(node), node) {
+            (Some((text, mark)), _) => {
+                let text = escape_latex(text);
+                match mark {
+                    Some(Mark::Bold) => latex.push_str(&format!("\\textbf{{{text}}}")),
+                    Some(Mark::Italic) => latex.push_str(&format!("\\emph{{{text}}}")),
+                    Some(Mark::Code) => latex.push_str(&format!("\\texttt{{{text}}}")),
+                    Some(Mark::Underline) => latex.push_str(&format!("\\uline{{{text}}}")),
+                    Some(Mark::Strike) => latex.push_str(&format!("\\sout{{{text}}}")),
+                    Some(Mark::Subscript) => latex.push_str(&format!("\\textsubscript{{{text}}}")),
+                    Some(Mark::Superscript) => {
+                        latex.push_str(&format!("\\textsuperscript{{{text}}}"))
+                    }
+                    Some(Mark::Highlight) => {
+                        latex.push_str(&format!("\\colorbox{{yellow}}{{{text}}}"))
+                    }
+                    None => latex.push_str(&text),
+                }
+            }
+            (None, EditorNode::Link(link)) => latex.push_str(&format!(
+                "\\href{{{}}}{{{}}}",
+                escape_url(&link.href),
+                escape_latex(&link.text)
+            )),
+            (None, EditorNode::Highlight(Highlight { text, color })) => {
+                let color = color
+                    .as_deref()
+                    .and_then(xcolor)
+                    .unwrap_or_else(|| "{yellow}".to_owned());
+                latex.push_str(&format!("\\colorbox{color}{{{}}}", escape_latex(text)))
+            }
+            (None, EditorNode::TextColor(TextColor { text, color })) => match xcolor(color) {
+                Some(color) => {
+                    latex.push_str(&format!("\\textcolor{color}{{{}}}", escape_latex(text)))
+                }
+                None => latex.push_str(&escape_latex(text)),
+            },
+            (None, EditorNode::HardBreak(_)) => latex.push_str("\\newline\n"),
+            (None, EditorNode::Html(HtmlNode(html))) => {
+                latex.push_str(&escape_latex(&text_content(html)))
+            }
+            (None, other) => latex.push_str(&escape_latex(&other.text())),
+        }
+    }
+    latex
+}
+
+fn list(list: &EditorNode, latex: &mut String) {
+    match list {
+        EditorNode::BulletList(bullets) => list_items("itemize", &bullets.list, latex),
+        EditorNode::OrderedList(ordered) => list_items("enumerate", &ordered.list, latex),
+        _ => {}
+    }
+}
+
+/// Writes list items, their nested lists in an environment inside the item.
+fn list_items(environment: &str, items: &[ListItem], latex: &mut String) {
+    latex.push_str(&format!("\\begin{{{environment}}}\n"));
+    for item in items {
+        latex.push_str(&format!("  \\item {}\n", inline(&item.inline())));
+        for sublist in item.sublists() {
+            list(sublist, latex);
+        }
+    }
+    latex.push_str(&format!("\\end{{{environment}}}\n"));
+}
+
+fn tasks(items: &[TaskItem], latex: &mut String) {
+    task_items(items, latex);
+    latex.push('\n');
+}
+
+/// Writes tasks as an `itemize`, subtasks in an `itemize` inside their task's item.
+fn task_items(items: &[TaskItem], latex: &mut String) {
+    latex.push_str("\\begin{itemize}\n");
+    for item in items {
+        let marker = if item.checked {
+            r"$\boxtimes$"
+        } else {
+            r"$\square$"
+        };
+        latex.push_str(&format!(
+            "  \\item[{marker}] {}\n",
+            escape_latex(&item.text)
+        ));
+        if !item.children.is_empty() {
+            task_items(&item.children, latex);
+        }
+    }
+    latex.push_str("\\end{itemize}\n");
+}
+
+/// Writes a table as a `tabular`, spans becoming `\multicolumn` and `\multirow` cells.
+fn tabular(table: &Table, latex: &mut String) {
+    let grid = table.grid();
+    let width = grid.first().map_or(0, Vec::len);
+    let headers = table.rows.iter().take_while(|row| row.is_header()).count();
+    latex.push_str(&format!(
+        "\\begin{{tabular}}{{|{}}}\n  \\hline\n",
+        "l|".repeat(width)
+    ));
+    for (r, slots) in grid.iter().enumerate() {
+        let mut cells = vec![];
+        for (c, slot) in slots.iter().enumerate() {
+            let Some((row, i)) = *slot else {
+                cells.push(String::new());
+                continue;
+            };
+            let cell = &table.rows[row].cells[i];
+            let Some((top, left)) = table.slot((row, i)) else {
+                continue;
+            };
+            if left != c {
+                continue;
+            }
+            let mut content = if top == r {
+                cell_latex(cell)
+            } else {
+                String::new()
+            };
+            if top == r && cell.rowspan > 1 {
+                content = format!("\\multirow{{{}}}{{*}}{{{content}}}", cell.rowspan);
+            }
+            if cell.colspan > 1 {
+                content = format!("\\multicolumn{{{}}}{{|l|}}{{{content}}}", cell.colspan);
+            }
+            cells.push(content);
+        }
+        latex.push_str(&format!("  {} \\\\\n", cells.join(" & ")));
+        if r + 1 == headers || r + 1 == grid.len() {
+            latex.push_str("  \\hline\n");
+        }
+    }
+    latex.push_str("\\end{tabular}\n\n");
+}
+
+fn cell_latex(cell: &TableCell) -> String {
+    let text = cell
+        .content
+        .iter()
+        .map(|node| match node {
+            EditorNode::Paragraph(paragraph) => inline(&paragraph.elements),
+            node => inline(std::slice::from_ref(node)),
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+    if cell.header {
+        format!("\\textbf{{{text}}}")
+    } else {
+        text
+    }
+}
+
+fn figure(image: &Image, latex: &mut String) {
+    latex.push_str("\\begin{figure}[h]\n  \\centering\n");
+    include_graphics(image, latex);
+    if let Some(caption) = caption(image) {
+        latex.push_str(&format!("  \\caption{{{caption}}}\n"));
+    }
+    latex.push_str("\\end{figure}\n\n");
+}
+
+/// Writes code in a `verbatim` environment, or in a `fancyvrb` `Verbatim` with its
+/// backslashes and braces written as commands when the code would end `verbatim` early.
+fn code_block(code: &str, latex: &mut String) {
+    let code = code.trim_end_matches('\n');
+    if !code.contains(r"\end{verbatim}") {
+        latex.push_str(&format!(
+            "\\begin{{verbatim}}\n{code}\n\\end{{verbatim}}\n\n"
+        ));
+        return;
+    }
+    let mut escaped = String::with_capacity(code.len());
+    for c in code.chars() {
+        match c {
+            '\\' => escaped.push_str(r"\textbackslash{}"),
+            '{' | '}' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            _ => escaped.push(c),
+        }
+    }
+    latex.push_str(&format!(
+        "\\begin{{Verbatim}}[commandchars=\\\\\\{{\\}}]\n{escaped}\n\\end{{Verbatim}}\n\n"
+    ));
+}
+
+/// Writes an image nested in an environment, where a `figure` cannot float, as a centred
+/// graphic followed by its caption text.
+fn centered_image(image: &Image, latex: &mut String) {
+    latex.push_str("\\begin{center}\n");
+    include_graphics(image, latex);
+    if let Some(caption) = caption(image) {
+        latex.push_str(&format!("  {caption}\n"));
+    }
+    latex.push_str("\\end{center}\n\n");
+}
+
+/// Writes the graphic of an image read from a local file. Images embedded as `data:` urls
+/// are left out, remote images and paths TeX cannot take as an argument are left as a
+/// comment.
+fn include_graphics(image: &Image, latex: &mut String) {
+    let src = image.src.trim();
+    if src.starts_with("data:") {
+        return;
+    }
+    if src.contains("://") || src.contains(['%', '#', '{', '}', '\n', '\r']) {
+        latex.push_str(&format!(
+            "  % image not included: {}\n",
+            src.replace(['\n', '\r'], " ")
+        ));
+        return;
+    }
+    // TeX reads slashes as directory separators on every platform.
+    let path = src.replace('\\', "/");
+    let path = if path.contains(['_', '&', '~', '$', '^', ' ']) {
+        format!("\\detokenize{{{path}}}")
+    } else {
+        path
+    };
+    latex.push_str(&format!(
+        "  \\includegraphics[width=\\linewidth]{{{path}}}\n"
+    ));
+}
+
+/// The caption of an image, falling back to its alt text.
+fn caption(image: &Image) -> Option<String> {
+    if !image.caption.is_empty() {
+        Some(inline(&image.caption))
+    } else if !image.alt.is_empty() {
+        Some(escape_latex(&image.alt))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nodes::{
+        BlockQuote, Bold, BulletList, CodeBlock, Header, InlineCode, Link, OrderedList, Paragraph,
+        Strike, TextNode,
+    };
+
+    #[test]
+    fn test_latex_export() {
+        let nodes = vec![
+            EditorNode::Heading(Header {
+                text: "Costs & 100% margins".to_owned(),
+                level: 2,
+            }),
+            EditorNode::Paragraph(Paragraph {
+                elements: vec![
+                    EditorNode::Text(TextNode {
+                        text: "Use ".to_owned(),
+                    }),
+                    EditorNode::InlineCode(InlineCode {
+                        text: "a_b{}".to_owned(),
+                    }),
+                    EditorNode::Bold(Bold {
+                        text: "~now".to_owned(),
+                    }),
+                    EditorNode::Link(Link::new("100% off", "https://a.io/#50%")),
+                    EditorNode::Strike(Strike {
+                        text: "old".to_owned(),
+                    }),
+                    EditorNode::TextColor(TextColor {
+                        text: "hot".to_owned(),
+                        color: "#f00".to_owned(),
+                    }),
+                ],
+            }),
+            EditorNode::OrderedList(OrderedList {
+                list: vec![ListItem {
+                    content: vec![
+                        EditorNode::Paragraph(Paragraph {
+                            elements: vec![EditorNode::Text(TextNode {
+                                text: "$5".to_owned(),
+                            })],
+                        }),
+                        EditorNode::BulletList(BulletList {
+                            list: vec![ListItem::from_text("net")],
+                        }),
+                    ],
+                }],
+                ..Default::default()
+            }),
+        ];
+        assert_eq!(
+            to_latex(&nodes),
+            "\\subsection{Costs \\& 100\\% margins}\n\n\
+             Use \\texttt{a\\_b\\{\\}}\\textbf{\\textasciitilde{}now}\
+             \\href{https://a.io/\\#50\\%}{100\\% off}\\sout{old}\\textcolor[HTML]{FF0000}{hot}\n\n\
+             \\begin{enumerate}\n  \\item \\$5\n\\begin{itemize}\n  \\item net\n\\end{itemize}\n\
+             \\end{enumerate}\n\n"
+        );
+    }
+
+    #[test]
+    fn test_image_in_quote_does_not_float() {
+        let image = Image {
+            src: "cat.png".to_owned(),
+            alt: "A cat".to_owned(),
+            ..Default::default()
+        };
+        let nodes = vec![EditorNode::BlockQuote(BlockQuote {
+            content: vec![EditorNode::Image(image)],
+        })];
+        assert_eq!(
+            to_latex(&nodes),
+            "\\begin{quote}\n\\begin{center}\n  \\includegraphics[width=\\linewidth]{cat.png}\n  \
+             A cat\n\\end{center}\n\n\\end{quote}\n\n"
+        );
+    }
+
+    #[test]
+    fn test_code_and_image_paths() {
+        let code = EditorNode::CodeBlock(CodeBlock {
+            code: "\\end{verbatim} 100%\n".to_owned(),
+            ..Default::default()
+        });
+        assert_eq!(
+            to_latex(&[code]),
+            "\\begin{Verbatim}[commandchars=\\\\\\{\\}]\n\
+             \\textbackslash{}end\\{verbatim\\} 100%\n\\end{Verbatim}\n\n"
+        );
+        let image = |src: &str| {
+            let mut latex = String::new();
+            let image = Image {
+                src: src.to_owned(),
+                ..Default::default()
+            };
+            include_graphics(&image, &mut latex);
+            latex
+        };
+        assert_eq!(
+            image("pics\\my_cat.png"),
+            "  \\includegraphics[width=\\linewidth]{\\detokenize{pics/my_cat.png}}\n"
+        );
+        assert_eq!(
+            image("https://a.io/cat.png"),
+            "  % image not included: https://a.io/cat.png\n"
+        );
+        assert_eq!(image("50%.png"), "  % image not included: 50%.png\n");
+        assert_eq!(image("data:image/png;base64,AAAA"), "");
+    }
+}
@@ -0,0 +1,334 @@
+//! Export of edita nodes to Markdown, with GitHub's task lists and tables.
+use crate::editor::HtmlNode;
+use crate::formats::html::{inline_to_html, link_to_html, to_html};
+use crate::formats::{inline_parts, list_entry, Mark};
+use crate::nodes::{EditorNode, Image, ListItem, OrderedList, Table, TaskItem};
+
+/// Writes nodes as Markdown.
+///
+/// Markdown lists only count up in decimal, so ordered lists in another style, reversed or
+/// starting below zero are written as html, as are collapsible sections, tables with merged
+/// cells or without a header row, images with a size, alignment or caption, links
+/// opening in another target, and underlined, sub or superscript, highlighted and
+/// coloured text. Struck through text uses GitHub's `~~`.
+pub fn to_markdown(nodes: &[EditorNode]) -> String {
+    let markdown = blocks(nodes);
+    if markdown.is_empty() {
+        markdown
+    } else {
+        markdown + "\n"
+    }
+}
+
+/// Escapes the characters that have a special meaning in Markdown.
+pub fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(
+            c,
+            '\\' | '`' | '*' | '_' | '~' | '[' | ']' | '<' | '>' | '#' | '|'
+        ) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+fn blocks(nodes: &[EditorNode]) -> String {
+    nodes.iter().map(block).collect::<Vec<_>>().join("\n\n")
+}
+
+fn block(node: &EditorNode) -> String {
+    match node {
+        EditorNode::Html(HtmlNode(html)) => html.clone(),
+        EditorNode::Paragraph(paragraph) => inline(&paragraph.elements),
+        EditorNode::Text(_)
+        | EditorNode::Bold(_)
+        | EditorNode::Italic(_)
+        | EditorNode::InlineCode(_)
+        | EditorNode::Underline(_)
+        | EditorNode::Strike(_)
+        | EditorNode::Subscript(_)
+        | EditorNode::Superscript(_)
+        | EditorNode::Highlight(_)
+        | EditorNode::TextColor(_)
+        | EditorNode::Link(_)
+        | EditorNode::Emoji(_)
+        | EditorNode::Mention(_)
+        | EditorNode::HardBreak(_) => inline(std::slice::from_ref(node)),
+        EditorNode::Heading(header) => format!(
+            "{} {}",
+            "#".repeat(header.level.clamp(1, 6) as usize),
+            escape_markdown(&header.text)
+        ),
+        EditorNode::CodeBlock(block) => {
+            let fence = "`".repeat(longest_run(&block.code, '`').max(2) + 1);
+            format!(
+                "{fence}{}\n{}\n{fence}",
+                block.language.as_deref().unwrap_or_default(),
+                block.code.trim_end_matches('\n')
+            )
+        }
+        EditorNode::HorizontalRule(_) => "---".to_owned(),
+        EditorNode::Image(image) if is_plain_image(image) => {
+            format!(
+                "![{}]{}",
+                escape_markdown(&image.alt),
+                destination(&image.src, &image.title)
+            )
+        }
+        EditorNode::BlockQuote(quote) => blocks(&quote.content)
+            .lines()
+            .map(|line| format!("> {line}").trim_end().to_owned())
+            .collect::<Vec<_>>()
+            .join("\n"),
+        EditorNode::ListItem(item) => list_item("-", item),
+        EditorNode::BulletList(list) => list
+            .list
+            .iter()
+            .map(|item| list_item("-", item))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        EditorNode::OrderedList(list) if counts_up(list) => list
+            .list
+            .iter()
+            .enumerate()
+            .map(|(i, item)| list_item(&format!("{}.", list.number(i)), item))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        EditorNode::TaskItem(item) => task(item),
+        EditorNode::TaskList(list) => list.list.iter().map(task).collect::<Vec<_>>().join("\n"),
+        EditorNode::Table(table) if is_pipe_table(table) => pipe_table(table),
+        EditorNode::OrderedList(_)
+        | EditorNode::Table(_)
+        | EditorNode::Details(_)
+        | EditorNode::Image(_) => to_html(std::slice::from_ref(node)),
+    }
+}
+
+fn inline(nodes: &[EditorNode]) -> String {
+    let mut markdown = String::new();
+    for node in nodes {
+        match (inline_parts(node), node) {
+            (Some((text, Some(Mark::Code))), _) => {
+                let ticks = "`".repeat(longest_run(text, '`') + 1);
+                let padding = if text.starts_with('`') || text.ends_with('`') {
+                    " "
+                } else {
+                    ""
+                };
+                markdown.push_str(&format!("{ticks}{padding}{text}{padding}{ticks}"));
+            }
+            (
+                Some((
+                    _,
+                    Some(Mark::Underline | Mark::Subscript | Mark::Superscript | Mark::Highlight),
+                )),
+                _,
+            )
+            | (None, EditorNode::Highlight(_) | EditorNode::TextColor(_)) => {
+                markdown.push_str(&inline_to_html(std::slice::from_ref(node)))
+            }
+            (Some((text, mark)), _) => {
+                let delimiter = match mark {
+                    Some(Mark::Bold) => "**",
+                    Some(Mark::Italic) => "*",
+                    Some(Mark::Strike) => "~~",
+                    _ => "",
+                };
+                markdown.push_str(&format!("{delimiter}{}{delimiter}", escape_markdown(text)));
+            }
+            (None, EditorNode::Link(link)) if !link.target.is_empty() => {
+                markdown.push_str(&link_to_html(link))
+            }
+            (None, EditorNode::Link(link)) if link.text == link.href && link.title.is_empty() => {
+                markdown.push_str(&format!("<{}>", link.href.replace(['<', '>'], "")))
+            }
+            (None, EditorNode::Link(link)) => markdown.push_str(&format!(
+                "[{}]{}",
+                escape_markdown(&link.text),
+                destination(&link.href, &link.title)
+            )),
+            (None, EditorNode::HardBreak(_)) => markdown.push_str("\\\n"),
+            (None, EditorNode::Html(HtmlNode(html))) => markdown.push_str(html),
+            (None, other) => markdown.push_str(&escape_markdown(&other.text())),
+        }
+    }
+    markdown
+}
+
+/// The `(<url> "title")` part of a link or image.
+fn destination(url: &str, title: &str) -> String {
+    let title = match title {
+        "" => String::new(),
+        title => format!(" \"{}\"", title.replace('"', "\\\"")),
+    };
+    format!("(<{}>{title})", url.replace(['<', '>'], ""))
+}
+
+/// The length of the longest run of `c` in `text`.
+fn longest_run(text: &str, c: char) -> usize {
+    text.split(|other| other != c)
+        .map(str::len)
+        .max()
+        .unwrap_or_default()
+}
+
+/// Whether Markdown can show the image, which has no size, alignment or caption.
+fn is_plain_image(image: &Image) -> bool {
+    image.width.is_none()
+        && image.height.is_none()
+        && image.align.is_none()
+        && image.caption.is_empty()
+}
+
+/// Whether the list can be numbered by Markdown, which only counts up from zero or above.
+fn counts_up(list: &OrderedList) -> bool {
+    list.style.is_decimal() && !list.reversed && list.first_number() >= 0
+}
+
+/// Writes an item, its paragraphs separated by blank lines and its nested lists right
+/// below them so that the list stays tight.
+fn list_item(marker: &str, item: &ListItem) -> String {
+    let mut body = String::new();
+    for node in &item.content {
+        if !body.is_empty() {
+            body.push_str(if node.is_list() { "\n" } else { "\n\n" });
+        }
+        body.push_str(&block(node));
+    }
+    list_entry(marker, &body)
+}
+
+fn task(item: &TaskItem) -> String {
+    let mut body = format!(
+        "[{}] {}",
+        if item.checked { "x" } else { " " },
+        escape_markdown(&item.text)
+    );
+    for child in &item.children {
+        body.push('\n');
+        body.push_str(&task(child));
+    }
+    list_entry("-", &body)
+}
+
+/// Whether the table fits a GitHub pipe table: a single header row on top, no merged
+/// cells and no cell holding more than a paragraph.
+fn is_pipe_table(table: &Table) -> bool {
+    let Some((head, body)) = table.rows.split_first() else {
+        return false;
+    };
+    head.is_header()
+        && body
+            .iter()
+            .all(|row| !row.cells.iter().any(|cell| cell.header))
+        && table.rows.iter().flat_map(|row| &row.cells).all(|cell| {
+            cell.colspan == 1
+                && cell.rowspan == 1
+                && cell.content.len() <= 1
+                && cell
+                    .content
+                    .iter()
+                    .all(|node| matches!(node, EditorNode::Paragraph(_)) || node.is_inline())
+        })
+}
+
+fn pipe_table(table: &Table) -> String {
+    let rows: Vec<String> = table
+        .rows
+        .iter()
+        .map(|row| {
+            let cells: Vec<String> = row
+                .cells
+                .iter()
+                .map(|cell| block_inline(&cell.content))
+                .collect();
+            format!("| {} |", cells.join(" | "))
+        })
+        .collect();
+    let separator = format!("|{}", " --- |".repeat(table.width()));
+    let mut lines = vec![rows[0].clone(), separator];
+    lines.extend(rows[1..].iter().cloned());
+    lines.join("\n")
+}
+
+/// The content of a table cell on a single line.
+fn block_inline(content: &[EditorNode]) -> String {
+    content
+        .iter()
+        .map(|node| match node {
+            EditorNode::Paragraph(paragraph) => inline(&paragraph.elements),
+            node => inline(std::slice::from_ref(node)),
+        })
+        .collect::<String>()
+        .replace("\\\n", "<br>")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nodes::{
+        BulletList, Header, Link, NumberingStyle, Paragraph, Strike, TextNode, Underline,
+    };
+
+    #[test]
+    fn test_markdown_export() {
+        let mut first = ListItem::from_text("one");
+        first.content.push(EditorNode::BulletList(BulletList {
+            list: vec![ListItem::from_text("a*b")],
+        }));
+        let nodes = vec![
+            EditorNode::Heading(Header {
+                text: "Plan".to_owned(),
+                level: 2,
+            }),
+            EditorNode::Paragraph(Paragraph {
+                elements: vec![
+                    EditorNode::Text(TextNode {
+                        text: "Steps from ".to_owned(),
+                    }),
+                    EditorNode::Link(Link::new("the [docs]", "https://a.io/d")),
+                    EditorNode::Text(TextNode {
+                        text: ":".to_owned(),
+                    }),
+                    EditorNode::Strike(Strike {
+                        text: "old".to_owned(),
+                    }),
+                    EditorNode::Underline(Underline {
+                        text: "new".to_owned(),
+                    }),
+                ],
+            }),
+            EditorNode::OrderedList(OrderedList {
+                list: vec![first, ListItem::from_text("two")],
+                start: Some(9),
+                ..Default::default()
+            }),
+            EditorNode::OrderedList(OrderedList {
+                list: vec![ListItem::from_text("x")],
+                style: NumberingStyle::LowerRoman,
+                ..Default::default()
+            }),
+            EditorNode::Image(Image {
+                title: "Logo".to_owned(),
+                ..Image::new("a.png", "A")
+            }),
+            EditorNode::Image(Image {
+                width: Some(64),
+                caption: vec![EditorNode::Text(TextNode {
+                    text: "Small".to_owned(),
+                })],
+                ..Image::new("a.png", "A")
+            }),
+        ];
+        assert_eq!(
+            to_markdown(&nodes),
+            "## Plan\n\nSteps from [the \\[docs\\]](<https://a.io/d>):~~old~~<u>new</u>\n\n9. one\n   - a\\*b\n10. two\n\n\
+             <ol type=\"i\"><li><p>x</p></li></ol>\n\n\
+             ![A](<a.png> \"Logo\")\n\n\
+             <figure><img src=\"a.png\" alt=\"A\" width=\"64\"><figcaption>Small</figcaption></figure>\n"
+        );
+    }
+}
@@ -0,0 +1,139 @@
+pub mod docx;
+pub mod editorjs;
+pub(crate) mod html;
+pub mod latex;
+pub mod markdown;
+pub mod plain_text;
+pub mod portable_text;
+pub mod tiptap;
+
+use std::fmt;
+
+use crate::nodes::{
+    Bold, EditorNode, Highlight, InlineCode, Italic, Strike, Subscript, Superscript, TextNode,
+    Underline,
+};
+
+/// Errors raised while importing a document from another editor's format.
+#[derive(Debug)]
+pub enum ImportError {
+    /// The input uses a node, block or mark that edita has no equivalent for.
+    Unsupported(String),
+    /// The input does not have the expected shape.
+    Invalid(String),
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImportError::Unsupported(kind) => write!(f, "unsupported {kind}"),
+            ImportError::Invalid(message) => write!(f, "invalid input: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for ImportError {}
+
+/// The inline formatting edita can represent on a run of text.
+///
+/// Inline nodes carry a single mark. The Tiptap importer rejects text stacking several of
+/// them, the Portable Text and DOCX importers keep the greatest one, `Code` being the
+/// strongest. Colours are not marks, formats that
+/// support them handle coloured highlights and text on their own.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum Mark {
+    Subscript,
+    Superscript,
+    Strike,
+    Underline,
+    Highlight,
+    Italic,
+    Bold,
+    Code,
+}
+
+impl Mark {
+    /// Builds the inline node holding `text` with an optional mark.
+    pub(crate) fn node(mark: Option<Mark>, text: String) -> EditorNode {
+        match mark {
+            Some(Mark::Code) => EditorNode::InlineCode(InlineCode { text }),
+            Some(Mark::Bold) => EditorNode::Bold(Bold { text }),
+            Some(Mark::Italic) => EditorNode::Italic(Italic { text }),
+            Some(Mark::Highlight) => EditorNode::Highlight(Highlight { text, color: None }),
+            Some(Mark::Underline) => EditorNode::Underline(Underline { text }),
+            Some(Mark::Strike) => EditorNode::Strike(Strike { text }),
+            Some(Mark::Superscript) => EditorNode::Superscript(Superscript { text }),
+            Some(Mark::Subscript) => EditorNode::Subscript(Subscript { text }),
+            None => EditorNode::Text(TextNode { text }),
+        }
+    }
+}
+
+/// Splits an inline node into its text and mark.
+///
+/// Coloured highlights and text, like links, have attributes a mark cannot carry and are
+/// not split.
+pub(crate) fn inline_parts(node: &EditorNode) -> Option<(&str, Option<Mark>)> {
+    match node {
+        EditorNode::Text(text) => Some((&text.text, None)),
+        EditorNode::Bold(bold) => Some((&bold.text, Some(Mark::Bold))),
+        EditorNode::Italic(italic) => Some((&italic.text, Some(Mark::Italic))),
+        EditorNode::InlineCode(code) => Some((&code.text, Some(Mark::Code))),
+        EditorNode::Underline(underline) => Some((&underline.text, Some(Mark::Underline))),
+        EditorNode::Strike(strike) => Some((&strike.text, Some(Mark::Strike))),
+        EditorNode::Subscript(subscript) => Some((&subscript.text, Some(Mark::Subscript))),
+        EditorNode::Superscript(superscript) => Some((&superscript.text, Some(Mark::Superscript))),
+        EditorNode::Highlight(Highlight { text, color: None }) => {
+            Some((text, Some(Mark::Highlight)))
+        }
+        EditorNode::Emoji(emoji) => Some((&emoji.emoji, None)),
+        _ => None,
+    }
+}
+
+/// The six uppercase hex digits of a `#rgb` or `#rrggbb` colour, dropping any alpha.
+///
+/// Anything else gives `None`, as nodes deserialized from JSON may hold any colour.
+pub(crate) fn hex_color(color: &str) -> Option<String> {
+    let hex = color.strip_prefix('#')?;
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let hex: String = match hex.len() {
+        3 | 4 => hex.chars().take(3).flat_map(|c| [c, c]).collect(),
+        6 | 8 => hex[..6].to_owned(),
+        _ => return None,
+    };
+    Some(hex.to_ascii_uppercase())
+}
+
+/// Writes a list item's `body` after its `marker`, indenting the lines that follow to line
+/// up with the first.
+pub(crate) fn list_entry(marker: &str, body: &str) -> String {
+    let indent = " ".repeat(marker.chars().count() + 1);
+    let mut entry = format!("{marker} ");
+    for (i, line) in body.lines().enumerate() {
+        if i > 0 {
+            entry.push('\n');
+            if !line.is_empty() {
+                entry.push_str(&indent);
+            }
+        }
+        entry.push_str(line);
+    }
+    entry
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hex_color() {
+        assert_eq!(hex_color("#f0a"), Some("FF00AA".to_owned()));
+        assert_eq!(hex_color("#12ab34cc"), Some("12AB34".to_owned()));
+        assert_eq!(hex_color("red"), None);
+        assert_eq!(hex_color("#aéééb"), None);
+        assert_eq!(hex_color("#<\">"), None);
+    }
+}
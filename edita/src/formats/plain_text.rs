@@ -0,0 +1,136 @@
+//! Export of edita nodes to plain text.
+use crate::editor::HtmlNode;
+use crate::formats::html::text_content;
+use crate::formats::list_entry;
+use crate::nodes::{EditorNode, ListItem, TaskItem};
+
+/// Writes nodes as plain text, blocks separated by blank lines.
+///
+/// List items keep their bullets, numbers or checkboxes, ordered lists being numbered the
+/// way they are shown, table cells are separated by tabs and links are followed by their
+/// url.
+pub fn to_plain_text(nodes: &[EditorNode]) -> String {
+    let text = blocks(nodes);
+    if text.is_empty() {
+        text
+    } else {
+        text + "\n"
+    }
+}
+
+fn blocks(nodes: &[EditorNode]) -> String {
+    nodes.iter().map(block).collect::<Vec<_>>().join("\n\n")
+}
+
+fn block(node: &EditorNode) -> String {
+    match node {
+        EditorNode::Html(HtmlNode(html)) => text_content(html),
+        EditorNode::CodeBlock(block) => block.code.trim_end_matches('\n').to_owned(),
+        EditorNode::HorizontalRule(_) => "---".to_owned(),
+        EditorNode::BlockQuote(quote) => blocks(&quote.content)
+            .lines()
+            .map(|line| format!("> {line}").trim_end().to_owned())
+            .collect::<Vec<_>>()
+            .join("\n"),
+        EditorNode::ListItem(item) => list_item("-", item),
+        EditorNode::BulletList(list) => list
+            .list
+            .iter()
+            .map(|item| list_item("-", item))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        EditorNode::OrderedList(list) => list
+            .list
+            .iter()
+            .enumerate()
+            .map(|(i, item)| list_item(&list.marker(i), item))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        EditorNode::TaskItem(item) => task(item),
+        EditorNode::TaskList(list) => list.list.iter().map(task).collect::<Vec<_>>().join("\n"),
+        EditorNode::Table(table) => table
+            .rows
+            .iter()
+            .map(|row| {
+                row.cells
+                    .iter()
+                    .map(|cell| blocks(&cell.content).replace('\n', " "))
+                    .collect::<Vec<_>>()
+                    .join("\t")
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+        EditorNode::Details(details) => {
+            let summary: String = details.summary.iter().map(EditorNode::text).collect();
+            match blocks(&details.content) {
+                content if content.is_empty() => summary,
+                content => format!("{summary}\n\n{content}"),
+            }
+        }
+        EditorNode::Image(image) if !image.caption.is_empty() => image.caption_text(),
+        EditorNode::Image(image) => image.alt.clone(),
+        EditorNode::Paragraph(paragraph) => paragraph.elements.iter().map(block).collect(),
+        EditorNode::Link(link)
+            if link.text != link.href && link.href != format!("mailto:{}", link.text) =>
+        {
+            format!("{} ({})", link.text, link.href)
+        }
+        node => node.text(),
+    }
+}
+
+fn list_item(marker: &str, item: &ListItem) -> String {
+    let mut body = String::new();
+    for node in &item.content {
+        if !body.is_empty() {
+            body.push_str(if node.is_list() { "\n" } else { "\n\n" });
+        }
+        body.push_str(&block(node));
+    }
+    list_entry(marker, &body)
+}
+
+fn task(item: &TaskItem) -> String {
+    let mut body = item.text.clone();
+    for child in &item.children {
+        body.push('\n');
+        body.push_str(&task(child));
+    }
+    list_entry(if item.checked { "[x]" } else { "[ ]" }, &body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nodes::{Link, NumberingStyle, OrderedList, Paragraph};
+
+    #[test]
+    fn test_plain_text_numbers_lists_as_shown() {
+        let mut first = ListItem::from_text("one");
+        first.content[0] = EditorNode::Paragraph(Paragraph {
+            elements: vec![
+                EditorNode::Link(Link::new("one", "https://a.io")),
+                EditorNode::Link(Link::new("me@a.io", "mailto:me@a.io")),
+            ],
+        });
+        first.content.push(EditorNode::OrderedList(OrderedList {
+            list: vec![ListItem::from_text("nested")],
+            style: NumberingStyle::LowerAlpha,
+            ..Default::default()
+        }));
+        let list = OrderedList {
+            list: vec![
+                first,
+                ListItem::from_text("two"),
+                ListItem::from_text("three"),
+            ],
+            style: NumberingStyle::UpperRoman,
+            reversed: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            to_plain_text(&[EditorNode::OrderedList(list)]),
+            "III. one (https://a.io)me@a.io\n     a. nested\nII. two\nI. three\n"
+        );
+    }
+}
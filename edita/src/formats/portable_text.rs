@@ -0,0 +1,734 @@
+//! Conversion between edita nodes and Portable Text, the rich text format used by Sanity.
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+
+use crate::editor::HtmlNode;
+use crate::formats::{inline_parts, ImportError, Mark};
+use crate::nodes::{
+    sanitize_color, sanitize_href, BlockQuote, BulletList, CodeBlock, Details, EditorNode, Header,
+    Highlight, HorizontalRule, Image, Link, ListItem, Mention, OrderedList, Paragraph, Table,
+    TableCell, TableRow, TaskItem, TaskList, TextColor, TextNode,
+};
+
+/// A Portable Text block, eg. `{"_type": "block", "style": "h2", "children": [...]}`.
+///
+/// Fields of custom block types such as `image` are kept in `fields`.
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq)]
+pub struct PortableTextBlock {
+    #[serde(rename = "_type")]
+    pub kind: String,
+    #[serde(rename = "_key", default, skip_serializing_if = "String::is_empty")]
+    pub key: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub style: Option<String>,
+    #[serde(rename = "listItem", default, skip_serializing_if = "Option::is_none")]
+    pub list_item: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub level: Option<u32>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<PortableTextSpan>,
+    #[serde(rename = "markDefs", default, skip_serializing_if = "Vec::is_empty")]
+    pub mark_defs: Vec<Value>,
+    #[serde(flatten)]
+    pub fields: Map<String, Value>,
+}
+
+/// A run of text inside a block, eg. `{"_type": "span", "text": "..", "marks": ["strong"]}`.
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq)]
+pub struct PortableTextSpan {
+    #[serde(rename = "_type")]
+    pub kind: String,
+    #[serde(rename = "_key", default, skip_serializing_if = "String::is_empty")]
+    pub key: String,
+    #[serde(default)]
+    pub text: String,
+    #[serde(default)]
+    pub marks: Vec<String>,
+    #[serde(flatten)]
+    pub fields: Map<String, Value>,
+}
+
+impl PortableTextBlock {
+    fn text(&self) -> String {
+        self.children
+            .iter()
+            .map(|span| span.text.as_str())
+            .collect()
+    }
+
+    /// Looks up a string nested in the block's extra fields, eg. `["asset", "url"]`.
+    fn field_str(&self, path: &[&str]) -> Option<&str> {
+        let (first, rest) = path.split_first()?;
+        let mut value = self.fields.get(*first)?;
+        for segment in rest {
+            value = value.get(segment)?;
+        }
+        value.as_str()
+    }
+}
+
+/// Converts Portable Text blocks into edita nodes.
+///
+/// Consecutive blocks with the same `listItem` are grouped into a single list, blocks of a
+/// deeper `level` going in a list inside the last item above them. Block types,
+/// styles, list kinds and marks edita cannot represent are reported as [`ImportError::Unsupported`].
+pub fn from_portable_text(blocks: &[PortableTextBlock]) -> Result<Vec<EditorNode>, ImportError> {
+    let mut nodes: Vec<EditorNode> = vec![];
+    for block in blocks {
+        if block.kind == "block" {
+            if let Some(list_item) = &block.list_item {
+                let level = block.level.unwrap_or(1);
+                if list_item == "check" && level > 1 {
+                    // Subtasks go under the last task one level up.
+                    let Some(EditorNode::TaskList(list)) = nodes.last_mut() else {
+                        return Err(ImportError::Invalid("subtask without a task".to_owned()));
+                    };
+                    let mut siblings = &mut list.list;
+                    for _ in 1..level {
+                        let parent = siblings.last_mut().ok_or_else(|| {
+                            ImportError::Invalid("subtask without a task".to_owned())
+                        })?;
+                        siblings = &mut parent.children;
+                    }
+                    siblings.push(task_item(block));
+                    continue;
+                }
+                if list_item == "check" {
+                    match nodes.last_mut() {
+                        Some(EditorNode::TaskList(list)) => list.list.push(task_item(block)),
+                        _ => nodes.push(EditorNode::TaskList(TaskList {
+                            list: vec![task_item(block)],
+                        })),
+                    }
+                    continue;
+                }
+                let item = ListItem {
+                    content: vec![EditorNode::Paragraph(Paragraph {
+                        elements: block
+                            .children
+                            .iter()
+                            .map(|span| import_span(block, span))
+                            .collect::<Result<_, _>>()?,
+                    })],
+                };
+                // Deeper items go in a list inside the last item one level up.
+                let mut siblings = &mut nodes;
+                for _ in 1..level {
+                    let parent = match siblings.last_mut() {
+                        Some(EditorNode::BulletList(BulletList { list }))
+                        | Some(EditorNode::OrderedList(OrderedList { list, .. })) => {
+                            list.last_mut()
+                        }
+                        _ => None,
+                    };
+                    let parent = parent.ok_or_else(|| {
+                        ImportError::Invalid("nested list item without a parent".to_owned())
+                    })?;
+                    siblings = &mut parent.content;
+                }
+                match (list_item.as_str(), siblings.last_mut()) {
+                    ("bullet", Some(EditorNode::BulletList(list))) => list.list.push(item),
+                    ("number", Some(EditorNode::OrderedList(list))) => list.list.push(item),
+                    ("bullet", _) => {
+                        siblings.push(EditorNode::BulletList(BulletList { list: vec![item] }))
+                    }
+                    ("number", _) => siblings.push(EditorNode::OrderedList(OrderedList {
+                        list: vec![item],
+                        ..Default::default()
+                    })),
+                    (other, _) => {
+                        return Err(ImportError::Unsupported(format!("list item {other}")))
+                    }
+                }
+                continue;
+            }
+        }
+        nodes.push(import_block(block)?);
+    }
+    Ok(nodes)
+}
+
+fn task_item(block: &PortableTextBlock) -> TaskItem {
+    TaskItem {
+        text: block.text(),
+        checked: block
+            .fields
+            .get("checked")
+            .and_then(Value::as_bool)
+            .unwrap_or_default(),
+        children: vec![],
+    }
+}
+
+fn import_block(block: &PortableTextBlock) -> Result<EditorNode, ImportError> {
+    let node = match block.kind.as_str() {
+        "block" => match block.style.as_deref().unwrap_or("normal") {
+            "normal" => EditorNode::Paragraph(import_paragraph(block)?),
+            "blockquote" => EditorNode::BlockQuote(BlockQuote {
+                content: vec![EditorNode::Paragraph(import_paragraph(block)?)],
+            }),
+            style => match style.strip_prefix('h').and_then(|level| level.parse().ok()) {
+                Some(level @ 1..=6) => EditorNode::Heading(Header {
+                    text: block.text(),
+                    level,
+                }),
+                _ => return Err(ImportError::Unsupported(format!("style {style}"))),
+            },
+        },
+        "image" => EditorNode::Image(Image {
+            src: block
+                .field_str(&["url"])
+                .or_else(|| block.field_str(&["asset", "url"]))
+                .or_else(|| block.field_str(&["asset", "_ref"]))
+                .ok_or_else(|| ImportError::Invalid("image block without asset".to_owned()))?
+                .to_owned(),
+            alt: block.field_str(&["alt"]).unwrap_or_default().to_owned(),
+            ..Default::default()
+        }),
+        "code" => EditorNode::CodeBlock(CodeBlock {
+            language: block.field_str(&["language"]).map(str::to_owned),
+            code: block.field_str(&["code"]).unwrap_or_default().to_owned(),
+        }),
+        "break" => EditorNode::HorizontalRule(HorizontalRule),
+        "table" => EditorNode::Table(import_table(block)?),
+        "details" => {
+            let content: Vec<PortableTextBlock> = match block.fields.get("content") {
+                Some(content) => serde_json::from_value(content.clone())
+                    .map_err(|e| ImportError::Invalid(e.to_string()))?,
+                None => vec![],
+            };
+            EditorNode::Details(Details {
+                open: block
+                    .fields
+                    .get("open")
+                    .and_then(Value::as_bool)
+                    .unwrap_or_default(),
+                summary: block
+                    .children
+                    .iter()
+                    .map(|span| import_span(block, span))
+                    .collect::<Result<_, _>>()?,
+                content: from_portable_text(&content)?,
+            })
+        }
+        "html" => EditorNode::Html(HtmlNode(
+            block.field_str(&["html"]).unwrap_or_default().to_owned(),
+        )),
+        other => return Err(ImportError::Unsupported(format!("block {other}"))),
+    };
+    Ok(node)
+}
+
+fn import_paragraph(block: &PortableTextBlock) -> Result<Paragraph, ImportError> {
+    Ok(Paragraph {
+        elements: block
+            .children
+            .iter()
+            .map(|span| import_span(block, span))
+            .collect::<Result<_, _>>()?,
+    })
+}
+
+fn import_table(block: &PortableTextBlock) -> Result<Table, ImportError> {
+    let rows = block
+        .fields
+        .get("rows")
+        .and_then(Value::as_array)
+        .ok_or_else(|| ImportError::Invalid("table block without rows".to_owned()))?;
+    let headers = block
+        .fields
+        .get("headerRows")
+        .and_then(Value::as_u64)
+        .unwrap_or_default() as usize;
+    let rows = rows
+        .iter()
+        .enumerate()
+        .map(|(r, row)| {
+            let cells = row
+                .get("cells")
+                .and_then(Value::as_array)
+                .ok_or_else(|| ImportError::Invalid("table row without cells".to_owned()))?;
+            let spans = row.get("spans").and_then(Value::as_array);
+            let cells = cells
+                .iter()
+                .enumerate()
+                .map(|(i, text)| {
+                    let text = text.as_str().unwrap_or_default();
+                    let span = |index: usize| {
+                        spans
+                            .and_then(|spans| spans.get(i)?.get(index)?.as_u64())
+                            .unwrap_or(1)
+                            .max(1) as u32
+                    };
+                    TableCell {
+                        header: r < headers,
+                        colspan: span(0),
+                        rowspan: span(1),
+                        content: (!text.is_empty())
+                            .then(|| {
+                                EditorNode::Paragraph(Paragraph {
+                                    elements: vec![EditorNode::Text(TextNode {
+                                        text: text.to_owned(),
+                                    })],
+                                })
+                            })
+                            .into_iter()
+                            .collect(),
+                    }
+                })
+                .collect();
+            Ok(TableRow { cells })
+        })
+        .collect::<Result<_, ImportError>>()?;
+    Ok(Table { rows })
+}
+
+fn import_span(
+    block: &PortableTextBlock,
+    span: &PortableTextSpan,
+) -> Result<EditorNode, ImportError> {
+    if span.kind == "mention" {
+        let field = |name: &str| span.fields.get(name).and_then(Value::as_str);
+        let label = field("label").unwrap_or_else(|| span.text.trim_start_matches('@'));
+        return Ok(EditorNode::Mention(Mention::new(
+            field("id").unwrap_or_default(),
+            label,
+        )));
+    }
+    if span.kind != "span" {
+        return Err(ImportError::Unsupported(format!("inline {}", span.kind)));
+    }
+    let mut strongest = None;
+    let mut link = None;
+    let (mut highlight, mut text_color) = (None, None);
+    for mark in &span.marks {
+        let mark = match mark.as_str() {
+            "strong" => Mark::Bold,
+            "em" => Mark::Italic,
+            "code" => Mark::Code,
+            "underline" => Mark::Underline,
+            "strike-through" => Mark::Strike,
+            "sub" => Mark::Subscript,
+            "sup" => Mark::Superscript,
+            "highlight" => Mark::Highlight,
+            key => {
+                let def = block
+                    .mark_defs
+                    .iter()
+                    .find(|def| def.get("_key").and_then(Value::as_str) == Some(key));
+                let field = |name: &str| {
+                    def.and_then(|def| def.get(name))
+                        .and_then(Value::as_str)
+                        .unwrap_or_default()
+                        .to_owned()
+                };
+                match field("_type").as_str() {
+                    // Links to disallowed protocols are dropped, keeping their text.
+                    "link" => {
+                        link = sanitize_href(&field("href")).map(|href| Link {
+                            text: span.text.clone(),
+                            href,
+                            title: field("title"),
+                            target: field("target"),
+                        });
+                        continue;
+                    }
+                    "highlight" => {
+                        highlight = sanitize_color(&field("color"));
+                        Mark::Highlight
+                    }
+                    "color" => {
+                        text_color = sanitize_color(&field("color"));
+                        continue;
+                    }
+                    "" => return Err(ImportError::Unsupported(format!("mark {key}"))),
+                    annotation => {
+                        return Err(ImportError::Unsupported(format!("mark {annotation}")))
+                    }
+                }
+            }
+        };
+        strongest = strongest.max(Some(mark));
+    }
+    let text = span.text.clone();
+    Ok(match (link, highlight, text_color) {
+        // Links hold plain text, so their other marks are dropped.
+        (Some(link), _, _) => EditorNode::Link(link),
+        // Colours only apply to text without a stronger mark.
+        (_, Some(color), _) if strongest == Some(Mark::Highlight) => {
+            EditorNode::Highlight(Highlight {
+                text,
+                color: Some(color),
+            })
+        }
+        (_, _, Some(color)) if strongest.is_none() => {
+            EditorNode::TextColor(TextColor { text, color })
+        }
+        _ => Mark::node(strongest, text),
+    })
+}
+
+/// Converts edita nodes into Portable Text blocks.
+///
+/// Task items are written as `"listItem": "check"` blocks with a `checked` field, subtasks
+/// one `level` deeper than their task. Images are written as `image` blocks with a `url`
+/// field, code blocks as `code` blocks in the shape of Sanity's code input, rules as `break`
+/// blocks and opaque html nodes as custom `html` blocks. Links are `link` annotations in the
+/// `markDefs` of their block, as are coloured highlights and text, which are `highlight`
+/// and `color` annotations with a `color` field. Mentions are inline `mention` objects
+/// with `id` and `label` fields.
+///
+/// Tables are written in the shape of Sanity's table plugin, rows holding the plain text of
+/// their cells. The number of leading header rows is kept in a `headerRows` field and rows
+/// with merged cells list each cell's `[colspan, rowspan]` in a `spans` field. Collapsible
+/// sections are custom `details` blocks whose children are the summary and whose `content`
+/// holds the blocks of the body.
+pub fn to_portable_text(nodes: &[EditorNode]) -> Vec<PortableTextBlock> {
+    let mut blocks = vec![];
+    for node in nodes {
+        export_node(node, &mut blocks);
+    }
+    for (index, block) in blocks.iter_mut().enumerate() {
+        block.key = format!("b{index}");
+        for (span, child) in block.children.iter_mut().enumerate() {
+            child.key = format!("b{index}s{span}");
+        }
+    }
+    blocks
+}
+
+fn text_block(style: &str, children: Vec<PortableTextSpan>) -> PortableTextBlock {
+    PortableTextBlock {
+        kind: "block".to_owned(),
+        style: Some(style.to_owned()),
+        children,
+        ..Default::default()
+    }
+}
+
+/// A text block of inline nodes, links and colours being written as annotations in its
+/// `markDefs`.
+fn inline_block(style: &str, nodes: &[EditorNode]) -> PortableTextBlock {
+    let mut block = text_block(style, vec![]);
+    for node in nodes {
+        let mut span = span(node);
+        let def = match node {
+            EditorNode::Link(link) => {
+                let key = format!("l{}", block.mark_defs.len());
+                let mut def = json!({ "_key": key, "_type": "link", "href": link.href });
+                for (name, value) in [("title", &link.title), ("target", &link.target)] {
+                    if !value.is_empty() {
+                        def[name] = json!(value);
+                    }
+                }
+                Some(def)
+            }
+            EditorNode::Highlight(Highlight {
+                color: Some(color), ..
+            }) => {
+                let key = format!("c{}", block.mark_defs.len());
+                Some(json!({ "_key": key, "_type": "highlight", "color": color }))
+            }
+            EditorNode::TextColor(TextColor { color, .. }) => {
+                let key = format!("c{}", block.mark_defs.len());
+                Some(json!({ "_key": key, "_type": "color", "color": color }))
+            }
+            _ => None,
+        };
+        if let Some(def) = def {
+            span.marks
+                .push(def["_key"].as_str().unwrap_or_default().to_owned());
+            block.mark_defs.push(def);
+        }
+        block.children.push(span);
+    }
+    block
+}
+
+fn span(node: &EditorNode) -> PortableTextSpan {
+    if let EditorNode::Mention(mention) = node {
+        let mut fields = Map::new();
+        fields.insert("id".to_owned(), json!(mention.id));
+        fields.insert("label".to_owned(), json!(mention.label));
+        return PortableTextSpan {
+            kind: "mention".to_owned(),
+            text: mention.text(),
+            fields,
+            ..Default::default()
+        };
+    }
+    let (text, mark) = match (inline_parts(node), node) {
+        (Some(parts), _) => parts,
+        (None, EditorNode::HardBreak(_)) => ("\n", None),
+        (None, EditorNode::Link(link)) => (link.text.as_str(), None),
+        (None, EditorNode::Highlight(highlight)) => (highlight.text.as_str(), None),
+        (None, EditorNode::TextColor(color)) => (color.text.as_str(), None),
+        (None, _) => ("", None),
+    };
+    PortableTextSpan {
+        kind: "span".to_owned(),
+        text: text.to_owned(),
+        marks: mark
+            .map(|mark| match mark {
+                Mark::Italic => "em",
+                Mark::Bold => "strong",
+                Mark::Code => "code",
+                Mark::Underline => "underline",
+                Mark::Strike => "strike-through",
+                Mark::Subscript => "sub",
+                Mark::Superscript => "sup",
+                Mark::Highlight => "highlight",
+            })
+            .into_iter()
+            .map(str::to_owned)
+            .collect(),
+        ..Default::default()
+    }
+}
+
+fn plain_span(text: &str) -> Vec<PortableTextSpan> {
+    vec![PortableTextSpan {
+        kind: "span".to_owned(),
+        text: text.to_owned(),
+        ..Default::default()
+    }]
+}
+
+fn list_block(kind: &str, text: &str) -> PortableTextBlock {
+    PortableTextBlock {
+        list_item: Some(kind.to_owned()),
+        level: Some(1),
+        ..text_block("normal", plain_span(text))
+    }
+}
+
+fn list_blocks(list: &EditorNode, level: u32, blocks: &mut Vec<PortableTextBlock>) {
+    let (kind, items) = match list {
+        EditorNode::BulletList(list) => ("bullet", &list.list),
+        EditorNode::OrderedList(list) => ("number", &list.list),
+        _ => return,
+    };
+    for item in items {
+        list_item_blocks(kind, item, level, blocks);
+    }
+}
+
+/// Writes a list item followed by the items of its nested lists, one `level` deeper.
+fn list_item_blocks(kind: &str, item: &ListItem, level: u32, blocks: &mut Vec<PortableTextBlock>) {
+    blocks.push(PortableTextBlock {
+        list_item: Some(kind.to_owned()),
+        level: Some(level),
+        ..inline_block("normal", &item.inline())
+    });
+    for list in item.sublists() {
+        list_blocks(list, level + 1, blocks);
+    }
+}
+
+/// Writes a task followed by its subtasks, one `level` deeper.
+fn task_blocks(item: &TaskItem, level: u32, blocks: &mut Vec<PortableTextBlock>) {
+    let mut block = list_block("check", &item.text);
+    block.level = Some(level);
+    block
+        .fields
+        .insert("checked".to_owned(), json!(item.checked));
+    blocks.push(block);
+    for child in &item.children {
+        task_blocks(child, level + 1, blocks);
+    }
+}
+
+fn export_node(node: &EditorNode, blocks: &mut Vec<PortableTextBlock>) {
+    match node {
+        EditorNode::Html(HtmlNode(html)) => blocks.push(PortableTextBlock {
+            kind: "html".to_owned(),
+            fields: Map::from_iter([("html".to_owned(), json!(html))]),
+            ..Default::default()
+        }),
+        EditorNode::Paragraph(paragraph) => {
+            blocks.push(inline_block("normal", &paragraph.elements))
+        }
+        EditorNode::Text(_)
+        | EditorNode::Bold(_)
+        | EditorNode::Italic(_)
+        | EditorNode::InlineCode(_)
+        | EditorNode::Underline(_)
+        | EditorNode::Strike(_)
+        | EditorNode::Subscript(_)
+        | EditorNode::Superscript(_)
+        | EditorNode::Highlight(_)
+        | EditorNode::TextColor(_)
+        | EditorNode::Link(_)
+        | EditorNode::Emoji(_)
+        | EditorNode::Mention(_)
+        | EditorNode::HardBreak(_) => {
+            blocks.push(inline_block("normal", std::slice::from_ref(node)))
+        }
+        EditorNode::Heading(header) => blocks.push(text_block(
+            &format!("h{}", header.level),
+            plain_span(&header.text),
+        )),
+        EditorNode::HorizontalRule(_) => blocks.push(PortableTextBlock {
+            kind: "break".to_owned(),
+            style: Some("lineBreak".to_owned()),
+            ..Default::default()
+        }),
+        EditorNode::CodeBlock(block) => {
+            let mut fields = Map::from_iter([("code".to_owned(), json!(block.code))]);
+            if let Some(language) = &block.language {
+                fields.insert("language".to_owned(), json!(language));
+            }
+            blocks.push(PortableTextBlock {
+                kind: "code".to_owned(),
+                fields,
+                ..Default::default()
+            })
+        }
+        EditorNode::Image(image) => blocks.push(PortableTextBlock {
+            kind: "image".to_owned(),
+            fields: Map::from_iter([
+                ("url".to_owned(), json!(image.src)),
+                ("alt".to_owned(), json!(image.alt)),
+            ]),
+            ..Default::default()
+        }),
+        EditorNode::BlockQuote(quote) => quote_blocks(quote, blocks),
+        EditorNode::ListItem(item) => list_item_blocks("bullet", item, 1, blocks),
+        EditorNode::BulletList(_) | EditorNode::OrderedList(_) => list_blocks(node, 1, blocks),
+        EditorNode::TaskItem(item) => task_blocks(item, 1, blocks),
+        EditorNode::TaskList(list) => {
+            for item in &list.list {
+                task_blocks(item, 1, blocks);
+            }
+        }
+        EditorNode::Table(table) => blocks.push(table_block(table)),
+        EditorNode::Details(details) => blocks.push(PortableTextBlock {
+            kind: "details".to_owned(),
+            children: details.summary.iter().map(span).collect(),
+            fields: Map::from_iter([
+                ("open".to_owned(), json!(details.open)),
+                (
+                    "content".to_owned(),
+                    json!(to_portable_text(&details.content)),
+                ),
+            ]),
+            ..Default::default()
+        }),
+    }
+}
+
+/// Writes the paragraphs of a quote as `blockquote` blocks. Portable Text cannot nest
+/// quotes, so nested quotes are flattened, and other blocks are written as they are.
+fn quote_blocks(quote: &BlockQuote, blocks: &mut Vec<PortableTextBlock>) {
+    for node in &quote.content {
+        match node {
+            EditorNode::Paragraph(paragraph) => {
+                blocks.push(inline_block("blockquote", &paragraph.elements))
+            }
+            EditorNode::BlockQuote(nested) => quote_blocks(nested, blocks),
+            node => export_node(node, blocks),
+        }
+    }
+}
+
+fn table_block(table: &Table) -> PortableTextBlock {
+    let rows: Vec<Value> = table
+        .rows
+        .iter()
+        .enumerate()
+        .map(|(r, row)| {
+            let cells: Vec<String> = row
+                .cells
+                .iter()
+                .map(|cell| {
+                    cell.content
+                        .iter()
+                        .map(EditorNode::text)
+                        .collect::<Vec<_>>()
+                        .join("\n")
+                })
+                .collect();
+            let mut value = json!({ "_type": "tableRow", "_key": format!("r{r}"), "cells": cells });
+            if row
+                .cells
+                .iter()
+                .any(|cell| cell.colspan > 1 || cell.rowspan > 1)
+            {
+                let spans: Vec<[u32; 2]> = row
+                    .cells
+                    .iter()
+                    .map(|cell| [cell.colspan, cell.rowspan])
+                    .collect();
+                value["spans"] = json!(spans);
+            }
+            value
+        })
+        .collect();
+    let mut fields = Map::from_iter([("rows".to_owned(), json!(rows))]);
+    let headers = table.rows.iter().take_while(|row| row.is_header()).count();
+    if headers > 0 {
+        fields.insert("headerRows".to_owned(), json!(headers));
+    }
+    PortableTextBlock {
+        kind: "table".to_owned(),
+        fields,
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BLOCKS: &str = r#"[
+        { "_type": "block", "_key": "a", "style": "h1", "children": [{ "_type": "span", "text": "Title", "marks": [] }] },
+        { "_type": "block", "_key": "b", "style": "normal", "markDefs": [{ "_key": "l", "_type": "link", "href": "https://a.io" }, { "_key": "c", "_type": "color", "color": "teal" }], "children": [
+            { "_type": "span", "text": "Hello ", "marks": [] },
+            { "_type": "span", "text": "world", "marks": ["strong", "em"] },
+            { "_type": "span", "text": "!", "marks": ["l"] },
+            { "_type": "span", "text": "old", "marks": ["strike-through"] },
+            { "_type": "span", "text": "teal", "marks": ["c"] },
+            { "_type": "mention", "text": "@ada", "id": "u1", "label": "ada" }
+        ] },
+        { "_type": "block", "_key": "c", "style": "normal", "listItem": "number", "level": 1, "children": [{ "_type": "span", "text": "one", "marks": [] }] },
+        { "_type": "block", "_key": "c1", "style": "normal", "listItem": "bullet", "level": 2, "children": [{ "_type": "span", "text": "nested", "marks": [] }] },
+        { "_type": "block", "_key": "d", "style": "normal", "listItem": "number", "level": 1, "children": [{ "_type": "span", "text": "two", "marks": [] }] },
+        { "_type": "image", "_key": "e", "asset": { "_ref": "image-abc-png", "url": "https://cdn/abc.png" }, "alt": "A" },
+        { "_type": "block", "_key": "f", "style": "normal", "listItem": "check", "level": 1, "checked": false, "children": [{ "_type": "span", "text": "task", "marks": [] }] },
+        { "_type": "block", "_key": "g", "style": "normal", "listItem": "check", "level": 2, "checked": true, "children": [{ "_type": "span", "text": "subtask", "marks": [] }] }
+    ]"#;
+
+    #[test]
+    fn test_portable_text_round_trip() {
+        let blocks: Vec<PortableTextBlock> = serde_json::from_str(BLOCKS).unwrap();
+        let nodes = from_portable_text(&blocks).unwrap();
+        assert_eq!(nodes.len(), 5);
+        assert!(matches!(&nodes[2], EditorNode::OrderedList(l) if l.list.len() == 2));
+        assert!(
+            matches!(&nodes[1], EditorNode::Paragraph(p) if matches!(&p.elements[2], EditorNode::Link(l) if l.href == "https://a.io"))
+        );
+        assert_eq!(nodes[2].text(), "one\nnested\ntwo");
+        assert!(matches!(&nodes[3], EditorNode::Image(i) if i.src == "https://cdn/abc.png"));
+        assert!(matches!(&nodes[4], EditorNode::TaskList(l) if l.list[0].children[0].checked));
+        let exported = to_portable_text(&nodes);
+        assert_eq!(exported.len(), blocks.len());
+        assert_eq!(exported[1].mark_defs[0]["href"], "https://a.io");
+        assert_eq!(exported[1].mark_defs[1]["color"], "teal");
+        assert_eq!(exported[1].children[3].marks, vec!["strike-through"]);
+        assert_eq!(exported[1].children[5].fields["id"], "u1");
+        assert_eq!(from_portable_text(&exported).unwrap().len(), nodes.len());
+    }
+
+    #[test]
+    fn test_unknown_annotation_is_reported() {
+        let blocks: Vec<PortableTextBlock> = serde_json::from_str(
+            r#"[{ "_type": "block", "markDefs": [{ "_key": "k", "_type": "footnote" }],
+                  "children": [{ "_type": "span", "text": "x", "marks": ["k"] }] }]"#,
+        )
+        .unwrap();
+        match from_portable_text(&blocks) {
+            Err(ImportError::Unsupported(kind)) => assert_eq!(kind, "mark footnote"),
+            other => panic!("expected an unsupported mark, got {other:?}"),
+        }
+    }
+}
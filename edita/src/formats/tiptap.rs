@@ -0,0 +1,707 @@
+//! Conversion between edita nodes and the JSON documents used by Tiptap and ProseMirror.
+//!
+//! edita inline nodes hold plain text with a single mark, so importing text carrying
+//! several marks, eg. bold and italic or a link in bold, fails with
+//! [`ImportError::Unsupported`] rather than dropping formatting. Links to disallowed
+//! protocols and colours that are not plain css colours are dropped, keeping the text.
+//!
+//! Documents exported by edita convert back and forth without loss.
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+
+use crate::editor::HtmlNode;
+use crate::formats::{ImportError, Mark};
+use crate::nodes::{
+    sanitize_color, sanitize_href, BlockQuote, BulletList, CodeBlock, Details, EditorNode, Emoji,
+    HardBreak, Header, Highlight, HorizontalRule, Image, Link, ListItem, Mention, NumberingStyle,
+    OrderedList, Paragraph, Table, TableCell, TableRow, TaskItem, TaskList, TextColor,
+};
+
+/// A node of a Tiptap document, eg. `{"type": "doc", "content": [...]}`.
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq)]
+pub struct TiptapNode {
+    #[serde(rename = "type")]
+    pub kind: String,
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    pub attrs: Map<String, Value>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub content: Vec<TiptapNode>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub marks: Vec<TiptapMark>,
+}
+
+/// A mark applied to a Tiptap text node, eg. `{"type": "bold"}`.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct TiptapMark {
+    #[serde(rename = "type")]
+    pub kind: String,
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    pub attrs: Map<String, Value>,
+}
+
+impl TiptapNode {
+    fn new(kind: &str) -> Self {
+        TiptapNode {
+            kind: kind.to_owned(),
+            ..Default::default()
+        }
+    }
+
+    fn with_content(mut self, content: Vec<TiptapNode>) -> Self {
+        self.content = content;
+        self
+    }
+
+    fn with_attr(mut self, name: &str, value: Value) -> Self {
+        self.attrs.insert(name.to_owned(), value);
+        self
+    }
+
+    fn text(text: &str, mark: Option<&str>) -> Self {
+        TiptapNode {
+            kind: "text".to_owned(),
+            text: Some(text.to_owned()),
+            marks: mark
+                .map(|kind| {
+                    vec![TiptapMark {
+                        kind: kind.to_owned(),
+                        attrs: Map::new(),
+                    }]
+                })
+                .unwrap_or_default(),
+            ..Default::default()
+        }
+    }
+
+    fn attr_str(&self, name: &str) -> String {
+        self.attrs
+            .get(name)
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_owned()
+    }
+
+    /// The concatenated text of this node and all its descendants.
+    fn plain_text(&self) -> String {
+        match self.kind.as_str() {
+            "text" => self.text.clone().unwrap_or_default(),
+            "hardBreak" => "\n".to_owned(),
+            "emoji" => Emoji::from_shortcode(&self.attr_str("name"))
+                .map(|emoji| emoji.emoji)
+                .unwrap_or_default(),
+            "mention" => format!("@{}", self.attr_str("label")),
+            _ => {
+                let blocks: Vec<String> = self.content.iter().map(|c| c.plain_text()).collect();
+                let separator = if self.content.iter().all(is_inline) {
+                    ""
+                } else {
+                    "\n"
+                };
+                blocks.join(separator)
+            }
+        }
+    }
+}
+
+fn is_inline(node: &TiptapNode) -> bool {
+    matches!(
+        node.kind.as_str(),
+        "text" | "hardBreak" | "emoji" | "mention"
+    )
+}
+
+/// Converts a Tiptap `doc` node into edita nodes.
+pub fn from_tiptap(doc: &TiptapNode) -> Result<Vec<EditorNode>, ImportError> {
+    if doc.kind != "doc" {
+        return Err(ImportError::Invalid(format!(
+            "expected a doc node, found {}",
+            doc.kind
+        )));
+    }
+    doc.content.iter().map(import_node).collect()
+}
+
+fn import_node(node: &TiptapNode) -> Result<EditorNode, ImportError> {
+    let imported = match node.kind.as_str() {
+        "paragraph" => EditorNode::Paragraph(Paragraph {
+            elements: node
+                .content
+                .iter()
+                .map(import_node)
+                .collect::<Result<_, _>>()?,
+        }),
+        "heading" => EditorNode::Heading(Header {
+            text: node.plain_text(),
+            level: node
+                .attrs
+                .get("level")
+                .and_then(Value::as_u64)
+                .unwrap_or(1)
+                .clamp(1, 6) as u8,
+        }),
+        "text" => import_text(node)?,
+        "hardBreak" => EditorNode::HardBreak(HardBreak),
+        "emoji" => {
+            let name = node.attr_str("name");
+            EditorNode::Emoji(
+                Emoji::from_shortcode(&name)
+                    .ok_or_else(|| ImportError::Unsupported(format!("emoji {name}")))?,
+            )
+        }
+        "mention" => EditorNode::Mention(Mention {
+            id: node.attr_str("id"),
+            label: node.attr_str("label"),
+        }),
+        "codeBlock" => EditorNode::CodeBlock(CodeBlock {
+            language: Some(node.attr_str("language")).filter(|language| !language.is_empty()),
+            code: node.plain_text(),
+        }),
+        "horizontalRule" => EditorNode::HorizontalRule(HorizontalRule),
+        "html" => EditorNode::Html(HtmlNode(node.attr_str("html"))),
+        "image" => {
+            let size = |name| {
+                node.attrs
+                    .get(name)
+                    .and_then(Value::as_u64)
+                    .map(|size| size as u32)
+            };
+            EditorNode::Image(Image {
+                src: node.attr_str("src"),
+                alt: node.attr_str("alt"),
+                title: node.attr_str("title"),
+                width: size("width"),
+                height: size("height"),
+                ..Default::default()
+            })
+        }
+        "blockquote" => EditorNode::BlockQuote(BlockQuote {
+            content: node
+                .content
+                .iter()
+                .map(import_node)
+                .collect::<Result<_, _>>()?,
+        }),
+        "listItem" => EditorNode::ListItem(import_list_item(node)?),
+        "bulletList" => EditorNode::BulletList(BulletList {
+            list: import_list_items(node)?,
+        }),
+        "orderedList" => EditorNode::OrderedList(import_ordered_list(node)?),
+        "taskItem" => EditorNode::TaskItem(import_task_item(node)?),
+        "taskList" => EditorNode::TaskList(TaskList {
+            list: import_task_items(node)?,
+        }),
+        "table" => EditorNode::Table(import_table(node)?),
+        "details" => EditorNode::Details(import_details(node)?),
+        other => return Err(ImportError::Unsupported(format!("node {other}"))),
+    };
+    Ok(imported)
+}
+
+fn import_ordered_list(node: &TiptapNode) -> Result<OrderedList, ImportError> {
+    // Tiptap has no reversed lists, the attribute is only read back from edita's exports.
+    let reversed = node
+        .attrs
+        .get("reversed")
+        .and_then(Value::as_bool)
+        .unwrap_or_default();
+    Ok(OrderedList {
+        list: import_list_items(node)?,
+        start: node
+            .attrs
+            .get("start")
+            .and_then(Value::as_i64)
+            .filter(|start| reversed || *start != 1),
+        style: node
+            .attrs
+            .get("type")
+            .and_then(Value::as_str)
+            .and_then(NumberingStyle::from_type)
+            .unwrap_or_default(),
+        reversed,
+    })
+}
+
+fn export_ordered_list(list: &OrderedList) -> TiptapNode {
+    let mut node = TiptapNode::new("orderedList").with_attr("start", json!(list.first_number()));
+    if !list.style.is_decimal() {
+        node = node.with_attr("type", json!(list.style.type_attribute()));
+    }
+    if list.reversed {
+        node = node.with_attr("reversed", json!(true));
+    }
+    node.with_content(list.list.iter().map(export_list_item).collect())
+}
+
+/// Tiptap images have no caption or alignment, their title and size are only written when
+/// set.
+fn export_image(image: &Image) -> TiptapNode {
+    let mut node = TiptapNode::new("image")
+        .with_attr("src", json!(image.src))
+        .with_attr("alt", json!(image.alt));
+    if !image.title.is_empty() {
+        node = node.with_attr("title", json!(image.title));
+    }
+    for (name, size) in [("width", image.width), ("height", image.height)] {
+        if let Some(size) = size {
+            node = node.with_attr(name, json!(size));
+        }
+    }
+    node
+}
+
+fn import_details(node: &TiptapNode) -> Result<Details, ImportError> {
+    let mut details = Details {
+        open: node
+            .attrs
+            .get("open")
+            .and_then(Value::as_bool)
+            .unwrap_or_default(),
+        ..Default::default()
+    };
+    for part in &node.content {
+        let nodes = part
+            .content
+            .iter()
+            .map(import_node)
+            .collect::<Result<_, _>>()?;
+        match part.kind.as_str() {
+            "detailsSummary" => details.summary = nodes,
+            "detailsContent" => details.content = nodes,
+            other => return Err(ImportError::Unsupported(format!("node {other} in details"))),
+        }
+    }
+    Ok(details)
+}
+
+fn import_table(node: &TiptapNode) -> Result<Table, ImportError> {
+    let span = |cell: &TiptapNode, name: &str| {
+        cell.attrs
+            .get(name)
+            .and_then(Value::as_u64)
+            .unwrap_or(1)
+            .max(1) as u32
+    };
+    let rows = node
+        .content
+        .iter()
+        .map(|row| {
+            if row.kind != "tableRow" {
+                return Err(ImportError::Unsupported(format!(
+                    "node {} in table",
+                    row.kind
+                )));
+            }
+            let cells = row
+                .content
+                .iter()
+                .map(|cell| {
+                    let header = match cell.kind.as_str() {
+                        "tableCell" => false,
+                        "tableHeader" => true,
+                        other => {
+                            return Err(ImportError::Unsupported(format!(
+                                "node {other} in tableRow"
+                            )))
+                        }
+                    };
+                    Ok(TableCell {
+                        header,
+                        colspan: span(cell, "colspan"),
+                        rowspan: span(cell, "rowspan"),
+                        content: cell
+                            .content
+                            .iter()
+                            .map(import_node)
+                            .collect::<Result<_, _>>()?,
+                    })
+                })
+                .collect::<Result<_, _>>()?;
+            Ok(TableRow { cells })
+        })
+        .collect::<Result<_, _>>()?;
+    Ok(Table { rows })
+}
+
+/// Imports a text node with at most one mark, as described in the module docs.
+fn import_text(node: &TiptapNode) -> Result<EditorNode, ImportError> {
+    let text = node.text.clone().unwrap_or_default();
+    let mut imported = None;
+    let mut kinds = vec![];
+    for mark in &node.marks {
+        let text = text.clone();
+        let color = || {
+            mark.attrs
+                .get("color")
+                .and_then(Value::as_str)
+                .and_then(sanitize_color)
+        };
+        let node = match mark.kind.as_str() {
+            "code" => Mark::node(Some(Mark::Code), text),
+            "bold" => Mark::node(Some(Mark::Bold), text),
+            "italic" => Mark::node(Some(Mark::Italic), text),
+            "underline" => Mark::node(Some(Mark::Underline), text),
+            "strike" => Mark::node(Some(Mark::Strike), text),
+            "subscript" => Mark::node(Some(Mark::Subscript), text),
+            "superscript" => Mark::node(Some(Mark::Superscript), text),
+            "highlight" => EditorNode::Highlight(Highlight {
+                text,
+                color: color(),
+            }),
+            // Tiptap keeps font families and sizes in the same mark, only the colour is kept.
+            "textStyle" => match color() {
+                Some(color) => EditorNode::TextColor(TextColor { text, color }),
+                None => continue,
+            },
+            "link" => {
+                let attr = |name: &str| {
+                    mark.attrs
+                        .get(name)
+                        .and_then(Value::as_str)
+                        .unwrap_or_default()
+                        .to_owned()
+                };
+                match sanitize_href(&attr("href")) {
+                    Some(href) => EditorNode::Link(Link {
+                        text,
+                        href,
+                        title: attr("title"),
+                        target: attr("target"),
+                    }),
+                    None => continue,
+                }
+            }
+            other => return Err(ImportError::Unsupported(format!("mark {other}"))),
+        };
+        kinds.push(mark.kind.as_str());
+        imported = Some(node);
+    }
+    if kinds.len() > 1 {
+        return Err(ImportError::Unsupported(format!(
+            "stacked marks {}",
+            kinds.join(", ")
+        )));
+    }
+    Ok(imported.unwrap_or_else(|| Mark::node(None, text)))
+}
+
+fn import_list_item(node: &TiptapNode) -> Result<ListItem, ImportError> {
+    Ok(ListItem {
+        content: node
+            .content
+            .iter()
+            .map(import_node)
+            .collect::<Result<_, _>>()?,
+    })
+}
+
+fn import_list_items(node: &TiptapNode) -> Result<Vec<ListItem>, ImportError> {
+    node.content
+        .iter()
+        .map(|item| match item.kind.as_str() {
+            "listItem" => import_list_item(item),
+            other => Err(ImportError::Unsupported(format!(
+                "node {other} in {}",
+                node.kind
+            ))),
+        })
+        .collect()
+}
+
+fn import_task_item(node: &TiptapNode) -> Result<TaskItem, ImportError> {
+    let (lists, blocks): (Vec<&TiptapNode>, Vec<&TiptapNode>) = node
+        .content
+        .iter()
+        .partition(|child| child.kind == "taskList");
+    let mut children = vec![];
+    for list in lists {
+        children.extend(import_task_items(list)?);
+    }
+    Ok(TaskItem {
+        text: blocks
+            .iter()
+            .map(|block| block.plain_text())
+            .collect::<Vec<_>>()
+            .join("\n"),
+        checked: node
+            .attrs
+            .get("checked")
+            .and_then(Value::as_bool)
+            .unwrap_or_default(),
+        children,
+    })
+}
+
+fn import_task_items(node: &TiptapNode) -> Result<Vec<TaskItem>, ImportError> {
+    node.content
+        .iter()
+        .map(|item| match item.kind.as_str() {
+            "taskItem" => import_task_item(item),
+            other => Err(ImportError::Unsupported(format!(
+                "node {other} in taskList"
+            ))),
+        })
+        .collect()
+}
+
+/// Converts edita nodes into a Tiptap `doc` node.
+///
+/// Opaque html nodes are written as custom `html` nodes so that they survive a round trip.
+pub fn to_tiptap(nodes: &[EditorNode]) -> TiptapNode {
+    TiptapNode::new("doc").with_content(nodes.iter().map(export_node).collect())
+}
+
+fn export_node(node: &EditorNode) -> TiptapNode {
+    match node {
+        EditorNode::Html(HtmlNode(html)) => TiptapNode::new("html").with_attr("html", json!(html)),
+        EditorNode::HorizontalRule(_) => TiptapNode::new("horizontalRule"),
+        EditorNode::HardBreak(_) => TiptapNode::new("hardBreak"),
+        EditorNode::Paragraph(paragraph) => TiptapNode::new("paragraph")
+            .with_content(paragraph.elements.iter().map(export_node).collect()),
+        EditorNode::Text(text) => TiptapNode::text(&text.text, None),
+        EditorNode::Bold(bold) => TiptapNode::text(&bold.text, Some("bold")),
+        EditorNode::Italic(italic) => TiptapNode::text(&italic.text, Some("italic")),
+        EditorNode::InlineCode(code) => TiptapNode::text(&code.text, Some("code")),
+        EditorNode::Underline(underline) => TiptapNode::text(&underline.text, Some("underline")),
+        EditorNode::Strike(strike) => TiptapNode::text(&strike.text, Some("strike")),
+        EditorNode::Subscript(subscript) => TiptapNode::text(&subscript.text, Some("subscript")),
+        EditorNode::Superscript(superscript) => {
+            TiptapNode::text(&superscript.text, Some("superscript"))
+        }
+        EditorNode::Highlight(highlight) => {
+            colored_text(&highlight.text, "highlight", highlight.color.as_deref())
+        }
+        EditorNode::TextColor(color) => colored_text(&color.text, "textStyle", Some(&color.color)),
+        EditorNode::Link(link) => export_link(link),
+        EditorNode::Emoji(emoji) => {
+            TiptapNode::new("emoji").with_attr("name", json!(emoji.shortcode))
+        }
+        EditorNode::Mention(mention) => TiptapNode::new("mention")
+            .with_attr("id", json!(mention.id))
+            .with_attr("label", json!(mention.label)),
+        EditorNode::CodeBlock(block) => TiptapNode::new("codeBlock")
+            .with_attr("language", json!(block.language))
+            .with_content(text_content(&block.code)),
+        EditorNode::Heading(header) => TiptapNode::new("heading")
+            .with_attr("level", json!(header.level))
+            .with_content(text_content(&header.text)),
+        EditorNode::Image(image) => export_image(image),
+        EditorNode::BlockQuote(quote) => TiptapNode::new("blockquote")
+            .with_content(quote.content.iter().map(export_node).collect()),
+        EditorNode::ListItem(item) => export_list_item(item),
+        EditorNode::BulletList(list) => TiptapNode::new("bulletList")
+            .with_content(list.list.iter().map(export_list_item).collect()),
+        EditorNode::OrderedList(list) => export_ordered_list(list),
+        EditorNode::TaskItem(item) => export_task_item(item),
+        EditorNode::TaskList(list) => TiptapNode::new("taskList")
+            .with_content(list.list.iter().map(export_task_item).collect()),
+        EditorNode::Details(details) => TiptapNode::new("details")
+            .with_attr("open", json!(details.open))
+            .with_content(vec![
+                TiptapNode::new("detailsSummary")
+                    .with_content(details.summary.iter().map(export_node).collect()),
+                TiptapNode::new("detailsContent")
+                    .with_content(details.content.iter().map(export_node).collect()),
+            ]),
+        EditorNode::Table(table) => TiptapNode::new("table").with_content(
+            table
+                .rows
+                .iter()
+                .map(|row| {
+                    TiptapNode::new("tableRow")
+                        .with_content(row.cells.iter().map(export_table_cell).collect())
+                })
+                .collect(),
+        ),
+    }
+}
+
+fn export_link(link: &Link) -> TiptapNode {
+    let mut node = TiptapNode::text(&link.text, Some("link"));
+    let attrs = &mut node.marks[0].attrs;
+    attrs.insert("href".to_owned(), json!(link.href));
+    for (name, value) in [("title", &link.title), ("target", &link.target)] {
+        if !value.is_empty() {
+            attrs.insert(name.to_owned(), json!(value));
+        }
+    }
+    node
+}
+
+/// A text node with a mark of `kind` setting its colour.
+fn colored_text(text: &str, kind: &str, color: Option<&str>) -> TiptapNode {
+    let mut node = TiptapNode::text(text, Some(kind));
+    if let Some(color) = color {
+        node.marks[0].attrs.insert("color".to_owned(), json!(color));
+    }
+    node
+}
+
+fn export_table_cell(cell: &TableCell) -> TiptapNode {
+    let kind = if cell.header {
+        "tableHeader"
+    } else {
+        "tableCell"
+    };
+    TiptapNode::new(kind)
+        .with_attr("colspan", json!(cell.colspan))
+        .with_attr("rowspan", json!(cell.rowspan))
+        .with_content(cell.content.iter().map(export_node).collect())
+}
+
+fn text_content(text: &str) -> Vec<TiptapNode> {
+    if text.is_empty() {
+        vec![]
+    } else {
+        vec![TiptapNode::text(text, None)]
+    }
+}
+
+fn export_list_item(item: &ListItem) -> TiptapNode {
+    TiptapNode::new("listItem").with_content(item.content.iter().map(export_node).collect())
+}
+
+fn export_task_item(item: &TaskItem) -> TiptapNode {
+    let mut content = vec![TiptapNode::new("paragraph").with_content(text_content(&item.text))];
+    if !item.children.is_empty() {
+        content.push(
+            TiptapNode::new("taskList")
+                .with_content(item.children.iter().map(export_task_item).collect()),
+        );
+    }
+    TiptapNode::new("taskItem")
+        .with_attr("checked", json!(item.checked))
+        .with_content(content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOC: &str = r##"{
+        "type": "doc",
+        "content": [
+            { "type": "heading", "attrs": { "level": 2 }, "content": [{ "type": "text", "text": "Title" }] },
+            { "type": "paragraph", "content": [
+                { "type": "text", "text": "Hello " },
+                { "type": "text", "text": "world", "marks": [{ "type": "bold" }] },
+                { "type": "hardBreak" },
+                { "type": "text", "text": "docs", "marks": [{ "type": "link", "attrs": { "href": "https://a.io", "target": "_blank" } }] },
+                { "type": "text", "text": "key", "marks": [{ "type": "highlight", "attrs": { "color": "#ff0" } }] },
+                { "type": "text", "text": "red", "marks": [{ "type": "textStyle", "attrs": { "color": "red" } }] },
+                { "type": "text", "text": "under", "marks": [{ "type": "underline" }] },
+                { "type": "mention", "attrs": { "id": "u1", "label": "ada" } }
+            ] },
+            { "type": "bulletList", "content": [
+                { "type": "listItem", "content": [
+                    { "type": "paragraph", "content": [{ "type": "text", "text": "one" }] },
+                    { "type": "orderedList", "attrs": { "start": 3, "type": "a" }, "content": [
+                        { "type": "listItem", "content": [{ "type": "paragraph", "content": [{ "type": "text", "text": "nested" }] }] }
+                    ] }
+                ] }
+            ] },
+            { "type": "taskList", "content": [
+                { "type": "taskItem", "attrs": { "checked": true }, "content": [
+                    { "type": "paragraph", "content": [{ "type": "text", "text": "done" }] },
+                    { "type": "taskList", "content": [
+                        { "type": "taskItem", "attrs": { "checked": false }, "content": [{ "type": "paragraph", "content": [{ "type": "text", "text": "later" }] }] }
+                    ] }
+                ] }
+            ] },
+            { "type": "image", "attrs": { "src": "a.png", "alt": "A", "title": "Logo", "width": 320 } },
+            { "type": "table", "content": [
+                { "type": "tableRow", "content": [
+                    { "type": "tableHeader", "attrs": { "colspan": 2, "rowspan": 1 }, "content": [{ "type": "paragraph", "content": [{ "type": "text", "text": "Both" }] }] }
+                ] },
+                { "type": "tableRow", "content": [
+                    { "type": "tableCell", "attrs": { "colspan": 1, "rowspan": 1 }, "content": [{ "type": "paragraph" }] },
+                    { "type": "tableCell", "attrs": { "colspan": 1, "rowspan": 1 }, "content": [{ "type": "paragraph" }] }
+                ] }
+            ] },
+            { "type": "blockquote", "content": [
+                { "type": "paragraph", "content": [{ "type": "text", "text": "Said" }] },
+                { "type": "blockquote", "content": [{ "type": "paragraph", "content": [{ "type": "text", "text": "before" }] }] }
+            ] }
+        ]
+    }"##;
+
+    #[test]
+    fn test_tiptap_round_trip() {
+        let doc: TiptapNode = serde_json::from_str(DOC).unwrap();
+        let nodes = from_tiptap(&doc).unwrap();
+        assert!(matches!(&nodes[0], EditorNode::Heading(h) if h.level == 2 && h.text == "Title"));
+        assert!(
+            matches!(&nodes[2], EditorNode::BulletList(l) if l.list[0].sublists().count() == 1)
+        );
+        assert_eq!(nodes[2].text(), "one\nnested");
+        assert!(
+            matches!(&nodes[1], EditorNode::Paragraph(p) if matches!(&p.elements[3], EditorNode::Link(l) if l.target == "_blank"))
+        );
+        assert!(
+            matches!(&nodes[1], EditorNode::Paragraph(p) if matches!(&p.elements[4], EditorNode::Highlight(h) if h.color.as_deref() == Some("#ff0")))
+        );
+        assert!(
+            matches!(&nodes[6], EditorNode::BlockQuote(q) if matches!(q.content[1], EditorNode::BlockQuote(_)))
+        );
+        let EditorNode::BulletList(bullets) = &nodes[2] else {
+            panic!("expected a bullet list");
+        };
+        assert!(matches!(
+            bullets.list[0].sublists().next(),
+            Some(EditorNode::OrderedList(l)) if l.marker(0) == "c."
+        ));
+        assert!(
+            matches!(&nodes[3], EditorNode::TaskList(l) if l.list[0].checked && !l.list[0].children[0].checked)
+        );
+        assert!(
+            matches!(&nodes[4], EditorNode::Image(i) if i.width == Some(320) && i.title == "Logo")
+        );
+        assert!(matches!(&nodes[5], EditorNode::Table(t) if t.width() == 2));
+        assert_eq!(from_tiptap(&to_tiptap(&nodes)).unwrap().len(), nodes.len());
+        assert_eq!(to_tiptap(&nodes), doc);
+    }
+
+    #[test]
+    fn test_stacked_marks_are_unsupported() {
+        let paragraph = |text: &str| {
+            let doc = format!(
+                r#"{{ "type": "doc", "content": [{{ "type": "paragraph", "content": [{text}] }}] }}"#
+            );
+            from_tiptap(&serde_json::from_str(&doc).unwrap())
+        };
+        assert!(matches!(
+            paragraph(r#"{ "type": "text", "text": "a", "marks": [{ "type": "italic" }, { "type": "bold" }] }"#),
+            Err(ImportError::Unsupported(message)) if message == "stacked marks italic, bold"
+        ));
+        assert!(matches!(
+            paragraph(
+                r#"{ "type": "text", "text": "b", "marks": [{ "type": "bold" }, { "type": "link", "attrs": { "href": "https://a.io" } }] }"#
+            ),
+            Err(ImportError::Unsupported(_))
+        ));
+        let nodes = paragraph(
+            r##"{ "type": "text", "text": "c", "marks": [{ "type": "link", "attrs": { "href": "javascript:x" } }, { "type": "strike" }] },
+               { "type": "text", "text": "d", "marks": [{ "type": "textStyle", "attrs": { "fontFamily": "serif" } }, { "type": "highlight", "attrs": { "color": "#ff0" } }] }"##,
+        )
+        .unwrap();
+        let EditorNode::Paragraph(paragraph) = &nodes[0] else {
+            panic!("expected a paragraph");
+        };
+        assert!(matches!(&paragraph.elements[0], EditorNode::Strike(_)));
+        assert!(
+            matches!(&paragraph.elements[1], EditorNode::Highlight(h) if h.color.as_deref() == Some("#ff0"))
+        );
+    }
+
+    #[test]
+    fn test_unknown_node_is_reported() {
+        let doc: TiptapNode =
+            serde_json::from_str(r#"{ "type": "doc", "content": [{ "type": "mermaid" }] }"#)
+                .unwrap();
+        assert!(matches!(
+            from_tiptap(&doc),
+            Err(ImportError::Unsupported(_))
+        ));
+    }
+}
@@ -1,34 +1,66 @@
+mod bubble;
 mod commands;
+mod document;
 mod editor;
+mod formats;
 mod nodes;
 
 mod state;
+mod suggestion;
+#[cfg(all(test, target_arch = "wasm32"))]
+mod testing;
+mod toolbar;
+mod upload;
 
 pub use edita_core as core;
 
-pub use crate::commands::bold::MakeBold;
-pub use crate::editor::EditorExt;
+pub use crate::bubble::bubble_toolbar;
+pub use crate::commands::marks::{
+    MarkElement, SetTextColor, ToggleHighlight, ToggleMark, ToggleSubscript, ToggleSuperscript,
+};
+pub use crate::document::{Document, DocumentError, Migration, Migrations, FORMAT_VERSION};
+pub use crate::editor::{EditorExt, HtmlBlock, HtmlNode};
+pub use crate::formats::docx::{
+    from_docx, from_docx_with, to_docx, to_docx_with, AssetLoader, AssetSink,
+};
+pub use crate::formats::editorjs::{from_editorjs, to_editorjs, EditorJsBlock, EditorJsData};
+pub use crate::formats::html::to_html;
+pub use crate::formats::latex::{escape_latex, to_latex, to_latex_document};
+pub use crate::formats::markdown::{escape_markdown, to_markdown};
+pub use crate::formats::plain_text::to_plain_text;
+pub use crate::formats::portable_text::{
+    from_portable_text, to_portable_text, PortableTextBlock, PortableTextSpan,
+};
+pub use crate::formats::tiptap::{from_tiptap, to_tiptap, TiptapMark, TiptapNode};
+pub use crate::formats::ImportError;
 pub use crate::nodes::*;
 pub use crate::state::EditorState;
+pub use crate::suggestion::{
+    suggestion_popup, trailing_query, SlashMenu, Suggestion, SuggestionAction, SuggestionProvider,
+};
+pub use crate::toolbar::{Toolbar, ToolbarCheck, ToolbarItem};
+pub use crate::upload::{
+    DataUrlUploader, MockUploader, UploadDone, UploadError, UploadFile, Uploader,
+};
 
 // # Blocks
 //
 // BulletList x
-// CodeBlock
-// DetailsContent
-// DetailsSummary
-// Emoji
-// Hardbreak
-// HR
+// CodeBlock x
+// DetailsContent x
+// DetailsSummary x
+// Emoji x
+// Hardbreak x
+// HR x
 // Image x
 // Heading x
 // ListItem x
 // OrderedList x
 // Paragraph x
-// Table
-// TableRow
-// TableCell
-// TableHeader
+// Table x
+// TableRow x
+// TableCell x
+// TableHeader x
 // TaskList x
 // TaskItem x
 // Text x
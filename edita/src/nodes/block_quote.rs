@@ -1,10 +1,18 @@
-use edita_core::{Block, Editor};
+use edita_core::{process_nodes, Block, Command, Editor};
 use hirola::prelude::*;
-use serde::Serialize;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 use wasm_bindgen::JsCast;
 use web_sys::{Element, Node};
 
-use crate::{nodes::EditorNode, EditorState};
+use crate::{
+    nodes::{
+        list_item::create_element,
+        paragraph::{unwrap_inline, wrap_inline},
+        EditorNode,
+    },
+    EditorState, Suggestion, ToolbarItem,
+};
 
 pub struct BlockQuoteBlock;
 
@@ -12,6 +20,15 @@ impl Block for BlockQuoteBlock {
     type Node = EditorNode;
     type State = EditorState;
     type Input = web_sys::Node;
+    fn hook(&self, editor: &mut Editor<Self::Node, Self::State, Self::Input>) {
+        editor.add_slash_item(Suggestion::command("Quote", ToggleBlockQuote));
+        editor.add_toolbar_item(
+            ToolbarItem::new("quote", "Quote", ToggleBlockQuote)
+                .with_icon("Quote")
+                .with_group("blocks")
+                .active_inside("blockquote"),
+        );
+    }
     fn accepts(&self, node: &Node) -> bool {
         if let Some(element) = node.dyn_ref::<Element>() {
             element.tag_name() == "BLOCKQUOTE"
@@ -22,22 +39,127 @@ impl Block for BlockQuoteBlock {
 
     fn parse(
         &self,
-        _editor: &Editor<Self::Node, Self::State, web_sys::Node>,
+        editor: &Editor<Self::Node, Self::State, web_sys::Node>,
         node: &web_sys::Node,
     ) -> EditorNode {
+        let children = node.child_nodes();
+        let children = (0..children.length()).filter_map(|i| children.get(i));
         EditorNode::BlockQuote(BlockQuote {
-            text: node.text_content().unwrap_or_default(),
+            content: wrap_inline(process_nodes(editor, children)),
         })
     }
 }
 
-#[derive(Clone, Serialize, Debug)]
+#[derive(Clone, Serialize, Deserialize, JsonSchema, Default, Debug)]
 pub struct BlockQuote {
-    text: String,
+    /// The quoted blocks, which may be quotes themselves.
+    pub content: Vec<EditorNode>,
+}
+
+impl BlockQuote {
+    /// The inline nodes of the quoted blocks, separated by hard breaks, for formats whose
+    /// quotes only hold a line of text.
+    pub fn inline(&self) -> Vec<EditorNode> {
+        unwrap_inline(&self.content)
+    }
 }
 
 impl crate::nodes::Node for BlockQuote {
     fn render(&self) -> hirola::dom::Dom {
-        html! { <blockquote>{&self.text}</blockquote> }
+        html! {
+            <blockquote>
+                {for node in &self.content {
+                    node.render()
+                }}
+            </blockquote>
+        }
+    }
+}
+
+/// The child of `parent` holding `node`.
+fn child_holding(parent: &Node, node: Node) -> Option<Node> {
+    std::iter::successors(Some(node), Node::parent_node).find(|node| {
+        node.parent_node()
+            .is_some_and(|node_parent| node_parent == *parent)
+    })
+}
+
+/// Lifts the blocks the selection runs through out of the quote around the cursor, the
+/// blocks below them staying quoted after them, or wraps the selected blocks in a quote
+/// outside of quotes.
+pub struct ToggleBlockQuote;
+
+impl Command<EditorState> for ToggleBlockQuote {
+    fn execute(&self, state: &mut EditorState) {
+        let Some(range) = state.selection_range() else {
+            return;
+        };
+        let Some(quote) = state.closest_at_cursor("blockquote") else {
+            let blocks = state.selected_blocks();
+            let Some(first) = blocks.first() else {
+                return;
+            };
+            state.keep_caret(|| {
+                let quote = create_element("blockquote");
+                first
+                    .parent_node()
+                    .unwrap()
+                    .insert_before(&quote, Some(first))
+                    .unwrap();
+                for block in &blocks {
+                    quote.append_child(block).unwrap();
+                }
+            });
+            return;
+        };
+        let quote: Node = quote.into();
+        let first = range
+            .start_container()
+            .ok()
+            .and_then(|node| child_holding(&quote, node));
+        // The selection may end past the quote, in which case its last child is lifted too.
+        let last = range
+            .end_container()
+            .ok()
+            .and_then(|node| child_holding(&quote, node))
+            .or_else(|| quote.last_child());
+        let (Some(first), Some(last)) = (first, last) else {
+            return;
+        };
+        let parent = quote.parent_node().unwrap();
+        state.keep_caret(|| {
+            let mut lifted = vec![first.clone()];
+            let mut node = first;
+            while node != last {
+                let Some(next) = node.next_sibling() else {
+                    break;
+                };
+                lifted.push(next.clone());
+                node = next;
+            }
+            // The children after the selection move into a copy of the quote.
+            let rest = quote.clone_node().unwrap();
+            while let Some(next) = node.next_sibling() {
+                rest.append_child(&next).unwrap();
+            }
+            let after = quote.next_sibling();
+            for block in &lifted {
+                // Loose text becomes a paragraph so that it stays a block.
+                let block = if block.node_type() == Node::TEXT_NODE {
+                    let paragraph = create_element("p");
+                    paragraph.append_child(block).unwrap();
+                    paragraph.into()
+                } else {
+                    block.clone()
+                };
+                parent.insert_before(&block, after.as_ref()).unwrap();
+            }
+            if rest.has_child_nodes() {
+                parent.insert_before(&rest, after.as_ref()).unwrap();
+            }
+            if !quote.has_child_nodes() {
+                parent.remove_child(&quote).unwrap();
+            }
+        });
     }
 }
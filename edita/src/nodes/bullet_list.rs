@@ -1,13 +1,13 @@
-use edita_core::{Block, Editor};
+use edita_core::{Block, Command, Editor};
 use hirola::prelude::*;
-use js_sys::Object;
-use serde::Serialize;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 use wasm_bindgen::JsCast;
 use web_sys::{Element, Node};
 
-use crate::{nodes::EditorNode, EditorState};
+use crate::{nodes::EditorNode, EditorState, Suggestion, ToolbarItem};
 
-use super::list_item::{ListItem, ListItemBlock};
+use super::list_item::{read_list_items, toggle_list, ListItem};
 
 pub struct BulletListBlock;
 
@@ -15,6 +15,19 @@ impl Block for BulletListBlock {
     type Node = EditorNode;
     type State = EditorState;
     type Input = web_sys::Node;
+    fn hook(&self, editor: &mut Editor<Self::Node, Self::State, Self::Input>) {
+        editor.add_slash_item(Suggestion::command("Bullet list", ToggleBulletList));
+        editor.add_toolbar_item(
+            ToolbarItem::new("bullet-list", "Bullet list", ToggleBulletList)
+                .with_icon("UL")
+                .with_group("blocks")
+                .with_active(|state| {
+                    state.closest_at_cursor("ul, ol").is_some_and(|list| {
+                        list.tag_name() == "UL" && !list.has_attribute("data-type")
+                    })
+                }),
+        );
+    }
     fn accepts(&self, node: &Node) -> bool {
         if let Some(element) = node.dyn_ref::<Element>() {
             element.tag_name() == "UL"
@@ -29,23 +42,14 @@ impl Block for BulletListBlock {
         node: &web_sys::Node,
     ) -> EditorNode {
         EditorNode::BulletList(BulletList {
-            list: Object::entries(&node.child_nodes())
-                .into_iter()
-                .map(|item| ListItemBlock::parse(&ListItemBlock, editor, item.dyn_ref().unwrap()))
-                .map(|node| match node {
-                    EditorNode::ListItem(item) => Some(item),
-                    _ => None,
-                })
-                .filter(|item| item.is_some())
-                .map(|c| c.unwrap())
-                .collect(),
+            list: read_list_items(editor, node),
         })
     }
 }
 
-#[derive(Clone, Serialize, Debug)]
+#[derive(Clone, Serialize, Deserialize, JsonSchema, Debug)]
 pub struct BulletList {
-    list: Vec<ListItem>,
+    pub list: Vec<ListItem>,
 }
 
 impl crate::nodes::Node for BulletList {
@@ -59,3 +63,13 @@ impl crate::nodes::Node for BulletList {
     }
     }
 }
+
+/// Turns the list at the cursor into a bullet list, or back into paragraphs if it is one,
+/// and wraps the selected blocks in a bullet list outside of lists.
+pub struct ToggleBulletList;
+
+impl Command<EditorState> for ToggleBulletList {
+    fn execute(&self, state: &mut EditorState) {
+        toggle_list(state, "ul");
+    }
+}
@@ -0,0 +1,242 @@
+use edita_core::{Block, Command, Editor};
+use hirola::dom::Html;
+use hirola::dom::XEffect;
+use hirola::{dom::Dom, prelude::*};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::JsCast;
+use web_sys::{window, Element};
+
+use crate::{
+    nodes::{EditorNode, Node},
+    state::EditorState,
+    suggestion::Suggestion,
+    toolbar::ToolbarItem,
+};
+
+/// The text inserted by Tab inside a code block.
+pub const CODE_INDENT: &str = "    ";
+
+#[derive(Clone, Serialize, Deserialize, JsonSchema, Default, Debug)]
+pub struct CodeBlock {
+    /// The language of the code, as in the `language-x` class, eg. `rust`.
+    pub language: Option<String>,
+    pub code: String,
+}
+
+impl Node for CodeBlock {
+    fn render(&self) -> Dom {
+        use hirola::prelude::DefaultAttrStr;
+        use hirola::prelude::DefaultAttributeEffect;
+        let class = self
+            .language
+            .as_ref()
+            .map(|language| format!("language-{language}"))
+            .unwrap_or_default();
+        let highlighted = highlight(&self.code, self.language.as_deref());
+        html! {
+            <pre data-type="codeBlock" spellcheck="false">
+                <code class=&class x:html=highlighted.as_str()></code>
+            </pre>
+        }
+    }
+}
+
+/// Highlights code as html spans classed after the syntax scopes, eg. `hl-keyword`.
+///
+/// The code is only escaped when the language is unknown or the `highlight` feature is
+/// disabled. Either way the spans hold the code's exact text, so parsing the block back
+/// with `text_content` yields the code unchanged.
+pub fn highlight(code: &str, language: Option<&str>) -> String {
+    #[cfg(feature = "highlight")]
+    if let Some(highlighted) = language.and_then(|language| syntax::highlight(code, language)) {
+        return highlighted;
+    }
+    #[cfg(not(feature = "highlight"))]
+    let _ = language;
+    crate::formats::html::escape(code)
+}
+
+/// The stylesheet for highlighted code in one of the bundled themes, eg. `InspiredGitHub`.
+#[cfg(feature = "highlight")]
+pub fn highlight_css(theme: &str) -> Option<String> {
+    syntax::css(theme)
+}
+
+#[cfg(feature = "highlight")]
+mod syntax {
+    use std::sync::OnceLock;
+
+    use syntect::highlighting::ThemeSet;
+    use syntect::html::{css_for_theme_with_class_style, ClassStyle, ClassedHTMLGenerator};
+    use syntect::parsing::SyntaxSet;
+    use syntect::util::LinesWithEndings;
+
+    const CLASS_STYLE: ClassStyle = ClassStyle::SpacedPrefixed { prefix: "hl-" };
+
+    fn syntaxes() -> &'static SyntaxSet {
+        static SYNTAXES: OnceLock<SyntaxSet> = OnceLock::new();
+        SYNTAXES.get_or_init(SyntaxSet::load_defaults_newlines)
+    }
+
+    pub(super) fn highlight(code: &str, language: &str) -> Option<String> {
+        let syntaxes = syntaxes();
+        let syntax = syntaxes.find_syntax_by_token(language)?;
+        let mut generator =
+            ClassedHTMLGenerator::new_with_class_style(syntax, syntaxes, CLASS_STYLE);
+        for line in LinesWithEndings::from(code) {
+            generator
+                .parse_html_for_line_which_includes_newline(line)
+                .ok()?;
+        }
+        Some(generator.finalize())
+    }
+
+    pub(super) fn css(theme: &str) -> Option<String> {
+        let themes = ThemeSet::load_defaults();
+        css_for_theme_with_class_style(themes.themes.get(theme)?, CLASS_STYLE).ok()
+    }
+}
+
+/// Reads the language from a `language-x` or `lang-x` class.
+fn language(element: &Element) -> Option<String> {
+    element.class_name().split_whitespace().find_map(|class| {
+        class
+            .strip_prefix("language-")
+            .or_else(|| class.strip_prefix("lang-"))
+            .map(str::to_owned)
+    })
+}
+
+pub struct CodeBlockBlock;
+
+impl Block for CodeBlockBlock {
+    type Node = EditorNode;
+    type Input = web_sys::Node;
+    type State = EditorState;
+
+    fn hook(&self, editor: &mut Editor<Self::Node, Self::State, Self::Input>) {
+        editor.bind_key("Tab", |state| {
+            let handled = state.closest_at_cursor("pre").is_some();
+            if handled {
+                state.execute(IndentCode);
+            }
+            handled
+        });
+        editor.bind_key("Enter", |state| {
+            let handled = state.closest_at_cursor("pre").is_some();
+            if handled {
+                state.execute(NewlineInCode);
+            }
+            handled
+        });
+        editor.add_slash_item(Suggestion::new(
+            "Code block",
+            EditorNode::CodeBlock(CodeBlock::default()),
+        ));
+        editor.add_toolbar_item(
+            ToolbarItem::new("code-block", "Code block", CodeBlockBlock)
+                .with_icon("Code")
+                .with_group("insert")
+                .active_inside("pre"),
+        );
+    }
+
+    fn accepts(&self, node: &web_sys::Node) -> bool {
+        if let Some(element) = node.dyn_ref::<Element>() {
+            element.tag_name() == "PRE"
+        } else {
+            false
+        }
+    }
+
+    fn parse(
+        &self,
+        _editor: &Editor<Self::Node, EditorState, web_sys::Node>,
+        node: &web_sys::Node,
+    ) -> EditorNode {
+        let pre: &Element = node.dyn_ref().unwrap();
+        let code = pre.query_selector("code").ok().flatten();
+        EditorNode::CodeBlock(CodeBlock {
+            language: code.as_ref().and_then(language).or_else(|| language(pre)),
+            // The highlighting spans only add markup, the text stays the code.
+            code: pre.text_content().unwrap_or_default(),
+        })
+    }
+}
+
+impl Command<EditorState> for CodeBlockBlock {
+    fn execute(&self, state: &mut EditorState) {
+        state.insert_block(CodeBlock::default())
+    }
+}
+
+/// Inserts [`CODE_INDENT`] at the cursor inside a code block.
+pub struct IndentCode;
+
+impl Command<EditorState> for IndentCode {
+    fn execute(&self, state: &mut EditorState) {
+        if state.closest_at_cursor("pre").is_some() {
+            state.insert_text(CODE_INDENT);
+        }
+    }
+}
+
+/// Starts a new line inside a code block, keeping the indentation of the current one.
+pub struct NewlineInCode;
+
+impl Command<EditorState> for NewlineInCode {
+    fn execute(&self, state: &mut EditorState) {
+        let (Some(pre), Some(range)) = (state.closest_at_cursor("pre"), state.selection_range())
+        else {
+            return;
+        };
+        let document = window().unwrap().document().unwrap();
+        let before = document.create_range().unwrap();
+        before.select_node_contents(&pre).unwrap();
+        before
+            .set_end(
+                &range.start_container().unwrap(),
+                range.start_offset().unwrap(),
+            )
+            .unwrap();
+        let before = String::from(before.to_string());
+        let line = before.rsplit('\n').next().unwrap_or_default();
+        let indent: String = line
+            .chars()
+            .take_while(|c| matches!(c, ' ' | '\t'))
+            .collect();
+        state.insert_text(&format!("\n{indent}"));
+    }
+}
+
+/// Sets the language of the code block at the cursor, highlighting it again.
+pub struct SetCodeLanguage(pub Option<String>);
+
+impl Command<EditorState> for SetCodeLanguage {
+    fn execute(&self, state: &mut EditorState) {
+        if let Some(pre) = state.closest_at_cursor("pre") {
+            let block = CodeBlock {
+                language: self.0.clone(),
+                code: pre.text_content().unwrap_or_default(),
+            };
+            pre.replace_with_with_node_1(&block.render().inner_element())
+                .unwrap();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_highlight() {
+        let code = "fn main() {\n    let x = 1 < 2;\n}\n";
+        let highlighted = highlight(code, Some("rust"));
+        #[cfg(feature = "highlight")]
+        assert!(highlighted.contains(r#"<span class="hl-"#));
+        assert_eq!(crate::formats::html::text_content(&highlighted), code);
+        assert_eq!(highlight("a < b", Some("klingon")), "a &lt; b");
+    }
+}
@@ -0,0 +1,195 @@
+use edita_core::{process_nodes, Block, Command, Editor};
+use hirola::{dom::Dom, prelude::*};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::JsCast;
+use web_sys::{window, Element};
+
+use crate::{
+    nodes::{EditorNode, Node, Paragraph},
+    state::EditorState,
+    suggestion::Suggestion,
+    toolbar::ToolbarItem,
+};
+
+/// A collapsible section, rendered as `<details>`.
+#[derive(Clone, Serialize, Deserialize, JsonSchema, Default, Debug)]
+pub struct Details {
+    /// Whether the section is expanded.
+    pub open: bool,
+    /// The inline nodes of the `<summary>`, shown when collapsed.
+    pub summary: Vec<EditorNode>,
+    /// The blocks revealed when expanded.
+    pub content: Vec<EditorNode>,
+}
+
+impl Node for Details {
+    fn render(&self) -> Dom {
+        let details = html! {
+            <details data-type="details">
+                <summary>
+                    {for node in &self.summary {
+                        node.render()
+                    }}
+                </summary>
+                <div data-type="detailsContent">
+                    {for node in &self.content {
+                        node.render()
+                    }}
+                </div>
+            </details>
+        };
+        if self.open {
+            details
+                .inner_element()
+                .unchecked_ref::<Element>()
+                .set_attribute("open", "")
+                .unwrap();
+        }
+        details
+    }
+}
+
+fn child_nodes(parent: &web_sys::Node) -> Vec<web_sys::Node> {
+    let children = parent.child_nodes();
+    (0..children.length())
+        .filter_map(|i| children.get(i))
+        .collect()
+}
+
+pub struct DetailsBlock;
+
+impl Block for DetailsBlock {
+    type Node = EditorNode;
+    type Input = web_sys::Node;
+    type State = EditorState;
+
+    fn hook(&self, editor: &mut Editor<Self::Node, Self::State, Self::Input>) {
+        editor.bind_key("Enter", |state| {
+            let handled = state.closest_at_cursor("summary").is_some();
+            if handled {
+                state.execute(EnterDetailsContent);
+            }
+            handled
+        });
+        editor.add_slash_item(Suggestion::command("Collapsible section", WrapInDetails));
+        editor.add_toolbar_item(
+            ToolbarItem::new("details", "Collapsible section", WrapInDetails)
+                .with_icon("Details")
+                .with_group("insert"),
+        );
+    }
+
+    fn accepts(&self, node: &web_sys::Node) -> bool {
+        if let Some(element) = node.dyn_ref::<Element>() {
+            element.tag_name() == "DETAILS"
+        } else {
+            false
+        }
+    }
+
+    fn parse(
+        &self,
+        editor: &Editor<Self::Node, EditorState, web_sys::Node>,
+        node: &web_sys::Node,
+    ) -> EditorNode {
+        let details: &Element = node.dyn_ref().unwrap();
+        let mut summary = vec![];
+        let mut content = vec![];
+        // Markup from elsewhere may hold the body directly inside `<details>`.
+        for child in child_nodes(node) {
+            match child.dyn_ref::<Element>() {
+                Some(element) if element.tag_name() == "SUMMARY" => {
+                    summary = process_nodes(editor, child_nodes(&child));
+                }
+                Some(element)
+                    if element.get_attribute("data-type").as_deref() == Some("detailsContent") =>
+                {
+                    content.extend(process_nodes(editor, child_nodes(&child)));
+                }
+                _ => content.extend(process_nodes(editor, [child])),
+            }
+        }
+        EditorNode::Details(Details {
+            open: details.has_attribute("open"),
+            summary,
+            content,
+        })
+    }
+}
+
+/// Places the caret at the start of an element's contents.
+fn caret_into(state: &EditorState, element: &web_sys::Node) {
+    let range = window()
+        .unwrap()
+        .document()
+        .unwrap()
+        .create_range()
+        .unwrap();
+    range.select_node_contents(element).unwrap();
+    range.collapse_with_to_start(true);
+    state.select_range(&range);
+}
+
+/// Wraps the blocks the selection runs through in an open section, leaving the caret in
+/// its empty summary.
+pub struct WrapInDetails;
+
+impl Command<EditorState> for WrapInDetails {
+    fn execute(&self, state: &mut EditorState) {
+        let blocks = state.selected_blocks();
+        let Some(first) = blocks.first() else {
+            return;
+        };
+        let details = Details {
+            open: true,
+            ..Default::default()
+        }
+        .render()
+        .inner_element()
+        .unchecked_into::<Element>();
+        first
+            .parent_node()
+            .unwrap()
+            .insert_before(&details, Some(first))
+            .unwrap();
+        let part = |selectors| details.query_selector(selectors).unwrap().unwrap();
+        let body = part(":scope > [data-type=detailsContent]");
+        for block in &blocks {
+            body.append_child(block).unwrap();
+        }
+        caret_into(state, &part(":scope > summary"));
+    }
+}
+
+/// Opens the section whose summary holds the caret and moves the caret into its body.
+///
+/// Bound to Enter inside a summary by [`DetailsBlock`].
+pub struct EnterDetailsContent;
+
+impl Command<EditorState> for EnterDetailsContent {
+    fn execute(&self, state: &mut EditorState) {
+        let Some(details) = state
+            .closest_at_cursor("summary")
+            .and_then(|summary| summary.parent_element())
+        else {
+            return;
+        };
+        details.set_attribute("open", "").unwrap();
+        let Some(body) = details
+            .query_selector(":scope > [data-type=detailsContent]")
+            .unwrap()
+        else {
+            return;
+        };
+        let first = match body.first_element_child() {
+            Some(first) => first.unchecked_into(),
+            None => {
+                let paragraph = Paragraph::default().render().inner_element();
+                body.append_child(&paragraph).unwrap();
+                paragraph
+            }
+        };
+        caret_into(state, &first);
+    }
+}
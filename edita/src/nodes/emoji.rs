@@ -0,0 +1,437 @@
+use edita_core::{Block, Command, Editor};
+use hirola::{dom::Dom, prelude::*};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::JsCast;
+use web_sys::{Element, HtmlInputElement};
+
+use crate::{
+    nodes::{EditorNode, Node},
+    state::EditorState,
+    suggestion::{Suggestion, SuggestionProvider, MAX_SUGGESTIONS},
+};
+
+#[derive(Clone, Serialize, Deserialize, JsonSchema, Debug, PartialEq, Eq)]
+pub struct Emoji {
+    /// The name typed between colons, eg `smile` for `:smile:`.
+    pub shortcode: String,
+    /// The unicode character(s) of the emoji.
+    pub emoji: String,
+}
+
+impl Emoji {
+    /// Looks up an emoji in the bundled table by its shortcode.
+    pub fn from_shortcode(shortcode: &str) -> Option<Emoji> {
+        EMOJI
+            .iter()
+            .find(|(code, _)| *code == shortcode)
+            .map(|(code, emoji)| Emoji {
+                shortcode: code.to_string(),
+                emoji: emoji.to_string(),
+            })
+    }
+
+    /// Finds the emoji whose shortcode ends `text`, returning the byte offset it starts at.
+    pub fn from_trailing_shortcode(text: &str) -> Option<(usize, Emoji)> {
+        let rest = text.strip_suffix(':')?;
+        let start = rest.rfind(':')?;
+        let shortcode = &rest[start + 1..];
+        let valid = !shortcode.is_empty()
+            && shortcode
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '+' | '-'));
+        if !valid {
+            return None;
+        }
+        Some((start, Emoji::from_shortcode(shortcode)?))
+    }
+}
+
+/// Searches the bundled table, shortcodes starting with `query` coming first.
+pub fn search_emoji(query: &str) -> Vec<Emoji> {
+    let query = query.trim().trim_matches(':').to_lowercase();
+    let (mut prefixed, contained): (Vec<_>, Vec<_>) = EMOJI
+        .iter()
+        .filter(|(code, _)| code.contains(query.as_str()))
+        .partition(|(code, _)| code.starts_with(query.as_str()));
+    prefixed.sort_by_key(|(code, _)| *code != query);
+    prefixed.extend(contained);
+    prefixed
+        .into_iter()
+        .map(|(code, emoji)| Emoji {
+            shortcode: code.to_string(),
+            emoji: emoji.to_string(),
+        })
+        .collect()
+}
+
+/// Suggests emoji from the bundled table for the shortcode being typed, eg. after `:`.
+///
+/// Nothing is suggested until the first letter of the shortcode is typed.
+pub struct EmojiSuggestions;
+
+impl SuggestionProvider for EmojiSuggestions {
+    fn suggest(&self, query: &str) -> Vec<Suggestion> {
+        if query.is_empty() {
+            return vec![];
+        }
+        search_emoji(query)
+            .into_iter()
+            .take(MAX_SUGGESTIONS)
+            .map(|emoji| {
+                let label = emoji.emoji.clone();
+                let detail = format!(":{}:", emoji.shortcode);
+                Suggestion::new(&label, EditorNode::Emoji(emoji)).with_detail(&detail)
+            })
+            .collect()
+    }
+}
+
+impl Node for Emoji {
+    fn render(&self) -> Dom {
+        use hirola::prelude::DefaultAttrStr;
+        use hirola::prelude::DefaultAttributeEffect;
+        html! {
+            <span
+                data-type="emoji"
+                data-node-atom="true"
+                contenteditable="false"
+                data-shortcode=&self.shortcode
+                title=&format!(":{}:", self.shortcode)
+            >
+                {&self.emoji}
+            </span>
+        }
+    }
+}
+
+pub struct EmojiBlock;
+
+impl Block for EmojiBlock {
+    type Node = EditorNode;
+    type Input = web_sys::Node;
+    type State = EditorState;
+
+    fn hook(&self, editor: &mut Editor<Self::Node, Self::State, Self::Input>) {
+        editor.add_input_rule(|state| state.execute(ExpandEmojiShortcode));
+    }
+
+    fn accepts(&self, node: &web_sys::Node) -> bool {
+        if let Some(element) = node.dyn_ref::<Element>() {
+            element.tag_name() == "SPAN"
+                && element
+                    .get_attribute("data-type")
+                    .is_some_and(|v| &v == "emoji")
+        } else {
+            false
+        }
+    }
+
+    fn parse(
+        &self,
+        _editor: &Editor<Self::Node, EditorState, web_sys::Node>,
+        node: &web_sys::Node,
+    ) -> EditorNode {
+        let element: &Element = node.dyn_ref().unwrap();
+        let shortcode = element.get_attribute("data-shortcode").unwrap_or_default();
+        let emoji = element
+            .text_content()
+            .filter(|text| !text.is_empty())
+            .or_else(|| Emoji::from_shortcode(&shortcode).map(|emoji| emoji.emoji))
+            .unwrap_or_default();
+        EditorNode::Emoji(Emoji { shortcode, emoji })
+    }
+}
+
+/// Inserts an emoji at the cursor.
+pub struct InsertEmoji(pub Emoji);
+
+impl Command<EditorState> for InsertEmoji {
+    fn execute(&self, state: &mut EditorState) {
+        if let Some(range) = state.selection_range() {
+            range.delete_contents().unwrap();
+            range.insert_node(&self.0.render().inner_element()).unwrap();
+            range.collapse_with_to_start(false);
+            state.select_range(&range);
+        }
+    }
+}
+
+/// Replaces a `:shortcode:` typed just before the cursor with its emoji.
+///
+/// Registered as an input rule by [`EmojiBlock`].
+pub struct ExpandEmojiShortcode;
+
+impl Command<EditorState> for ExpandEmojiShortcode {
+    fn execute(&self, state: &mut EditorState) {
+        let Some(range) = state.selection_range().filter(|range| range.collapsed()) else {
+            return;
+        };
+        let container = range.start_container().unwrap();
+        if container.node_type() != web_sys::Node::TEXT_NODE {
+            return;
+        }
+        let text = container.text_content().unwrap_or_default();
+        // DOM offsets count UTF-16 code units.
+        let offset = range.start_offset().unwrap() as usize;
+        let before: Vec<u16> = text.encode_utf16().take(offset).collect();
+        let before = String::from_utf16_lossy(&before);
+        if let Some((start, emoji)) = Emoji::from_trailing_shortcode(&before) {
+            let start = before[..start].encode_utf16().count() as u32;
+            range.set_start(&container, start).unwrap();
+            InsertEmoji(emoji).execute(state);
+        }
+    }
+}
+
+/// A searchable picker over the bundled emoji table.
+///
+/// Choosing an emoji inserts it where the cursor was in the editor before the picker
+/// took focus.
+pub fn emoji_picker(state: &EditorState) -> Dom {
+    use hirola::dom::effects::attr_on::*;
+    let results = MutableVec::new_with_values(search_emoji(""));
+    let saved_range = Mutable::new(None);
+    let search = results.clone();
+    let on_input = move |event: web_sys::Event| {
+        let input: HtmlInputElement = event.target().unwrap().unchecked_into();
+        search
+            .lock_mut()
+            .replace_cloned(search_emoji(&input.value()));
+    };
+    let editor = state.clone();
+    let save_range = saved_range.callback(move |saved_range| {
+        if let Some(range) = editor.selection_range() {
+            saved_range.set(Some(range));
+        }
+    });
+    let state = state.clone();
+    html! {
+        <div class="emoji-picker" on:mouse-down=save_range>
+            <input type="search" placeholder="Search emoji" on:input=on_input/>
+            <div class="emoji-picker-results">
+                {results
+                    .signal_vec_cloned()
+                    .map_render(move |emoji| {
+                        let state = state.clone();
+                        let saved_range = saved_range.clone();
+                        let title = format!(":{}:", emoji.shortcode);
+                        let label = emoji.emoji.clone();
+                        html! {
+                            <button
+                                type="button"
+                                title=&title
+                                on:click=move |_| {
+                                    if let Some(range) = saved_range.get_cloned() {
+                                        state.select_range(&range);
+                                    }
+                                    state.execute(InsertEmoji(emoji.clone()));
+                                }
+                            >
+                                {label}
+                            </button>
+                        }
+                    })}
+            </div>
+        </div>
+    }
+}
+
+/// The bundled table of shortcodes and their emoji.
+pub const EMOJI: &[(&str, &str)] = &[
+    ("grinning", "😀"),
+    ("smiley", "😃"),
+    ("smile", "😄"),
+    ("grin", "😁"),
+    ("laughing", "😆"),
+    ("sweat_smile", "😅"),
+    ("rofl", "🤣"),
+    ("joy", "😂"),
+    ("slightly_smiling_face", "🙂"),
+    ("upside_down_face", "🙃"),
+    ("wink", "😉"),
+    ("blush", "😊"),
+    ("innocent", "😇"),
+    ("heart_eyes", "😍"),
+    ("star_struck", "🤩"),
+    ("kissing_heart", "😘"),
+    ("yum", "😋"),
+    ("stuck_out_tongue", "😛"),
+    ("stuck_out_tongue_winking_eye", "😜"),
+    ("zany_face", "🤪"),
+    ("hugs", "🤗"),
+    ("thinking", "🤔"),
+    ("shushing_face", "🤫"),
+    ("neutral_face", "😐"),
+    ("expressionless", "😑"),
+    ("no_mouth", "😶"),
+    ("smirk", "😏"),
+    ("unamused", "😒"),
+    ("roll_eyes", "🙄"),
+    ("grimacing", "😬"),
+    ("relieved", "😌"),
+    ("pensive", "😔"),
+    ("sleepy", "😪"),
+    ("sleeping", "😴"),
+    ("mask", "😷"),
+    ("nerd_face", "🤓"),
+    ("sunglasses", "😎"),
+    ("confused", "😕"),
+    ("worried", "😟"),
+    ("open_mouth", "😮"),
+    ("astonished", "😲"),
+    ("flushed", "😳"),
+    ("pleading_face", "🥺"),
+    ("cry", "😢"),
+    ("sob", "😭"),
+    ("scream", "😱"),
+    ("disappointed", "😞"),
+    ("sweat", "😓"),
+    ("weary", "😩"),
+    ("yawning_face", "🥱"),
+    ("triumph", "😤"),
+    ("rage", "😡"),
+    ("angry", "😠"),
+    ("skull", "💀"),
+    ("poop", "💩"),
+    ("clown_face", "🤡"),
+    ("ghost", "👻"),
+    ("alien", "👽"),
+    ("robot", "🤖"),
+    ("see_no_evil", "🙈"),
+    ("wave", "👋"),
+    ("raised_hand", "✋"),
+    ("ok_hand", "👌"),
+    ("v", "✌️"),
+    ("crossed_fingers", "🤞"),
+    ("point_up", "☝️"),
+    ("point_right", "👉"),
+    ("point_left", "👈"),
+    ("point_down", "👇"),
+    ("+1", "👍"),
+    ("thumbsup", "👍"),
+    ("-1", "👎"),
+    ("thumbsdown", "👎"),
+    ("fist", "✊"),
+    ("clap", "👏"),
+    ("raised_hands", "🙌"),
+    ("pray", "🙏"),
+    ("handshake", "🤝"),
+    ("muscle", "💪"),
+    ("eyes", "👀"),
+    ("brain", "🧠"),
+    ("heart", "❤️"),
+    ("orange_heart", "🧡"),
+    ("yellow_heart", "💛"),
+    ("green_heart", "💚"),
+    ("blue_heart", "💙"),
+    ("purple_heart", "💜"),
+    ("black_heart", "🖤"),
+    ("broken_heart", "💔"),
+    ("sparkling_heart", "💖"),
+    ("100", "💯"),
+    ("boom", "💥"),
+    ("sparkles", "✨"),
+    ("star", "⭐"),
+    ("fire", "🔥"),
+    ("zap", "⚡"),
+    ("sunny", "☀️"),
+    ("cloud", "☁️"),
+    ("umbrella", "☔"),
+    ("snowflake", "❄️"),
+    ("rainbow", "🌈"),
+    ("earth_africa", "🌍"),
+    ("seedling", "🌱"),
+    ("evergreen_tree", "🌲"),
+    ("rose", "🌹"),
+    ("sunflower", "🌻"),
+    ("cat", "🐱"),
+    ("dog", "🐶"),
+    ("unicorn", "🦄"),
+    ("crab", "🦀"),
+    ("bug", "🐛"),
+    ("bee", "🐝"),
+    ("apple", "🍎"),
+    ("pizza", "🍕"),
+    ("hamburger", "🍔"),
+    ("cake", "🍰"),
+    ("coffee", "☕"),
+    ("tea", "🍵"),
+    ("beer", "🍺"),
+    ("tada", "🎉"),
+    ("confetti_ball", "🎊"),
+    ("gift", "🎁"),
+    ("balloon", "🎈"),
+    ("trophy", "🏆"),
+    ("medal_sports", "🏅"),
+    ("soccer", "⚽"),
+    ("video_game", "🎮"),
+    ("musical_note", "🎵"),
+    ("art", "🎨"),
+    ("rocket", "🚀"),
+    ("airplane", "✈️"),
+    ("car", "🚗"),
+    ("house", "🏠"),
+    ("hourglass", "⌛"),
+    ("alarm_clock", "⏰"),
+    ("calendar", "📆"),
+    ("memo", "📝"),
+    ("pencil2", "✏️"),
+    ("book", "📖"),
+    ("books", "📚"),
+    ("bookmark", "🔖"),
+    ("link", "🔗"),
+    ("paperclip", "📎"),
+    ("pushpin", "📌"),
+    ("mag", "🔍"),
+    ("bulb", "💡"),
+    ("lock", "🔒"),
+    ("unlock", "🔓"),
+    ("key", "🔑"),
+    ("hammer", "🔨"),
+    ("wrench", "🔧"),
+    ("gear", "⚙️"),
+    ("package", "📦"),
+    ("computer", "💻"),
+    ("keyboard", "⌨️"),
+    ("iphone", "📱"),
+    ("email", "📧"),
+    ("bell", "🔔"),
+    ("speech_balloon", "💬"),
+    ("chart_with_upwards_trend", "📈"),
+    ("moneybag", "💰"),
+    ("white_check_mark", "✅"),
+    ("heavy_check_mark", "✔️"),
+    ("x", "❌"),
+    ("warning", "⚠️"),
+    ("no_entry", "⛔"),
+    ("question", "❓"),
+    ("exclamation", "❗"),
+    ("information_source", "ℹ️"),
+    ("arrow_right", "➡️"),
+    ("arrow_left", "⬅️"),
+    ("arrow_up", "⬆️"),
+    ("arrow_down", "⬇️"),
+    ("recycle", "♻️"),
+    ("construction", "🚧"),
+    ("checkered_flag", "🏁"),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_emoji_lookup() {
+        assert_eq!(Emoji::from_shortcode("tada").unwrap().emoji, "🎉");
+        let (start, emoji) = Emoji::from_trailing_shortcode("So done :+1:").unwrap();
+        assert_eq!((start, emoji.emoji.as_str()), (8, "👍"));
+        assert!(Emoji::from_trailing_shortcode("at 10:30:").is_none());
+        assert!(Emoji::from_trailing_shortcode("a :not an emoji:").is_none());
+        let results = search_emoji(":heart");
+        assert_eq!(results[0].shortcode, "heart");
+        assert!(results
+            .iter()
+            .any(|emoji| emoji.shortcode == "broken_heart"));
+    }
+}
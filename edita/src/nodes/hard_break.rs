@@ -0,0 +1,71 @@
+use edita_core::{Block, Command, Editor};
+use hirola::{dom::Dom, prelude::*};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::JsCast;
+use web_sys::Element;
+
+use crate::{
+    nodes::{EditorNode, Node},
+    state::EditorState,
+};
+
+#[derive(Clone, Serialize, Deserialize, JsonSchema, Default, Debug)]
+pub struct HardBreak;
+
+impl Node for HardBreak {
+    fn render(&self) -> Dom {
+        html! { <br/> }
+    }
+}
+
+pub struct HardBreakBlock;
+
+impl Block for HardBreakBlock {
+    type Node = EditorNode;
+    type Input = web_sys::Node;
+    type State = EditorState;
+
+    fn hook(&self, editor: &mut Editor<Self::Node, Self::State, Self::Input>) {
+        editor.bind_key("Shift-Enter", |state| {
+            let handled = state.closest_at_cursor("p").is_some();
+            if handled {
+                state.execute(InsertHardBreak);
+            }
+            handled
+        });
+    }
+
+    fn accepts(&self, node: &web_sys::Node) -> bool {
+        if let Some(element) = node.dyn_ref::<Element>() {
+            element.tag_name() == "BR"
+        } else {
+            false
+        }
+    }
+
+    fn parse(
+        &self,
+        _editor: &Editor<Self::Node, EditorState, web_sys::Node>,
+        _node: &web_sys::Node,
+    ) -> EditorNode {
+        EditorNode::HardBreak(HardBreak)
+    }
+}
+
+/// Inserts a line break at the cursor.
+pub struct InsertHardBreak;
+
+impl Command<EditorState> for InsertHardBreak {
+    fn execute(&self, state: &mut EditorState) {
+        let Some(range) = state.selection_range() else {
+            return;
+        };
+        let line_break = HardBreak.render().inner_element();
+        range.delete_contents().unwrap();
+        range.insert_node(&line_break).unwrap();
+        range.set_start_after(&line_break).unwrap();
+        range.collapse_with_to_start(true);
+        state.select_range(&range);
+    }
+}
@@ -1,15 +1,28 @@
 use edita_core::{Block, Command, Editor};
 use hirola::{dom::Dom, prelude::*};
-use serde::Serialize;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 use wasm_bindgen::JsCast;
-use web_sys::Element;
+use web_sys::{window, Element};
 
-use crate::{nodes::EditorNode, state::EditorState};
+use crate::{
+    nodes::{BulletList, EditorNode, OrderedList},
+    state::EditorState,
+    suggestion::Suggestion,
+    toolbar::ToolbarItem,
+};
 
-#[derive(Clone, Serialize, Debug)]
+/// The attribute on the editor root that turns on section numbering.
+pub const NUMBERED_HEADINGS: &str = "data-numbered-headings";
+
+/// Shows the number of each heading before it while [`NumberHeadings`] is on.
+pub const NUMBERED_HEADINGS_CSS: &str = "[data-numbered-headings] \
+    :is(h1, h2, h3, h4, h5, h6)[data-number]::before { content: attr(data-number) \" \"; }";
+
+#[derive(Clone, Serialize, Deserialize, JsonSchema, Debug)]
 pub struct Header {
-    text: String,
-    level: u8,
+    pub text: String,
+    pub level: u8,
 }
 
 impl Default for Header {
@@ -21,18 +34,234 @@ impl Default for Header {
     }
 }
 
+impl Header {
+    /// The `<hN>` tag of the heading, levels outside 1 to 6 being clamped.
+    pub fn tag(&self) -> String {
+        format!("h{}", self.level.clamp(1, 6))
+    }
+
+    /// The slug of the heading's text, see [`slugify`].
+    ///
+    /// Headings with the same text share a slug, the ids they are rendered and exported
+    /// with get a `-1`, `-2` suffix from [`HeadingAnchors`].
+    pub fn anchor(&self) -> String {
+        slugify(&self.text)
+    }
+}
+
 impl crate::nodes::Node for Header {
     fn render(&self) -> Dom {
-        html! { <h1 data-ph="Heading">{self.text.clone()}</h1> }
+        let heading = Dom::element(&self.tag());
+        heading.set_attribute("data-ph", "Heading");
+        heading.set_attribute("id", &self.anchor());
+        heading.append_child(&Dom::text_node(&self.text));
+        heading
+    }
+}
+
+/// Turns text into an anchor id, eg. `"Getting Started!"` into `getting-started`.
+///
+/// Letters and digits are lowercased, runs of whitespace, dashes and underscores become a
+/// single dash and everything else is dropped. Text without any letter gives `heading`.
+pub fn slugify(text: &str) -> String {
+    let mut slug = String::new();
+    for c in text.chars() {
+        if c.is_alphanumeric() {
+            slug.extend(c.to_lowercase());
+        } else if (c.is_whitespace() || c == '-' || c == '_') && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    let slug = slug.trim_matches('-');
+    if slug.is_empty() {
+        "heading".to_owned()
+    } else {
+        slug.to_owned()
+    }
+}
+
+/// Hands out the unique ids of the headings of a document, in document order.
+///
+/// The first heading with a given slug gets the slug itself, the next ones a `-1`, `-2`
+/// suffix, matching the anchors of [`table_of_contents`].
+#[derive(Default)]
+pub struct HeadingAnchors {
+    used: Vec<String>,
+}
+
+impl HeadingAnchors {
+    /// The id of the next heading, with `text`.
+    pub fn next(&mut self, text: &str) -> String {
+        let slug = slugify(text);
+        let mut anchor = slug.clone();
+        let mut repeat = 0;
+        while self.used.contains(&anchor) {
+            repeat += 1;
+            anchor = format!("{slug}-{repeat}");
+        }
+        self.used.push(anchor.clone());
+        anchor
     }
 }
 
+/// A heading in the outline of a document.
+#[derive(Clone, Serialize, Deserialize, JsonSchema, Debug, PartialEq, Eq)]
+pub struct TocEntry {
+    pub level: u8,
+    pub text: String,
+    /// The unique id of the heading, repeated slugs getting a `-1`, `-2` suffix.
+    pub anchor: String,
+    /// The section number, eg. `2.1`, counted by position in the tree so that skipped
+    /// levels do not leave zeros.
+    pub number: String,
+    /// The headings of a deeper level up to the next heading of this level or above.
+    pub children: Vec<TocEntry>,
+}
+
+/// Gives each heading, as a `(level, text)` pair, its unique anchor, section number and
+/// depth in the outline.
+fn outline<'a>(headings: impl IntoIterator<Item = (u8, &'a str)>) -> Vec<(String, String, usize)> {
+    let mut anchors = HeadingAnchors::default();
+    // The open sections, as their level, number and count of subsections.
+    let mut open: Vec<(u8, Vec<u32>, u32)> = vec![];
+    let mut top_level = 0;
+    let mut outline = vec![];
+    for (level, text) in headings {
+        let anchor = anchors.next(text);
+
+        while open.last().is_some_and(|(open, _, _)| *open >= level) {
+            open.pop();
+        }
+        let number = match open.last_mut() {
+            Some((_, number, sections)) => {
+                *sections += 1;
+                let mut number = number.clone();
+                number.push(*sections);
+                number
+            }
+            None => {
+                top_level += 1;
+                vec![top_level]
+            }
+        };
+        let depth = number.len() - 1;
+        let label = number
+            .iter()
+            .map(u32::to_string)
+            .collect::<Vec<_>>()
+            .join(".");
+        open.push((level, number, 0));
+        outline.push((anchor, label, depth));
+    }
+    outline
+}
+
+fn collect_headings<'a>(nodes: &'a [EditorNode], headings: &mut Vec<&'a Header>) {
+    for node in nodes {
+        match node {
+            EditorNode::Heading(header) => headings.push(header),
+            EditorNode::Details(details) => collect_headings(&details.content, headings),
+            EditorNode::ListItem(item) => collect_headings(&item.content, headings),
+            EditorNode::BlockQuote(quote) => collect_headings(&quote.content, headings),
+            EditorNode::BulletList(BulletList { list })
+            | EditorNode::OrderedList(OrderedList { list, .. }) => {
+                for item in list {
+                    collect_headings(&item.content, headings);
+                }
+            }
+            EditorNode::Table(table) => {
+                for row in &table.rows {
+                    for cell in &row.cells {
+                        collect_headings(&cell.content, headings);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Builds the heading tree of a document, including headings nested in other blocks.
+pub fn table_of_contents(nodes: &[EditorNode]) -> Vec<TocEntry> {
+    let mut headings = vec![];
+    collect_headings(nodes, &mut headings);
+    let outline = outline(headings.iter().map(|h| (h.level, h.text.as_str())));
+    let mut flat = headings
+        .iter()
+        .zip(outline)
+        .map(|(header, (anchor, number, depth))| {
+            let entry = TocEntry {
+                level: header.level,
+                text: header.text.clone(),
+                anchor,
+                number,
+                children: vec![],
+            };
+            (depth, entry)
+        })
+        .peekable();
+    nest(&mut flat, 0)
+}
+
+fn nest(
+    flat: &mut std::iter::Peekable<impl Iterator<Item = (usize, TocEntry)>>,
+    depth: usize,
+) -> Vec<TocEntry> {
+    let mut entries: Vec<TocEntry> = vec![];
+    while let Some((entry_depth, _)) = flat.peek() {
+        if *entry_depth < depth {
+            break;
+        }
+        if *entry_depth > depth {
+            let children = nest(flat, depth + 1);
+            if let Some(parent) = entries.last_mut() {
+                parent.children.extend(children);
+            }
+            continue;
+        }
+        let (_, entry) = flat.next().unwrap();
+        entries.push(entry);
+    }
+    entries
+}
+
+const HEADINGS: &str = "h1, h2, h3, h4, h5, h6";
+
 pub struct HeaderBlock;
 
 impl Block for HeaderBlock {
     type Node = EditorNode;
     type Input = web_sys::Node;
     type State = EditorState;
+    fn hook(&self, editor: &mut Editor<Self::Node, Self::State, Self::Input>) {
+        editor.add_input_rule(|state| state.execute(UpdateHeadingAnchors));
+        editor.add_toolbar_item(
+            ToolbarItem::new("paragraph", "Paragraph", SetHeadingLevel(0))
+                .with_icon("P")
+                .with_group("blocks")
+                .active_inside("p"),
+        );
+        for level in 1..=3 {
+            let heading = Header {
+                level,
+                ..Default::default()
+            };
+            editor.add_slash_item(Suggestion::new(
+                &format!("Heading {level}"),
+                EditorNode::Heading(heading),
+            ));
+            editor.add_toolbar_item(
+                ToolbarItem::new(
+                    &format!("heading-{level}"),
+                    &format!("Heading {level}"),
+                    SetHeadingLevel(level),
+                )
+                .with_icon(&format!("H{level}"))
+                .with_group("blocks")
+                .active_inside(&format!("h{level}")),
+            );
+        }
+    }
     fn accepts(&self, node: &web_sys::Node) -> bool {
         if let Some(element) = node.dyn_ref::<Element>() {
             matches!(
@@ -69,6 +298,140 @@ impl Block for HeaderBlock {
 
 impl Command<EditorState> for HeaderBlock {
     fn execute(&self, state: &mut EditorState) {
-        state.add_node(Header::default())
+        state.insert_block(Header::default())
+    }
+}
+
+/// Turns the heading or paragraph at the cursor into a heading of the given level, a level
+/// of 0 turning it into a paragraph.
+pub struct SetHeadingLevel(pub u8);
+
+impl Command<EditorState> for SetHeadingLevel {
+    fn execute(&self, state: &mut EditorState) {
+        let Some(block) = state.closest_at_cursor(&format!("{HEADINGS}, p")) else {
+            return;
+        };
+        let (tag, placeholder) = match self.0 {
+            0 => ("p".to_owned(), "A paragraph goes here"),
+            level => (format!("h{}", level.min(6)), "Heading"),
+        };
+        if block.tag_name().eq_ignore_ascii_case(&tag) {
+            return;
+        }
+        let range = state.selection_range();
+        let caret = range
+            .as_ref()
+            .and_then(|range| Some((range.start_container().ok()?, range.start_offset().ok()?)));
+        let document = window().unwrap().document().unwrap();
+        let replacement = document.create_element(&tag).unwrap();
+        replacement.set_attribute("data-ph", placeholder).unwrap();
+        while let Some(child) = block.first_child() {
+            replacement.append_child(&child).unwrap();
+        }
+        block.replace_with_with_node_1(&replacement).unwrap();
+        if let Some((container, offset)) = caret {
+            let container = match &container == block.unchecked_ref::<web_sys::Node>() {
+                true => replacement.clone().unchecked_into(),
+                false => container,
+            };
+            let range = document.create_range().unwrap();
+            range.set_start(&container, offset).unwrap();
+            range.collapse_with_to_start(true);
+            state.select_range(&range);
+        }
+        state.execute(UpdateHeadingAnchors);
+    }
+}
+
+/// Gives the headings of the editor their unique anchor ids and section numbers.
+///
+/// Registered as an input rule by [`HeaderBlock`], so the ids follow the text as it is
+/// typed and match the anchors of [`table_of_contents`].
+pub struct UpdateHeadingAnchors;
+
+impl Command<EditorState> for UpdateHeadingAnchors {
+    fn execute(&self, state: &mut EditorState) {
+        let root = state.node().get().inner_element();
+        let headings = root
+            .unchecked_ref::<Element>()
+            .query_selector_all(HEADINGS)
+            .unwrap();
+        let headings: Vec<Element> = (0..headings.length())
+            .filter_map(|i| headings.get(i)?.dyn_into().ok())
+            .collect();
+        let texts: Vec<(u8, String)> = headings
+            .iter()
+            .map(|heading| {
+                let level = heading.tag_name()[1..].parse().unwrap_or(1);
+                (level, heading.text_content().unwrap_or_default())
+            })
+            .collect();
+        let outline = outline(texts.iter().map(|(level, text)| (*level, text.as_str())));
+        for (heading, (anchor, number, _)) in headings.iter().zip(outline) {
+            if heading.id() != anchor {
+                heading.set_id(&anchor);
+            }
+            if heading.get_attribute("data-number").as_deref() != Some(&number) {
+                heading.set_attribute("data-number", &number).unwrap();
+            }
+        }
+    }
+}
+
+/// Turns automatic section numbering on or off, see [`NUMBERED_HEADINGS_CSS`].
+pub struct NumberHeadings(pub bool);
+
+impl Command<EditorState> for NumberHeadings {
+    fn execute(&self, state: &mut EditorState) {
+        let root = state.node().get().inner_element();
+        let root: &Element = root.unchecked_ref();
+        if self.0 {
+            root.set_attribute(NUMBERED_HEADINGS, "").unwrap();
+        } else {
+            root.remove_attribute(NUMBERED_HEADINGS).unwrap();
+        }
+        state.execute(UpdateHeadingAnchors);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn heading(level: u8, text: &str) -> EditorNode {
+        EditorNode::Heading(Header {
+            text: text.to_owned(),
+            level,
+        })
+    }
+
+    #[test]
+    fn test_table_of_contents() {
+        assert_eq!(
+            slugify("  Getting Started: a_b -- C++! "),
+            "getting-started-a-b-c"
+        );
+        let toc = table_of_contents(&[
+            heading(1, "Intro"),
+            heading(3, "Details"),
+            heading(2, "Intro"),
+            heading(1, "Usage"),
+            heading(2, "?"),
+        ]);
+        let outline: Vec<_> = toc
+            .iter()
+            .flat_map(|entry| std::iter::once(entry).chain(&entry.children))
+            .map(|entry| (entry.number.as_str(), entry.anchor.as_str()))
+            .collect();
+        assert_eq!(
+            outline,
+            [
+                ("1", "intro"),
+                ("1.1", "details"),
+                ("1.2", "intro-1"),
+                ("2", "usage"),
+                ("2.1", "heading"),
+            ]
+        );
     }
 }
@@ -0,0 +1,97 @@
+use edita_core::{Block, Command, Editor};
+use hirola::{dom::Dom, prelude::*};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::JsCast;
+use web_sys::{window, Element};
+
+use crate::{
+    nodes::{EditorNode, Node, Paragraph},
+    state::EditorState,
+    suggestion::Suggestion,
+    toolbar::ToolbarItem,
+};
+
+#[derive(Clone, Serialize, Deserialize, JsonSchema, Default, Debug)]
+pub struct HorizontalRule;
+
+impl Node for HorizontalRule {
+    fn render(&self) -> Dom {
+        html! { <hr contenteditable="false"/> }
+    }
+}
+
+pub struct HorizontalRuleBlock;
+
+impl Block for HorizontalRuleBlock {
+    type Node = EditorNode;
+    type Input = web_sys::Node;
+    type State = EditorState;
+
+    fn hook(&self, editor: &mut Editor<Self::Node, Self::State, Self::Input>) {
+        editor.add_input_rule(|state| state.execute(ExpandRuleShortcut));
+        editor.add_slash_item(Suggestion::new(
+            "Divider",
+            EditorNode::HorizontalRule(HorizontalRule),
+        ));
+        editor.add_toolbar_item(
+            ToolbarItem::new("rule", "Divider", InsertHorizontalRule)
+                .with_icon("HR")
+                .with_group("insert"),
+        );
+    }
+
+    fn accepts(&self, node: &web_sys::Node) -> bool {
+        if let Some(element) = node.dyn_ref::<Element>() {
+            element.tag_name() == "HR"
+        } else {
+            false
+        }
+    }
+
+    fn parse(
+        &self,
+        _editor: &Editor<Self::Node, EditorState, web_sys::Node>,
+        _node: &web_sys::Node,
+    ) -> EditorNode {
+        EditorNode::HorizontalRule(HorizontalRule)
+    }
+}
+
+/// Inserts a rule after the block at the cursor, or at the end of the editor.
+pub struct InsertHorizontalRule;
+
+impl Command<EditorState> for InsertHorizontalRule {
+    fn execute(&self, state: &mut EditorState) {
+        state.insert_block(HorizontalRule);
+    }
+}
+
+/// Turns a paragraph holding only `---` into a rule followed by an empty paragraph.
+///
+/// Registered as an input rule by [`HorizontalRuleBlock`].
+pub struct ExpandRuleShortcut;
+
+impl Command<EditorState> for ExpandRuleShortcut {
+    fn execute(&self, state: &mut EditorState) {
+        let Some(paragraph) = state.closest_at_cursor("p") else {
+            return;
+        };
+        if paragraph.text_content().unwrap_or_default().trim() != "---" {
+            return;
+        }
+        let next = Paragraph::default().render().inner_element();
+        paragraph
+            .replace_with_with_node_2(&HorizontalRule.render().inner_element(), &next)
+            .unwrap();
+        let range = window()
+            .unwrap()
+            .document()
+            .unwrap()
+            .create_range()
+            .unwrap();
+        range.select_node_contents(&next).unwrap();
+        range.collapse_with_to_start(true);
+        state.select_range(&range);
+    }
+}
@@ -1,22 +1,41 @@
-use edita_core::Block;
-use hirola::prelude::html;
-use serde::Serialize;
-use wasm_bindgen::JsCast;
-use web_sys::{Element, HtmlImageElement};
+use std::cell::RefCell;
 
-use edita_core::{Editor};
+use edita_core::{process_nodes, Block, Editor};
+use hirola::dom::Dom;
+use hirola::prelude::*;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::{closure::Closure, JsCast};
+use web_sys::{window, Element, HtmlElement, HtmlImageElement, MouseEvent};
 
 use crate::{nodes::EditorNode, EditorState};
 
+/// The element holding the resize handles of the selected image, kept outside the editor so
+/// that it never ends up in the content.
+const RESIZER: &str = "[data-image-resizer]";
+
+/// Images are never resized below this width, in css pixels.
+const MIN_WIDTH: f64 = 16.0;
+
 pub struct ImageBlock;
 
 impl Block for ImageBlock {
     type Input = web_sys::Node;
     type Node = EditorNode;
     type State = EditorState;
+
+    fn hook(&self, editor: &mut Editor<Self::Node, Self::State, Self::Input>) {
+        editor.on_click(|state, target| match target.dyn_ref::<HtmlImageElement>() {
+            Some(image) => show_resizer(state, image),
+            None => hide_resizer(),
+        });
+    }
+
     fn accepts(&self, node: &web_sys::Node) -> bool {
         if let Some(element) = node.dyn_ref::<Element>() {
             element.tag_name() == "IMG"
+                || (element.tag_name() == "FIGURE"
+                    && element.query_selector("img").ok().flatten().is_some())
         } else {
             false
         }
@@ -24,31 +43,286 @@ impl Block for ImageBlock {
 
     fn parse(
         &self,
-        _editor: &Editor<EditorNode, EditorState, web_sys::Node>,
+        editor: &Editor<EditorNode, EditorState, web_sys::Node>,
         node: &web_sys::Node,
     ) -> EditorNode {
-        if let Some(img_element) = node.dyn_ref::<HtmlImageElement>() {
-            EditorNode::Image(Image {
-                src: img_element.src(),
-                alt: img_element.alt(),
-                // Add more attributes if needed
-            })
+        let element: &Element = node.unchecked_ref();
+        let (image, caption) = if element.tag_name() == "FIGURE" {
+            let image = element.query_selector("img").unwrap().unwrap();
+            let caption = element
+                .query_selector(":scope > figcaption")
+                .unwrap()
+                .map(|caption| {
+                    let children = caption.child_nodes();
+                    process_nodes(
+                        editor,
+                        (0..children.length()).filter_map(|i| children.get(i)),
+                    )
+                })
+                .unwrap_or_default();
+            (image, caption)
         } else {
-            panic!("not an image")
+            (element.clone(), vec![])
+        };
+        let image: &HtmlImageElement = image.unchecked_ref();
+        // Sizes set while resizing live in the style until the attributes catch up.
+        let dimension = |name: &str| {
+            let style = image.style().get_property_value(name).unwrap_or_default();
+            style
+                .strip_suffix("px")
+                .and_then(|px| px.trim().parse::<f64>().ok())
+                .map(|px| px.round() as u32)
+                .or_else(|| image.get_attribute(name)?.trim().parse().ok())
+        };
+        let align = [element, image]
+            .iter()
+            .find_map(|element| element.get_attribute("data-align"))
+            .and_then(|align| ImageAlign::from_attribute(&align));
+        EditorNode::Image(Image {
+            src: image.src(),
+            alt: image.alt(),
+            title: image.title(),
+            width: dimension("width"),
+            height: dimension("height"),
+            align,
+            caption,
+        })
+    }
+}
+
+/// How an image sits among the text around it.
+#[derive(Clone, Copy, Serialize, Deserialize, JsonSchema, Debug, PartialEq, Eq)]
+pub enum ImageAlign {
+    /// Floated to the left, text flowing on its right.
+    Left,
+    /// On a line of its own, centered.
+    Center,
+    /// Floated to the right, text flowing on its left.
+    Right,
+}
+
+impl ImageAlign {
+    /// The alignment of a `data-align` attribute.
+    pub fn from_attribute(value: &str) -> Option<Self> {
+        match value {
+            "left" => Some(ImageAlign::Left),
+            "center" => Some(ImageAlign::Center),
+            "right" => Some(ImageAlign::Right),
+            _ => None,
+        }
+    }
+
+    /// The value of the `data-align` attribute for the alignment.
+    pub fn attribute(self) -> &'static str {
+        match self {
+            ImageAlign::Left => "left",
+            ImageAlign::Center => "center",
+            ImageAlign::Right => "right",
         }
     }
 }
 
-#[derive(Clone, Serialize, Debug)]
+#[derive(Clone, Serialize, Deserialize, JsonSchema, Default, Debug)]
 pub struct Image {
     pub src: String,
     pub alt: String,
+    /// The tooltip of the image.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub title: String,
+    /// The displayed width in css pixels, the natural width when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub width: Option<u32>,
+    /// The displayed height in css pixels, following the width when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub height: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub align: Option<ImageAlign>,
+    /// The inline nodes of the caption, the image being rendered in a `<figure>` when there
+    /// is one.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub caption: Vec<EditorNode>,
+}
+
+impl Image {
+    /// An image with no caption, size or alignment.
+    pub fn new(src: &str, alt: &str) -> Self {
+        Image {
+            src: src.to_owned(),
+            alt: alt.to_owned(),
+            ..Default::default()
+        }
+    }
+
+    /// The plain text of the caption.
+    pub fn caption_text(&self) -> String {
+        self.caption.iter().map(EditorNode::text).collect()
+    }
 }
 
 impl crate::nodes::Node for Image {
-    fn render(&self) -> hirola::dom::Dom {
-        use hirola::prelude::DefaultAttrStr;
-        use hirola::prelude::DefaultAttributeEffect;
-        html! { <img src=&self.src alt=&self.alt/> }
+    fn render(&self) -> Dom {
+        let image = Dom::element("img");
+        image.set_attribute("src", &self.src);
+        image.set_attribute("alt", &self.alt);
+        if !self.title.is_empty() {
+            image.set_attribute("title", &self.title);
+        }
+        if let Some(width) = self.width {
+            image.set_attribute("width", &width.to_string());
+        }
+        if let Some(height) = self.height {
+            image.set_attribute("height", &height.to_string());
+        }
+        let align = self.align.map(ImageAlign::attribute);
+        if self.caption.is_empty() {
+            if let Some(align) = align {
+                image.set_attribute("data-align", align);
+            }
+            return image;
+        }
+        let figure = Dom::element("figure");
+        if let Some(align) = align {
+            figure.set_attribute("data-align", align);
+        }
+        figure.append_child(&image);
+        let caption = Dom::element("figcaption");
+        for node in &self.caption {
+            caption.append_child(&node.render());
+        }
+        figure.append_child(&caption);
+        figure
     }
 }
+
+type MouseListener = Closure<dyn FnMut(MouseEvent)>;
+
+thread_local! {
+    /// The listeners of the resize handles on screen and of the drag in progress, dropped
+    /// when they are replaced.
+    static RESIZE_LISTENERS: RefCell<Vec<MouseListener>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Removes the resize handles.
+fn hide_resizer() {
+    let document = window().unwrap().document().unwrap();
+    if let Some(resizer) = document.query_selector(RESIZER).unwrap() {
+        resizer.remove();
+    }
+}
+
+/// Lays the resizer out over the image.
+fn place_resizer(resizer: &HtmlElement, image: &HtmlImageElement) {
+    let window = window().unwrap();
+    let rect = image.get_bounding_client_rect();
+    let style = resizer.style();
+    let px = |value: f64| format!("{value}px");
+    style
+        .set_property("left", &px(rect.left() + window.scroll_x().unwrap()))
+        .unwrap();
+    style
+        .set_property("top", &px(rect.top() + window.scroll_y().unwrap()))
+        .unwrap();
+    style.set_property("width", &px(rect.width())).unwrap();
+    style.set_property("height", &px(rect.height())).unwrap();
+}
+
+/// Shows handles on the corners of an image that resize it when dragged, keeping its
+/// aspect ratio.
+fn show_resizer(state: &EditorState, image: &HtmlImageElement) {
+    hide_resizer();
+    let document = window().unwrap().document().unwrap();
+    let resizer: HtmlElement = document.create_element("div").unwrap().unchecked_into();
+    resizer.set_attribute("data-image-resizer", "").unwrap();
+    resizer
+        .set_attribute(
+            "style",
+            "position: absolute; box-sizing: border-box; outline: 1px solid #68cef8; \
+             pointer-events: none;",
+        )
+        .unwrap();
+    place_resizer(&resizer, image);
+    let mut listeners = vec![];
+    for (corner, position, cursor) in [
+        ("nw", "left: -4px; top: -4px", "nwse-resize"),
+        ("ne", "right: -4px; top: -4px", "nesw-resize"),
+        ("sw", "left: -4px; bottom: -4px", "nesw-resize"),
+        ("se", "right: -4px; bottom: -4px", "nwse-resize"),
+    ] {
+        let handle = document.create_element("span").unwrap();
+        handle.set_attribute("data-handle", corner).unwrap();
+        handle
+            .set_attribute(
+                "style",
+                &format!(
+                    "position: absolute; {position}; width: 8px; height: 8px; \
+                     background: #68cef8; pointer-events: auto; cursor: {cursor};"
+                ),
+            )
+            .unwrap();
+        // Handles on the left grow the image when dragged to the left.
+        let direction = if corner.ends_with('w') { -1.0 } else { 1.0 };
+        let (state, image, dragged) = (state.clone(), image.clone(), resizer.clone());
+        let start = MouseListener::new(move |event: MouseEvent| {
+            event.prevent_default();
+            start_drag(&state, &image, &dragged, event.client_x(), direction);
+        });
+        handle
+            .add_event_listener_with_callback("mousedown", start.as_ref().unchecked_ref())
+            .unwrap();
+        resizer.append_child(&handle).unwrap();
+        listeners.push(start);
+    }
+    document.body().unwrap().append_child(&resizer).unwrap();
+    RESIZE_LISTENERS.with(|current| *current.borrow_mut() = listeners);
+}
+
+/// Follows the mouse until it is released, resizing the image from its width when the
+/// drag started at `x`.
+fn start_drag(
+    state: &EditorState,
+    image: &HtmlImageElement,
+    resizer: &HtmlElement,
+    x: i32,
+    direction: f64,
+) {
+    let window = window().unwrap();
+    let start_width = image.get_bounding_client_rect().width();
+    let ratio = match (image.natural_width(), image.natural_height()) {
+        (width, height) if width > 0 && height > 0 => height as f64 / width as f64,
+        _ => image.get_bounding_client_rect().height() / start_width.max(1.0),
+    };
+    let (moved_image, moved_resizer) = (image.clone(), resizer.clone());
+    let on_move = MouseListener::new(move |event: MouseEvent| {
+        let width = (start_width + direction * (event.client_x() - x) as f64).max(MIN_WIDTH);
+        let style = moved_image.style();
+        style
+            .set_property("width", &format!("{}px", width.round()))
+            .unwrap();
+        style
+            .set_property("height", &format!("{}px", (width * ratio).round()))
+            .unwrap();
+        place_resizer(&moved_resizer, &moved_image);
+    });
+    let (state, image) = (state.clone(), image.clone());
+    let on_release = MouseListener::new(move |_: MouseEvent| {
+        let window = web_sys::window().unwrap();
+        window.set_onmousemove(None);
+        window.set_onmouseup(None);
+        // The final size moves to the attributes, where the content keeps it.
+        let rect = image.get_bounding_client_rect();
+        let style = image.style();
+        style.remove_property("width").unwrap();
+        style.remove_property("height").unwrap();
+        image.set_width(rect.width().round() as u32);
+        image.set_height(rect.height().round() as u32);
+        state.notify();
+    });
+    window.set_onmousemove(Some(on_move.as_ref().unchecked_ref()));
+    window.set_onmouseup(Some(on_release.as_ref().unchecked_ref()));
+    RESIZE_LISTENERS.with(|current| {
+        let mut current = current.borrow_mut();
+        // Listeners of an earlier drag are not running anymore and can go.
+        current.truncate(4);
+        current.extend([on_move, on_release]);
+    });
+}
@@ -0,0 +1,387 @@
+use std::ops::Range;
+
+use edita_core::{Block, Command, Editor};
+use hirola::dom::Dom;
+use hirola::prelude::*;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::JsCast;
+use web_sys::{window, Element};
+
+use crate::{
+    nodes::{EditorNode, Node, TextNode},
+    state::EditorState,
+    toolbar::ToolbarItem,
+};
+
+/// The protocols links may point to. Hrefs with any other scheme, like `javascript:`, are
+/// dropped.
+pub const LINK_PROTOCOLS: &[&str] = &["http", "https", "mailto", "tel"];
+
+/// Characters ending a typed or pasted url that belong to the sentence around it.
+const TRAILING_PUNCTUATION: &[char] = &['.', ',', ';', ':', '!', '?', '\'', '"', ')', ']'];
+
+/// Checks an href against [`LINK_PROTOCOLS`], returning it trimmed when it is allowed.
+///
+/// Relative hrefs, like `/docs` or `#intro`, have no scheme and are always allowed.
+pub fn sanitize_href(href: &str) -> Option<String> {
+    let href = href.trim();
+    // Browsers ignore whitespace and control characters inside the scheme, so
+    // `java\nscript:` must be caught too.
+    let compact: String = href
+        .chars()
+        .filter(|c| !c.is_whitespace() && !c.is_control())
+        .collect();
+    let scheme = compact
+        .split_once(':')
+        .map(|(scheme, _)| scheme)
+        .filter(|scheme| !scheme.contains(['/', '?', '#']));
+    match scheme {
+        Some(scheme)
+            if !LINK_PROTOCOLS
+                .iter()
+                .any(|allowed| allowed.eq_ignore_ascii_case(scheme)) =>
+        {
+            None
+        }
+        _ => Some(href.to_owned()),
+    }
+}
+
+/// The href of a word that looks like a url, eg. `https://example.com` or `www.example.com`.
+fn url_href(word: &str) -> Option<String> {
+    let lower = word.to_ascii_lowercase();
+    let href = if lower.starts_with("www.") {
+        format!("https://{word}")
+    } else if ["http://", "https://"]
+        .iter()
+        .any(|scheme| lower.starts_with(scheme))
+        || lower.starts_with("mailto:") && word.contains('@')
+    {
+        word.to_owned()
+    } else {
+        return None;
+    };
+    let host = href.split_once("://").map_or("", |(_, rest)| rest);
+    let host = host.split(['/', '?', '#']).next().unwrap_or_default();
+    if href.starts_with("mailto:") || host.contains('.') && !host.ends_with('.') {
+        sanitize_href(&href)
+    } else {
+        None
+    }
+}
+
+/// The href of `text` when it is a single url, eg. a pasted one.
+pub fn detect_url(text: &str) -> Option<String> {
+    let text = text.trim();
+    if text.contains(char::is_whitespace) {
+        return None;
+    }
+    url_href(text)
+}
+
+/// Finds the url ending the last word of `text`, returning its byte range and href.
+///
+/// Brackets and quotes around the url and punctuation closing the sentence after it are
+/// left out, keeping a `)` that closes a `(` inside the url.
+pub fn trailing_url(text: &str) -> Option<(Range<usize>, String)> {
+    let word_start = text
+        .rfind(char::is_whitespace)
+        .map_or(0, |i| i + text[i..].chars().next().unwrap().len_utf8());
+    let mut word = text[word_start..].trim_start_matches(['(', '[', '<', '\'', '"']);
+    let start = text.len() - word.len();
+    while let Some(last) = word.chars().last() {
+        let balanced = last == ')' && word.matches('(').count() >= word.matches(')').count();
+        if !TRAILING_PUNCTUATION.contains(&last) || balanced {
+            break;
+        }
+        word = &word[..word.len() - last.len_utf8()];
+    }
+    let href = url_href(word)?;
+    Some((start..start + word.len(), href))
+}
+
+pub struct LinkBlock;
+
+impl Block for LinkBlock {
+    type Node = EditorNode;
+    type Input = web_sys::Node;
+    type State = EditorState;
+
+    fn hook(&self, editor: &mut Editor<Self::Node, Self::State, Self::Input>) {
+        editor.add_input_rule(|state| state.execute(AutoLink));
+        editor.on_paste(|state, text| {
+            let Some(href) = detect_url(text) else {
+                return false;
+            };
+            let mut link = SetLink::new(&href);
+            if state
+                .selection_range()
+                .is_some_and(|range| range.collapsed())
+            {
+                link.text = Some(text.trim().to_owned());
+            }
+            state.execute(link);
+            true
+        });
+        editor.add_toolbar_item(
+            ToolbarItem::new("link", "Link", ToggleLink)
+                .with_icon("Link")
+                .with_group("marks")
+                .active_inside("a"),
+        );
+    }
+
+    fn accepts(&self, node: &web_sys::Node) -> bool {
+        if let Some(element) = node.dyn_ref::<Element>() {
+            element.tag_name() == "A" && element.has_attribute("href")
+        } else {
+            false
+        }
+    }
+
+    fn parse(
+        &self,
+        _editor: &Editor<Self::Node, EditorState, web_sys::Node>,
+        node: &web_sys::Node,
+    ) -> EditorNode {
+        let element: &Element = node.unchecked_ref();
+        let text = element.text_content().unwrap_or_default();
+        let href = element.get_attribute("href").unwrap_or_default();
+        match sanitize_href(&href) {
+            Some(href) => EditorNode::Link(Link {
+                text,
+                href,
+                title: element.get_attribute("title").unwrap_or_default(),
+                target: element.get_attribute("target").unwrap_or_default(),
+            }),
+            None => EditorNode::Text(TextNode { text }),
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, JsonSchema, Default, Debug, PartialEq)]
+pub struct Link {
+    pub text: String,
+    pub href: String,
+    /// The tooltip of the link.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub title: String,
+    /// Where the link opens, eg. `_blank` for a new tab.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub target: String,
+}
+
+impl Link {
+    /// A link with no title or target.
+    pub fn new(text: &str, href: &str) -> Self {
+        Link {
+            text: text.to_owned(),
+            href: href.to_owned(),
+            ..Default::default()
+        }
+    }
+}
+
+impl Node for Link {
+    fn render(&self) -> Dom {
+        let link = Dom::element("a");
+        link.set_attribute("href", &self.href);
+        if !self.title.is_empty() {
+            link.set_attribute("title", &self.title);
+        }
+        if !self.target.is_empty() {
+            link.set_attribute("target", &self.target);
+            // Pages opened in a new tab must not get a handle on the editor's window.
+            link.set_attribute("rel", "noopener noreferrer");
+        }
+        link.append_child(&html! { <>{&self.text}</> });
+        link
+    }
+}
+
+/// Sets the link at the cursor, or links the selected text.
+///
+/// The link is skipped when its href is not allowed by [`LINK_PROTOCOLS`].
+#[derive(Clone, Debug, Default)]
+pub struct SetLink {
+    pub href: String,
+    pub title: String,
+    pub target: String,
+    /// Text inserted as the link when the selection is empty, the href by default.
+    pub text: Option<String>,
+}
+
+impl SetLink {
+    pub fn new(href: &str) -> Self {
+        SetLink {
+            href: href.to_owned(),
+            ..Default::default()
+        }
+    }
+
+    fn apply(&self, link: &Element, href: &str) {
+        link.set_attribute("href", href).unwrap();
+        for (name, value) in [("title", &self.title), ("target", &self.target)] {
+            if value.is_empty() {
+                link.remove_attribute(name).unwrap();
+            } else {
+                link.set_attribute(name, value).unwrap();
+            }
+        }
+        if self.target.is_empty() {
+            link.remove_attribute("rel").unwrap();
+        } else {
+            link.set_attribute("rel", "noopener noreferrer").unwrap();
+        }
+    }
+}
+
+impl Command<EditorState> for SetLink {
+    fn execute(&self, state: &mut EditorState) {
+        let Some(href) = sanitize_href(&self.href) else {
+            return;
+        };
+        if let Some(link) = state.closest_at_cursor("a") {
+            self.apply(&link, &href);
+            return;
+        }
+        let Some(range) = state.selection_range() else {
+            return;
+        };
+        let document = window().unwrap().document().unwrap();
+        let link = document.create_element("a").unwrap();
+        self.apply(&link, &href);
+        if range.collapsed() {
+            let text = self.text.as_deref().unwrap_or(&href);
+            link.set_text_content(Some(text));
+        } else {
+            link.append_child(&range.extract_contents().unwrap())
+                .unwrap();
+            // Links hold plain text, so nested links and formatting are flattened.
+            link.set_text_content(link.text_content().as_deref());
+        }
+        range.insert_node(&link).unwrap();
+        range.set_start_after(&link).unwrap();
+        range.collapse_with_to_start(true);
+        state.select_range(&range);
+    }
+}
+
+/// Removes the link at the cursor, keeping its text.
+pub struct Unlink;
+
+impl Command<EditorState> for Unlink {
+    fn execute(&self, state: &mut EditorState) {
+        let Some(link) = state.closest_at_cursor("a") else {
+            return;
+        };
+        state.keep_caret(|| {
+            let document = window().unwrap().document().unwrap();
+            let fragment = document.create_document_fragment();
+            while let Some(child) = link.first_child() {
+                fragment.append_child(&child).unwrap();
+            }
+            link.replace_with_with_node_1(&fragment).unwrap();
+        });
+    }
+}
+
+/// Removes the link at the cursor, or links the selection to an address asked for with a
+/// prompt.
+pub struct ToggleLink;
+
+impl Command<EditorState> for ToggleLink {
+    fn execute(&self, state: &mut EditorState) {
+        if state.closest_at_cursor("a").is_some() {
+            state.execute(Unlink);
+            return;
+        }
+        let href = window()
+            .unwrap()
+            .prompt_with_message("Link to")
+            .ok()
+            .flatten();
+        if let Some(href) = href.filter(|href| !href.is_empty()) {
+            state.execute(SetLink::new(&href));
+        }
+    }
+}
+
+/// Links a url typed just before the cursor once it is followed by a space.
+///
+/// Registered as an input rule by [`LinkBlock`].
+pub struct AutoLink;
+
+impl Command<EditorState> for AutoLink {
+    fn execute(&self, state: &mut EditorState) {
+        let Some(range) = state.selection_range().filter(|range| range.collapsed()) else {
+            return;
+        };
+        let container = range.start_container().unwrap();
+        if container.node_type() != web_sys::Node::TEXT_NODE
+            || state.closest_at_cursor("a, code, pre").is_some()
+        {
+            return;
+        }
+        let text = container.text_content().unwrap_or_default();
+        // DOM offsets count UTF-16 code units.
+        let offset = range.start_offset().unwrap() as usize;
+        let before: Vec<u16> = text.encode_utf16().take(offset).collect();
+        let before = String::from_utf16_lossy(&before);
+        let Some(typed) = before.strip_suffix(char::is_whitespace) else {
+            return;
+        };
+        let Some((url, href)) = trailing_url(typed) else {
+            return;
+        };
+        let start = typed[..url.start].encode_utf16().count() as u32;
+        let end = typed[..url.end].encode_utf16().count() as u32;
+        state.keep_caret(|| {
+            let document = window().unwrap().document().unwrap();
+            let range = document.create_range().unwrap();
+            range.set_start(&container, start).unwrap();
+            range.set_end(&container, end).unwrap();
+            let link = Link::new(&typed[url], &href).render().inner_element();
+            range.delete_contents().unwrap();
+            range.insert_node(&link).unwrap();
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_link_detection() {
+        assert_eq!(
+            sanitize_href(" https://a.io "),
+            Some("https://a.io".to_owned())
+        );
+        assert_eq!(sanitize_href("/docs#intro"), Some("/docs#intro".to_owned()));
+        assert_eq!(sanitize_href("java\nscript:alert(1)"), None);
+        assert_eq!(sanitize_href("JavaScript:alert(1)"), None);
+        assert_eq!(sanitize_href("data:text/html,hi"), None);
+
+        assert_eq!(
+            detect_url("www.rust-lang.org"),
+            Some("https://www.rust-lang.org".to_owned())
+        );
+        assert_eq!(
+            detect_url("mailto:me@a.io"),
+            Some("mailto:me@a.io".to_owned())
+        );
+        assert_eq!(detect_url("https://localhost"), None);
+        assert_eq!(detect_url("see https://a.io"), None);
+
+        let text = "read (https://en.wikipedia.org/wiki/Rust_(language)).";
+        let (range, href) = trailing_url(text).unwrap();
+        assert_eq!(
+            &text[range],
+            "https://en.wikipedia.org/wiki/Rust_(language)"
+        );
+        assert_eq!(href, "https://en.wikipedia.org/wiki/Rust_(language)");
+        assert_eq!(trailing_url("not a link."), None);
+    }
+}
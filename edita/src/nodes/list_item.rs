@@ -1,10 +1,20 @@
-use edita_core::{Block, Editor};
+use edita_core::{process_nodes, Block, Command, Editor};
 use hirola::prelude::*;
-use serde::Serialize;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 use wasm_bindgen::JsCast;
-use web_sys::{Element, Node};
+use web_sys::{window, Element, Node};
 
-use crate::{nodes::EditorNode, EditorState};
+use crate::{
+    nodes::{
+        paragraph::{unwrap_inline, wrap_inline},
+        EditorNode, Paragraph, TextNode,
+    },
+    EditorState,
+};
+
+/// List items, leaving out task items which have their own keys.
+const ITEM: &str = "li:not([data-type=taskItem])";
 
 pub struct ListItemBlock;
 
@@ -12,6 +22,24 @@ impl Block for ListItemBlock {
     type Node = EditorNode;
     type State = EditorState;
     type Input = web_sys::Node;
+
+    fn hook(&self, editor: &mut Editor<Self::Node, Self::State, Self::Input>) {
+        editor.bind_key("Tab", |state| {
+            let handled = item_at_cursor(state).is_some();
+            if handled {
+                state.execute(Indent);
+            }
+            handled
+        });
+        editor.bind_key("Shift-Tab", |state| {
+            let handled = item_at_cursor(state).is_some();
+            if handled {
+                state.execute(Outdent);
+            }
+            handled
+        });
+    }
+
     fn accepts(&self, node: &Node) -> bool {
         if let Some(element) = node.dyn_ref::<Element>() {
             element.tag_name() == "LI"
@@ -22,22 +50,249 @@ impl Block for ListItemBlock {
 
     fn parse(
         &self,
-        _editor: &Editor<Self::Node, Self::State, web_sys::Node>,
+        editor: &Editor<Self::Node, Self::State, web_sys::Node>,
         node: &web_sys::Node,
     ) -> EditorNode {
-        EditorNode::ListItem(ListItem {
-            text: node.text_content().unwrap_or_default(),
-        })
+        EditorNode::ListItem(read_item(editor, node))
     }
 }
 
-#[derive(Clone, Serialize, Debug)]
+fn read_item(editor: &Editor<EditorNode, EditorState, web_sys::Node>, item: &Node) -> ListItem {
+    let children = item.child_nodes();
+    let children = (0..children.length()).filter_map(|i| children.get(i));
+    ListItem {
+        content: wrap_inline(process_nodes(editor, children)),
+    }
+}
+
+/// Reads the `<li>` children of a list.
+pub(super) fn read_list_items(
+    editor: &Editor<EditorNode, EditorState, web_sys::Node>,
+    list: &Node,
+) -> Vec<ListItem> {
+    let children = list.child_nodes();
+    (0..children.length())
+        .filter_map(|i| children.get(i))
+        .filter(|child| child.node_name() == "LI")
+        .map(|item| read_item(editor, &item))
+        .collect()
+}
+
+#[derive(Clone, Serialize, Deserialize, JsonSchema, Default, Debug)]
 pub struct ListItem {
-    text: String,
+    /// The blocks of the item, usually a paragraph followed by nested lists.
+    pub content: Vec<EditorNode>,
+}
+
+impl ListItem {
+    /// An item holding a single paragraph of plain text.
+    pub fn from_text(text: &str) -> Self {
+        let elements = match text {
+            "" => vec![],
+            text => vec![EditorNode::Text(TextNode {
+                text: text.to_owned(),
+            })],
+        };
+        ListItem {
+            content: vec![EditorNode::Paragraph(Paragraph { elements })],
+        }
+    }
+
+    /// The inline nodes of the item's own blocks, separated by hard breaks, for formats
+    /// whose list items only hold a line of text.
+    pub fn inline(&self) -> Vec<EditorNode> {
+        unwrap_inline(self.content.iter().filter(|block| !block.is_list()))
+    }
+
+    /// The plain text of the item, leaving out its nested lists.
+    pub fn own_text(&self) -> String {
+        self.inline().iter().map(EditorNode::text).collect()
+    }
+
+    /// The lists nested in the item.
+    pub fn sublists(&self) -> impl Iterator<Item = &EditorNode> {
+        self.content.iter().filter(|block| block.is_list())
+    }
 }
 
 impl crate::nodes::Node for ListItem {
     fn render(&self) -> hirola::dom::Dom {
-        html! { <li>{&self.text}</li> }
+        html! {
+            <li>
+                {for node in &self.content {
+                    node.render()
+                }}
+            </li>
+        }
+    }
+}
+
+/// The list item around the cursor, unless the cursor is in a table cell or code block
+/// nested inside it.
+fn item_at_cursor(state: &EditorState) -> Option<Element> {
+    state
+        .closest_at_cursor("li, td, th, pre")
+        .filter(|element| element.matches(ITEM).unwrap_or_default())
+}
+
+pub(super) fn create_element(tag: &str) -> Element {
+    window()
+        .unwrap()
+        .document()
+        .unwrap()
+        .create_element(tag)
+        .unwrap()
+}
+
+/// The elements after `element` under the same parent.
+fn following_siblings(element: &Element) -> Vec<Element> {
+    std::iter::successors(
+        element.next_element_sibling(),
+        Element::next_element_sibling,
+    )
+    .collect()
+}
+
+/// Wraps the contents of an item in a paragraph if it holds loose text, so that it stays
+/// a block once lifted out of its list.
+fn wrap_loose_text(item: &Element) {
+    if item.first_element_child().is_some() || !item.has_child_nodes() {
+        return;
+    }
+    let paragraph = create_element("p");
+    while let Some(child) = item.first_child() {
+        paragraph.append_child(&child).unwrap();
+    }
+    item.append_child(&paragraph).unwrap();
+}
+
+/// Moves the list item at the cursor into a list nested in the item above it, of the same
+/// kind as its own list.
+///
+/// Bound to Tab inside list items by [`ListItemBlock`].
+pub struct Indent;
+
+impl Command<EditorState> for Indent {
+    fn execute(&self, state: &mut EditorState) {
+        let Some(item) = item_at_cursor(state) else {
+            return;
+        };
+        let (Some(previous), Some(list)) = (item.previous_element_sibling(), item.parent_element())
+        else {
+            return;
+        };
+        state.keep_caret(|| {
+            let sublist = match previous.last_element_child() {
+                Some(last) if last.tag_name() == list.tag_name() => last,
+                _ => {
+                    let sublist = create_element(&list.tag_name());
+                    previous.append_child(&sublist).unwrap();
+                    sublist
+                }
+            };
+            sublist.append_child(&item).unwrap();
+        });
+    }
+}
+
+/// Moves the list item at the cursor out of its list, to follow the item the list is
+/// nested in, or out of the list altogether at the top level.
+///
+/// The items below it move along, nested under it, or into a list of their own after it
+/// at the top level. Bound to Shift-Tab inside list items by [`ListItemBlock`].
+pub struct Outdent;
+
+impl Command<EditorState> for Outdent {
+    fn execute(&self, state: &mut EditorState) {
+        let Some(item) = item_at_cursor(state) else {
+            return;
+        };
+        let Some(list) = item.parent_element() else {
+            return;
+        };
+        state.keep_caret(|| {
+            let following = following_siblings(&item);
+            // A shallow copy keeps the attributes of the list, such as its start number.
+            let rest = (!following.is_empty()).then(|| {
+                let rest: Element = list.clone_node().unwrap().unchecked_into();
+                for sibling in &following {
+                    rest.append_child(sibling).unwrap();
+                }
+                rest
+            });
+            match list
+                .parent_element()
+                .filter(|parent| parent.matches(ITEM).unwrap_or_default())
+            {
+                Some(parent) => {
+                    if let Some(rest) = rest {
+                        item.append_child(&rest).unwrap();
+                    }
+                    parent.after_with_node_1(&item).unwrap();
+                }
+                None => {
+                    if let Some(rest) = rest {
+                        list.after_with_node_1(&rest).unwrap();
+                    }
+                    wrap_loose_text(&item);
+                    while let Some(child) = item.last_child() {
+                        list.after_with_node_1(&child).unwrap();
+                    }
+                    item.remove();
+                }
+            }
+            if list.child_element_count() == 0 {
+                list.remove();
+            }
+        });
     }
 }
+
+/// Turns the list around the cursor into a list of `tag`, lifting its items out if it
+/// already is one, or wraps the selected blocks in a new list of `tag`.
+pub(super) fn toggle_list(state: &EditorState, tag: &str) {
+    if let Some(list) = item_at_cursor(state).and_then(|item| item.parent_element()) {
+        if list.tag_name().eq_ignore_ascii_case(tag) {
+            state.keep_caret(|| {
+                let items: Vec<Element> = std::iter::successors(
+                    list.first_element_child(),
+                    Element::next_element_sibling,
+                )
+                .collect();
+                for item in items {
+                    wrap_loose_text(&item);
+                    while let Some(child) = item.first_child() {
+                        list.before_with_node_1(&child).unwrap();
+                    }
+                }
+                list.remove();
+            });
+        } else {
+            state.keep_caret(|| {
+                let replacement = create_element(tag);
+                while let Some(child) = list.first_child() {
+                    replacement.append_child(&child).unwrap();
+                }
+                list.replace_with_with_node_1(&replacement).unwrap();
+            });
+        }
+        return;
+    }
+    let blocks = state.selected_blocks();
+    let Some(first) = blocks.first() else {
+        return;
+    };
+    state.keep_caret(|| {
+        let list = create_element(tag);
+        first
+            .parent_node()
+            .unwrap()
+            .insert_before(&list, Some(first))
+            .unwrap();
+        for block in &blocks {
+            let item = create_element("li");
+            item.append_child(block).unwrap();
+            list.append_child(&item).unwrap();
+        }
+    });
+}
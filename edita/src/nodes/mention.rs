@@ -0,0 +1,87 @@
+use edita_core::{Block, Editor};
+use hirola::{dom::Dom, prelude::*};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::JsCast;
+use web_sys::Element;
+
+use crate::{
+    nodes::{EditorNode, Node},
+    state::EditorState,
+};
+
+/// A mention of a user, shown as `@label` and kept as a single atom.
+#[derive(Clone, Serialize, Deserialize, JsonSchema, Debug, PartialEq, Eq)]
+pub struct Mention {
+    /// The id of the mentioned user in the host application.
+    pub id: String,
+    /// The name shown after the `@`.
+    pub label: String,
+}
+
+impl Mention {
+    pub fn new(id: &str, label: &str) -> Self {
+        Mention {
+            id: id.to_owned(),
+            label: label.to_owned(),
+        }
+    }
+
+    /// The text the mention is shown as, eg. `@ada`.
+    pub fn text(&self) -> String {
+        format!("@{}", self.label)
+    }
+}
+
+impl Node for Mention {
+    fn render(&self) -> Dom {
+        use hirola::prelude::DefaultAttrStr;
+        use hirola::prelude::DefaultAttributeEffect;
+        html! {
+            <span
+                data-type="mention"
+                data-node-atom="true"
+                contenteditable="false"
+                data-id=&self.id
+                data-label=&self.label
+            >
+                {self.text()}
+            </span>
+        }
+    }
+}
+
+pub struct MentionBlock;
+
+impl Block for MentionBlock {
+    type Node = EditorNode;
+    type Input = web_sys::Node;
+    type State = EditorState;
+
+    fn accepts(&self, node: &web_sys::Node) -> bool {
+        if let Some(element) = node.dyn_ref::<Element>() {
+            element.tag_name() == "SPAN"
+                && element
+                    .get_attribute("data-type")
+                    .is_some_and(|v| &v == "mention")
+        } else {
+            false
+        }
+    }
+
+    fn parse(
+        &self,
+        _editor: &Editor<Self::Node, EditorState, web_sys::Node>,
+        node: &web_sys::Node,
+    ) -> EditorNode {
+        let element: &Element = node.dyn_ref().unwrap();
+        let label = element.get_attribute("data-label").unwrap_or_else(|| {
+            let text = element.text_content().unwrap_or_default();
+            text.trim_start_matches('@').to_owned()
+        });
+        EditorNode::Mention(Mention {
+            id: element.get_attribute("data-id").unwrap_or_default(),
+            label,
+        })
+    }
+}
@@ -1,47 +1,98 @@
 mod block_quote;
 mod bullet_list;
+mod code_block;
+mod details;
+mod emoji;
+mod hard_break;
 mod heading;
+mod horizontal_rule;
 mod image;
+mod link;
 mod list_item;
+mod mention;
 mod ordered_list;
 mod paragraph;
+mod table;
 mod task_item;
 mod task_list;
 mod text;
+use std::collections::BTreeSet;
+
 use hirola::{dom::Dom, prelude::*};
-use serde::Serialize;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 
-use crate::{editor::HtmlNode, nodes::block_quote::BlockQuote};
+use crate::editor::HtmlNode;
 
 pub use self::{
-    bullet_list::BulletList,
-    heading::Header,
-    image::Image,
-    list_item::ListItem,
-    ordered_list::OrderedList,
+    block_quote::{BlockQuote, ToggleBlockQuote},
+    bullet_list::{BulletList, ToggleBulletList},
+    code_block::{highlight, CodeBlock, IndentCode, NewlineInCode, SetCodeLanguage, CODE_INDENT},
+    details::{Details, EnterDetailsContent, WrapInDetails},
+    emoji::{
+        emoji_picker, search_emoji, Emoji, EmojiSuggestions, ExpandEmojiShortcode, InsertEmoji,
+        EMOJI,
+    },
+    hard_break::{HardBreak, InsertHardBreak},
+    heading::{
+        slugify, table_of_contents, Header, HeadingAnchors, NumberHeadings, SetHeadingLevel,
+        TocEntry, UpdateHeadingAnchors, NUMBERED_HEADINGS, NUMBERED_HEADINGS_CSS,
+    },
+    horizontal_rule::{ExpandRuleShortcut, HorizontalRule, InsertHorizontalRule},
+    image::{Image, ImageAlign},
+    link::{
+        detect_url, sanitize_href, trailing_url, AutoLink, Link, SetLink, ToggleLink, Unlink,
+        LINK_PROTOCOLS,
+    },
+    list_item::{Indent, ListItem, Outdent},
+    mention::Mention,
+    ordered_list::{NumberingStyle, OrderedList, ToggleOrderedList},
     paragraph::Paragraph,
-    task_item::TaskItem,
+    table::{
+        AddColumnAfter, AddColumnBefore, AddRowAfter, AddRowBefore, CellIndex, DeleteColumn,
+        DeleteRow, GoToNextCell, GoToPreviousCell, InsertTable, MergeCells, Slot, SplitCell, Table,
+        TableCell, TableRow,
+    },
+    task_item::{IndentTask, OutdentTask, TaskItem, ToggleTask},
     task_list::TaskList,
-    text::{Bold, InlineCode, Italic, TextNode},
+    text::{
+        sanitize_color, Bold, Highlight, InlineCode, Italic, Strike, Subscript, Superscript,
+        TextColor, TextNode, Underline,
+    },
 };
 
 pub use self::{
+    block_quote::BlockQuoteBlock,
     bullet_list::BulletListBlock,
+    code_block::CodeBlockBlock,
+    details::DetailsBlock,
+    emoji::EmojiBlock,
+    hard_break::HardBreakBlock,
     heading::HeaderBlock,
+    horizontal_rule::HorizontalRuleBlock,
     image::ImageBlock,
+    link::LinkBlock,
     list_item::ListItemBlock,
+    mention::MentionBlock,
     ordered_list::OrderedListBlock,
     paragraph::ParagraphBlock,
+    table::TableBlock,
     task_item::TaskItemBlock,
     task_list::TaskListBlock,
-    text::{BoldBlock, InlineCodeBlock, ItalicBlock, TextNodeBlock},
+    text::{
+        BoldBlock, HighlightBlock, InlineCodeBlock, ItalicBlock, StrikeBlock, SubscriptBlock,
+        SuperscriptBlock, TextColorBlock, TextNodeBlock, UnderlineBlock,
+    },
 };
 
+#[cfg(feature = "highlight")]
+pub use self::code_block::highlight_css;
+
 pub trait Node {
     fn render(&self) -> Dom;
 }
 
-#[derive(Serialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug)]
 pub enum EditorNode {
     Html(HtmlNode),
     Paragraph(Paragraph),
@@ -50,6 +101,13 @@ pub enum EditorNode {
     Heading(Header),
     Italic(Italic),
     InlineCode(InlineCode),
+    Underline(Underline),
+    Strike(Strike),
+    Subscript(Subscript),
+    Superscript(Superscript),
+    Highlight(Highlight),
+    TextColor(TextColor),
+    Link(Link),
     Image(Image),
     BlockQuote(BlockQuote),
     ListItem(ListItem),
@@ -57,6 +115,195 @@ pub enum EditorNode {
     OrderedList(OrderedList),
     TaskItem(TaskItem),
     TaskList(TaskList),
+    Emoji(Emoji),
+    Mention(Mention),
+    CodeBlock(CodeBlock),
+    HorizontalRule(HorizontalRule),
+    HardBreak(HardBreak),
+    Table(Table),
+    Details(Details),
+}
+
+impl EditorNode {
+    /// The name of this node as it appears in serialized documents.
+    pub fn node_type(&self) -> &'static str {
+        match self {
+            EditorNode::Html(_) => "Html",
+            EditorNode::Paragraph(_) => "Paragraph",
+            EditorNode::Text(_) => "Text",
+            EditorNode::Bold(_) => "Bold",
+            EditorNode::Heading(_) => "Heading",
+            EditorNode::Italic(_) => "Italic",
+            EditorNode::InlineCode(_) => "InlineCode",
+            EditorNode::Underline(_) => "Underline",
+            EditorNode::Strike(_) => "Strike",
+            EditorNode::Subscript(_) => "Subscript",
+            EditorNode::Superscript(_) => "Superscript",
+            EditorNode::Highlight(_) => "Highlight",
+            EditorNode::TextColor(_) => "TextColor",
+            EditorNode::Link(_) => "Link",
+            EditorNode::Image(_) => "Image",
+            EditorNode::BlockQuote(_) => "BlockQuote",
+            EditorNode::ListItem(_) => "ListItem",
+            EditorNode::BulletList(_) => "BulletList",
+            EditorNode::OrderedList(_) => "OrderedList",
+            EditorNode::TaskItem(_) => "TaskItem",
+            EditorNode::TaskList(_) => "TaskList",
+            EditorNode::Emoji(_) => "Emoji",
+            EditorNode::Mention(_) => "Mention",
+            EditorNode::CodeBlock(_) => "CodeBlock",
+            EditorNode::HorizontalRule(_) => "HorizontalRule",
+            EditorNode::HardBreak(_) => "HardBreak",
+            EditorNode::Table(_) => "Table",
+            EditorNode::Details(_) => "Details",
+        }
+    }
+
+    /// Whether this node goes inside a block, like text, rather than being one.
+    pub fn is_inline(&self) -> bool {
+        matches!(
+            self,
+            EditorNode::Text(_)
+                | EditorNode::Bold(_)
+                | EditorNode::Italic(_)
+                | EditorNode::InlineCode(_)
+                | EditorNode::Underline(_)
+                | EditorNode::Strike(_)
+                | EditorNode::Subscript(_)
+                | EditorNode::Superscript(_)
+                | EditorNode::Highlight(_)
+                | EditorNode::TextColor(_)
+                | EditorNode::Link(_)
+                | EditorNode::Emoji(_)
+                | EditorNode::Mention(_)
+                | EditorNode::HardBreak(_)
+        )
+    }
+
+    /// Whether this node is a bullet or ordered list.
+    pub fn is_list(&self) -> bool {
+        matches!(self, EditorNode::BulletList(_) | EditorNode::OrderedList(_))
+    }
+
+    /// The plain text of this node and the nodes nested inside it.
+    pub fn text(&self) -> String {
+        match self {
+            EditorNode::Html(_) | EditorNode::Image(_) | EditorNode::HorizontalRule(_) => {
+                String::new()
+            }
+            EditorNode::HardBreak(_) => "\n".to_owned(),
+            EditorNode::Paragraph(paragraph) => {
+                paragraph.elements.iter().map(EditorNode::text).collect()
+            }
+            EditorNode::Text(TextNode { text })
+            | EditorNode::Bold(Bold { text })
+            | EditorNode::Italic(Italic { text })
+            | EditorNode::InlineCode(InlineCode { text })
+            | EditorNode::Underline(Underline { text })
+            | EditorNode::Strike(Strike { text })
+            | EditorNode::Subscript(Subscript { text })
+            | EditorNode::Superscript(Superscript { text })
+            | EditorNode::Highlight(Highlight { text, .. })
+            | EditorNode::TextColor(TextColor { text, .. })
+            | EditorNode::Link(Link { text, .. })
+            | EditorNode::Heading(Header { text, .. }) => text.clone(),
+            EditorNode::ListItem(ListItem { content })
+            | EditorNode::BlockQuote(BlockQuote { content }) => content
+                .iter()
+                .map(EditorNode::text)
+                .collect::<Vec<_>>()
+                .join("\n"),
+            EditorNode::TaskItem(item) => item.plain_text(),
+            EditorNode::Emoji(emoji) => emoji.emoji.clone(),
+            EditorNode::Mention(mention) => mention.text(),
+            EditorNode::CodeBlock(block) => block.code.clone(),
+            EditorNode::BulletList(BulletList { list })
+            | EditorNode::OrderedList(OrderedList { list, .. }) => list
+                .iter()
+                .flat_map(|item| &item.content)
+                .map(EditorNode::text)
+                .collect::<Vec<_>>()
+                .join("\n"),
+            EditorNode::TaskList(TaskList { list }) => list
+                .iter()
+                .map(TaskItem::plain_text)
+                .collect::<Vec<_>>()
+                .join("\n"),
+            EditorNode::Table(table) => table
+                .rows
+                .iter()
+                .map(|row| {
+                    row.cells
+                        .iter()
+                        .map(|cell| cell.content.iter().map(EditorNode::text).collect())
+                        .collect::<Vec<String>>()
+                        .join("\t")
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+            EditorNode::Details(details) => {
+                let summary: String = details.summary.iter().map(EditorNode::text).collect();
+                std::iter::once(summary)
+                    .chain(details.content.iter().map(EditorNode::text))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            }
+        }
+    }
+
+    /// Collects the names of this node and every node nested inside it.
+    pub fn collect_node_types(&self, types: &mut BTreeSet<String>) {
+        types.insert(self.node_type().to_owned());
+        match self {
+            EditorNode::Paragraph(paragraph) => {
+                for element in &paragraph.elements {
+                    element.collect_node_types(types);
+                }
+            }
+            EditorNode::BulletList(BulletList { list })
+            | EditorNode::OrderedList(OrderedList { list, .. })
+                if !list.is_empty() =>
+            {
+                types.insert("ListItem".to_owned());
+                for node in list.iter().flat_map(|item| &item.content) {
+                    node.collect_node_types(types);
+                }
+            }
+            EditorNode::ListItem(ListItem { content })
+            | EditorNode::BlockQuote(BlockQuote { content }) => {
+                for node in content {
+                    node.collect_node_types(types);
+                }
+            }
+            EditorNode::TaskList(TaskList { list }) if !list.is_empty() => {
+                types.insert("TaskItem".to_owned());
+            }
+            EditorNode::Table(table) => {
+                for row in &table.rows {
+                    types.insert("TableRow".to_owned());
+                    for cell in &row.cells {
+                        let name = if cell.header {
+                            "TableHeader"
+                        } else {
+                            "TableCell"
+                        };
+                        types.insert(name.to_owned());
+                        for node in &cell.content {
+                            node.collect_node_types(types);
+                        }
+                    }
+                }
+            }
+            EditorNode::Details(details) => {
+                types.insert("DetailsSummary".to_owned());
+                types.insert("DetailsContent".to_owned());
+                for node in details.summary.iter().chain(&details.content) {
+                    node.collect_node_types(types);
+                }
+            }
+            _ => {}
+        }
+    }
 }
 
 impl Node for EditorNode {
@@ -69,6 +316,13 @@ impl Node for EditorNode {
             EditorNode::Heading(heading) => heading.render(),
             EditorNode::Italic(italic) => italic.render(),
             EditorNode::InlineCode(inline_code) => inline_code.render(),
+            EditorNode::Underline(underline) => underline.render(),
+            EditorNode::Strike(strike) => strike.render(),
+            EditorNode::Subscript(subscript) => subscript.render(),
+            EditorNode::Superscript(superscript) => superscript.render(),
+            EditorNode::Highlight(highlight) => highlight.render(),
+            EditorNode::TextColor(colored) => colored.render(),
+            EditorNode::Link(link) => link.render(),
             EditorNode::Image(image) => image.render(),
             EditorNode::BlockQuote(quote) => quote.render(),
             EditorNode::ListItem(item) => item.render(),
@@ -76,6 +330,13 @@ impl Node for EditorNode {
             EditorNode::OrderedList(list) => list.render(),
             EditorNode::TaskItem(item) => item.render(),
             EditorNode::TaskList(list) => list.render(),
+            EditorNode::Emoji(emoji) => emoji.render(),
+            EditorNode::Mention(mention) => mention.render(),
+            EditorNode::CodeBlock(block) => block.render(),
+            EditorNode::HorizontalRule(rule) => rule.render(),
+            EditorNode::HardBreak(line_break) => line_break.render(),
+            EditorNode::Table(table) => table.render(),
+            EditorNode::Details(details) => details.render(),
         }
     }
 }
@@ -1,13 +1,13 @@
-use edita_core::{Block, Editor};
+use edita_core::{Block, Command, Editor};
 use hirola::prelude::*;
-use js_sys::Object;
-use serde::Serialize;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 use wasm_bindgen::JsCast;
 use web_sys::{Element, Node};
 
-use crate::{nodes::EditorNode, EditorState};
+use crate::{nodes::EditorNode, EditorState, Suggestion, ToolbarItem};
 
-use super::list_item::{ListItem, ListItemBlock};
+use super::list_item::{read_list_items, toggle_list, ListItem};
 
 pub struct OrderedListBlock;
 
@@ -15,6 +15,19 @@ impl Block for OrderedListBlock {
     type Node = EditorNode;
     type State = EditorState;
     type Input = web_sys::Node;
+    fn hook(&self, editor: &mut Editor<Self::Node, Self::State, Self::Input>) {
+        editor.add_slash_item(Suggestion::command("Numbered list", ToggleOrderedList));
+        editor.add_toolbar_item(
+            ToolbarItem::new("ordered-list", "Numbered list", ToggleOrderedList)
+                .with_icon("OL")
+                .with_group("blocks")
+                .with_active(|state| {
+                    state
+                        .closest_at_cursor("ul, ol")
+                        .is_some_and(|list| list.tag_name() == "OL")
+                }),
+        );
+    }
     fn accepts(&self, node: &Node) -> bool {
         if let Some(element) = node.dyn_ref::<Element>() {
             element.tag_name() == "OL"
@@ -28,34 +41,250 @@ impl Block for OrderedListBlock {
         editor: &Editor<Self::Node, Self::State, web_sys::Node>,
         node: &web_sys::Node,
     ) -> EditorNode {
+        let element = node.unchecked_ref::<Element>();
+        let style = element
+            .get_attribute("type")
+            .and_then(|kind| NumberingStyle::from_type(&kind))
+            .or_else(|| {
+                let css = element.get_attribute("style")?;
+                let (_, value) = css.split_once("list-style-type:")?;
+                NumberingStyle::from_css(value.split(';').next()?.trim())
+            })
+            .unwrap_or_default();
         EditorNode::OrderedList(OrderedList {
-            list: Object::entries(&node.child_nodes())
-                .into_iter()
-                .map(|item| ListItemBlock::parse(&ListItemBlock, editor, item.dyn_ref().unwrap()))
-                .map(|node| match node {
-                    EditorNode::ListItem(item) => Some(item),
-                    _ => None,
-                })
-                .filter(|item| item.is_some())
-                .map(|c| c.unwrap())
-                .collect(),
+            list: read_list_items(editor, node),
+            start: element
+                .get_attribute("start")
+                .and_then(|start| start.trim().parse().ok()),
+            style,
+            reversed: element.has_attribute("reversed"),
         })
     }
 }
 
-#[derive(Clone, Serialize, Debug)]
+/// How the items of an ordered list are numbered, after the `type` attribute of `<ol>`.
+#[derive(Clone, Copy, Serialize, Deserialize, JsonSchema, Default, Debug, PartialEq, Eq)]
+pub enum NumberingStyle {
+    /// 1, 2, 3
+    #[default]
+    Decimal,
+    /// a, b, c
+    LowerAlpha,
+    /// A, B, C
+    UpperAlpha,
+    /// i, ii, iii
+    LowerRoman,
+    /// I, II, III
+    UpperRoman,
+}
+
+impl NumberingStyle {
+    /// The style of an `<ol type>` attribute.
+    pub fn from_type(kind: &str) -> Option<Self> {
+        match kind {
+            "1" => Some(NumberingStyle::Decimal),
+            "a" => Some(NumberingStyle::LowerAlpha),
+            "A" => Some(NumberingStyle::UpperAlpha),
+            "i" => Some(NumberingStyle::LowerRoman),
+            "I" => Some(NumberingStyle::UpperRoman),
+            _ => None,
+        }
+    }
+
+    /// The style of a css `list-style-type`.
+    pub fn from_css(value: &str) -> Option<Self> {
+        match value {
+            "decimal" => Some(NumberingStyle::Decimal),
+            "lower-alpha" | "lower-latin" => Some(NumberingStyle::LowerAlpha),
+            "upper-alpha" | "upper-latin" => Some(NumberingStyle::UpperAlpha),
+            "lower-roman" => Some(NumberingStyle::LowerRoman),
+            "upper-roman" => Some(NumberingStyle::UpperRoman),
+            _ => None,
+        }
+    }
+
+    /// The value of the `<ol type>` attribute for the style.
+    pub fn type_attribute(self) -> &'static str {
+        match self {
+            NumberingStyle::Decimal => "1",
+            NumberingStyle::LowerAlpha => "a",
+            NumberingStyle::UpperAlpha => "A",
+            NumberingStyle::LowerRoman => "i",
+            NumberingStyle::UpperRoman => "I",
+        }
+    }
+
+    /// The css `list-style-type` of the style.
+    pub fn css(self) -> &'static str {
+        match self {
+            NumberingStyle::Decimal => "decimal",
+            NumberingStyle::LowerAlpha => "lower-alpha",
+            NumberingStyle::UpperAlpha => "upper-alpha",
+            NumberingStyle::LowerRoman => "lower-roman",
+            NumberingStyle::UpperRoman => "upper-roman",
+        }
+    }
+
+    pub fn is_decimal(&self) -> bool {
+        *self == NumberingStyle::Decimal
+    }
+
+    /// Writes `number` in the style. Numbers letters and roman numerals cannot express
+    /// are written in decimal, as browsers do.
+    pub fn format(self, number: i64) -> String {
+        match self {
+            NumberingStyle::LowerAlpha | NumberingStyle::UpperAlpha if number > 0 => {
+                let mut letters = vec![];
+                let mut rest = number;
+                while rest > 0 {
+                    rest -= 1;
+                    letters.push((b'a' + (rest % 26) as u8) as char);
+                    rest /= 26;
+                }
+                let letters: String = letters.into_iter().rev().collect();
+                if self == NumberingStyle::UpperAlpha {
+                    letters.to_uppercase()
+                } else {
+                    letters
+                }
+            }
+            NumberingStyle::LowerRoman | NumberingStyle::UpperRoman
+                if (1..4000).contains(&number) =>
+            {
+                const NUMERALS: [(i64, &str); 13] = [
+                    (1000, "M"),
+                    (900, "CM"),
+                    (500, "D"),
+                    (400, "CD"),
+                    (100, "C"),
+                    (90, "XC"),
+                    (50, "L"),
+                    (40, "XL"),
+                    (10, "X"),
+                    (9, "IX"),
+                    (5, "V"),
+                    (4, "IV"),
+                    (1, "I"),
+                ];
+                let mut roman = String::new();
+                let mut rest = number;
+                for (value, numeral) in NUMERALS {
+                    while rest >= value {
+                        roman.push_str(numeral);
+                        rest -= value;
+                    }
+                }
+                if self == NumberingStyle::LowerRoman {
+                    roman.to_lowercase()
+                } else {
+                    roman
+                }
+            }
+            _ => number.to_string(),
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, JsonSchema, Default, Debug)]
 pub struct OrderedList {
-    list: Vec<ListItem>,
+    pub list: Vec<ListItem>,
+    /// The number of the first item, 1 by default, or the number of items when reversed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start: Option<i64>,
+    #[serde(default, skip_serializing_if = "NumberingStyle::is_decimal")]
+    pub style: NumberingStyle,
+    /// Whether the items count down.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub reversed: bool,
+}
+
+impl OrderedList {
+    /// The number of the first item.
+    pub fn first_number(&self) -> i64 {
+        match (self.start, self.reversed) {
+            (Some(start), _) => start,
+            (None, false) => 1,
+            (None, true) => self.list.len() as i64,
+        }
+    }
+
+    /// The number of the item at `index`.
+    pub fn number(&self, index: usize) -> i64 {
+        if self.reversed {
+            self.first_number().saturating_sub(index as i64)
+        } else {
+            self.first_number().saturating_add(index as i64)
+        }
+    }
+
+    /// The marker shown before the item at `index`, eg. `"5."` or `"iv."`.
+    pub fn marker(&self, index: usize) -> String {
+        format!("{}.", self.style.format(self.number(index)))
+    }
+
+    /// Whether the list is numbered 1, 2, 3, as formats without numbering options show it.
+    pub fn is_plain(&self) -> bool {
+        self.style.is_decimal() && !self.reversed && self.first_number() == 1
+    }
 }
 
 impl crate::nodes::Node for OrderedList {
     fn render(&self) -> hirola::dom::Dom {
-        html! {
-        <ol>
-            {for item in &self.list {
-                html! { <>{item.render()}</> }
-            }}
-        </ol>
+        let list = html! {
+            <ol>
+                {for item in &self.list {
+                    html! { <>{item.render()}</> }
+                }}
+            </ol>
+        };
+        let element = list.inner_element();
+        let element = element.unchecked_ref::<Element>();
+        if let Some(start) = self.start {
+            element.set_attribute("start", &start.to_string()).unwrap();
+        }
+        if !self.style.is_decimal() {
+            element
+                .set_attribute("type", self.style.type_attribute())
+                .unwrap();
+        }
+        if self.reversed {
+            element.set_attribute("reversed", "").unwrap();
+        }
+        list
     }
+}
+
+/// Turns the list at the cursor into an ordered list, or back into paragraphs if it is one,
+/// and wraps the selected blocks in an ordered list outside of lists.
+pub struct ToggleOrderedList;
+
+impl Command<EditorState> for ToggleOrderedList {
+    fn execute(&self, state: &mut EditorState) {
+        toggle_list(state, "ol");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_numbers_saturate_at_the_ends_of_i64() {
+        let list = OrderedList {
+            list: vec![],
+            start: Some(i64::MAX),
+            style: NumberingStyle::default(),
+            reversed: false,
+        };
+        assert_eq!(list.number(0), i64::MAX);
+        assert_eq!(list.number(3), i64::MAX);
+
+        let list = OrderedList {
+            start: Some(i64::MIN),
+            reversed: true,
+            ..list
+        };
+        assert_eq!(list.number(3), i64::MIN);
+        assert_eq!(list.marker(3), format!("{}.", i64::MIN));
     }
 }
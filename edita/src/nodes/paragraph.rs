@@ -1,17 +1,19 @@
 use edita_core::{process_nodes, Block, Editor, Command};
 use hirola::{dom::Dom, prelude::*};
-use serde::Serialize;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 use wasm_bindgen::JsCast;
 use web_sys::Element;
 
 use crate::{
-    nodes::{EditorNode, Node},
+    nodes::{EditorNode, HardBreak, Node, TextNode},
     state::EditorState,
+    suggestion::Suggestion,
 };
 
-#[derive(Clone, Serialize, Default, Debug)]
+#[derive(Clone, Serialize, Deserialize, JsonSchema, Default, Debug)]
 pub struct Paragraph {
-    elements: Vec<EditorNode>,
+    pub elements: Vec<EditorNode>,
 }
 
 impl Node for Paragraph {
@@ -32,6 +34,12 @@ impl Block for ParagraphBlock {
     type Node = EditorNode;
     type Input = web_sys::Node;
     type State = EditorState;
+    fn hook(&self, editor: &mut Editor<Self::Node, Self::State, Self::Input>) {
+        editor.add_slash_item(Suggestion::new(
+            "Text",
+            EditorNode::Paragraph(Paragraph::default()),
+        ));
+    }
     fn accepts(&self, node: &web_sys::Node) -> bool {
         if let Some(element) = node.dyn_ref::<Element>() {
             element.tag_name() == "P"
@@ -63,6 +71,51 @@ impl Block for ParagraphBlock {
 
 impl Command<EditorState> for ParagraphBlock {
     fn execute(&self, state: &mut EditorState) {
-        state.add_node(Paragraph::default())
+        state.insert_block(Paragraph::default())
+    }
+}
+
+/// Wraps the runs of inline nodes among blocks, such as the loose text of `<li>text</li>`,
+/// in paragraphs. Runs of blank text, usually the indentation of the markup, are dropped.
+pub(super) fn wrap_inline(nodes: Vec<EditorNode>) -> Vec<EditorNode> {
+    let mut blocks = vec![];
+    let mut inline: Vec<EditorNode> = vec![];
+    let flush = |inline: &mut Vec<EditorNode>, blocks: &mut Vec<EditorNode>| {
+        let blank = inline
+            .iter()
+            .all(|node| matches!(node, EditorNode::Text(text) if text.text.trim().is_empty()));
+        let elements = std::mem::take(inline);
+        if !blank {
+            blocks.push(EditorNode::Paragraph(Paragraph { elements }));
+        }
+    };
+    for node in nodes {
+        if node.is_inline() {
+            inline.push(node);
+        } else {
+            flush(&mut inline, &mut blocks);
+            blocks.push(node);
+        }
+    }
+    flush(&mut inline, &mut blocks);
+    blocks
+}
+
+/// The inline nodes of blocks separated by hard breaks, for formats that only hold a line
+/// of text where edita holds blocks.
+pub(super) fn unwrap_inline<'a>(
+    blocks: impl IntoIterator<Item = &'a EditorNode>,
+) -> Vec<EditorNode> {
+    let mut inline = vec![];
+    for block in blocks {
+        if !inline.is_empty() {
+            inline.push(EditorNode::HardBreak(HardBreak));
+        }
+        match block {
+            EditorNode::Paragraph(paragraph) => inline.extend(paragraph.elements.clone()),
+            node if node.is_inline() => inline.push(node.clone()),
+            block => inline.push(EditorNode::Text(TextNode { text: block.text() })),
+        }
     }
+    inline
 }
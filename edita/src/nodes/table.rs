@@ -0,0 +1,948 @@
+use edita_core::{process_nodes, Block, Command, Editor};
+use hirola::{dom::Dom, prelude::*};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::JsCast;
+use web_sys::{window, Element};
+
+use crate::{
+    editor::HtmlNode,
+    formats::html::escape,
+    nodes::{EditorNode, Node},
+    state::EditorState,
+    suggestion::Suggestion,
+    toolbar::ToolbarItem,
+};
+
+/// The `(row, cell)` index of a cell in [`Table::rows`].
+pub type CellIndex = (usize, usize);
+
+/// A `(row, column)` position in the grid the cells are laid out on.
+pub type Slot = (usize, usize);
+
+#[derive(Clone, Serialize, Deserialize, JsonSchema, Default, Debug)]
+pub struct Table {
+    pub rows: Vec<TableRow>,
+}
+
+#[derive(Clone, Serialize, Deserialize, JsonSchema, Default, Debug)]
+pub struct TableRow {
+    pub cells: Vec<TableCell>,
+}
+
+#[derive(Clone, Serialize, Deserialize, JsonSchema, Debug)]
+pub struct TableCell {
+    /// Whether this is a header cell, rendered as `<th>`.
+    pub header: bool,
+    pub colspan: u32,
+    pub rowspan: u32,
+    pub content: Vec<EditorNode>,
+}
+
+impl Default for TableCell {
+    fn default() -> Self {
+        Self {
+            header: false,
+            colspan: 1,
+            rowspan: 1,
+            content: vec![],
+        }
+    }
+}
+
+impl TableCell {
+    pub fn new(header: bool, content: Vec<EditorNode>) -> Self {
+        Self {
+            header,
+            content,
+            ..Default::default()
+        }
+    }
+}
+
+impl TableRow {
+    /// Whether the row only holds header cells.
+    pub fn is_header(&self) -> bool {
+        !self.cells.is_empty() && self.cells.iter().all(|cell| cell.header)
+    }
+}
+
+/// The slots covered by a cell, as `(top, left, bottom, right)` inclusive bounds.
+type Extent = (usize, usize, usize, usize);
+
+impl Table {
+    /// Creates a table of empty cells, the first row holding headers when `header_row` is set.
+    pub fn new(rows: usize, columns: usize, header_row: bool) -> Self {
+        Table {
+            rows: (0..rows)
+                .map(|row| TableRow {
+                    cells: (0..columns)
+                        .map(|_| TableCell::new(header_row && row == 0, vec![]))
+                        .collect(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Lays the cells out on a grid, each slot holding the index of the cell covering it.
+    ///
+    /// Every grid row has the same width, slots no cell covers are `None` and spans reaching
+    /// past the last row are cut.
+    pub fn grid(&self) -> Vec<Vec<Option<CellIndex>>> {
+        let height = self.rows.len();
+        let mut grid: Vec<Vec<Option<CellIndex>>> = vec![vec![]; height];
+        for (r, row) in self.rows.iter().enumerate() {
+            let mut column = 0;
+            for (i, cell) in row.cells.iter().enumerate() {
+                while grid[r].get(column).is_some_and(Option::is_some) {
+                    column += 1;
+                }
+                let colspan = cell.colspan.max(1) as usize;
+                for slots in grid.iter_mut().skip(r).take(cell.rowspan.max(1) as usize) {
+                    if slots.len() < column + colspan {
+                        slots.resize(column + colspan, None);
+                    }
+                    slots[column..column + colspan].fill(Some((r, i)));
+                }
+                column += colspan;
+            }
+        }
+        let width = grid.iter().map(Vec::len).max().unwrap_or_default();
+        for slots in &mut grid {
+            slots.resize(width, None);
+        }
+        grid
+    }
+
+    /// The number of columns of the grid.
+    pub fn width(&self) -> usize {
+        self.grid().first().map_or(0, Vec::len)
+    }
+
+    fn extent(grid: &[Vec<Option<CellIndex>>], cell: CellIndex) -> Option<Extent> {
+        let mut extent: Option<Extent> = None;
+        for (r, slots) in grid.iter().enumerate() {
+            for (c, slot) in slots.iter().enumerate() {
+                if *slot == Some(cell) {
+                    extent = Some(match extent {
+                        None => (r, c, r, c),
+                        Some((top, left, bottom, right)) => {
+                            (top.min(r), left.min(c), bottom.max(r), right.max(c))
+                        }
+                    });
+                }
+            }
+        }
+        extent
+    }
+
+    /// The slot at the top left of a cell.
+    pub fn slot(&self, cell: CellIndex) -> Option<Slot> {
+        Self::extent(&self.grid(), cell).map(|(top, left, _, _)| (top, left))
+    }
+
+    /// The index a cell starting at `column` takes in row `r`.
+    fn insertion_index(&self, grid: &[Vec<Option<CellIndex>>], r: usize, column: usize) -> usize {
+        (0..self.rows[r].cells.len())
+            .filter(|i| Self::extent(grid, (r, *i)).is_some_and(|(_, left, _, _)| left < column))
+            .count()
+    }
+
+    /// Inserts an empty row before grid row `at`, stretching the cells spanning across it.
+    pub fn insert_row(&mut self, at: usize) {
+        let grid = self.grid();
+        let at = at.min(self.rows.len());
+        let width = grid.first().map_or(0, Vec::len);
+        let mut cells = vec![];
+        let mut stretched = vec![];
+        let mut column = 0;
+        while column < width {
+            match grid.get(at).and_then(|slots| slots[column]) {
+                Some((r, i)) if r < at => {
+                    if !stretched.contains(&(r, i)) {
+                        stretched.push((r, i));
+                    }
+                    column += self.rows[r].cells[i].colspan.max(1) as usize;
+                }
+                _ => {
+                    cells.push(TableCell::default());
+                    column += 1;
+                }
+            }
+        }
+        for (r, i) in stretched {
+            self.rows[r].cells[i].rowspan += 1;
+        }
+        self.rows.insert(at, TableRow { cells });
+    }
+
+    /// Removes grid row `at`, shrinking the cells spanning across it.
+    pub fn remove_row(&mut self, at: usize) {
+        if at >= self.rows.len() {
+            return;
+        }
+        let grid = self.grid();
+        let height = self.rows.len();
+        let mut seen = vec![];
+        let mut moved = vec![];
+        for (column, slot) in grid[at].iter().enumerate() {
+            let Some((r, i)) = *slot else {
+                continue;
+            };
+            if seen.contains(&(r, i)) {
+                continue;
+            }
+            seen.push((r, i));
+            let cell = &mut self.rows[r].cells[i];
+            if r < at {
+                cell.rowspan -= 1;
+            } else if cell.rowspan > 1 && at + 1 < height {
+                // The cell continues below, so it moves down into the next row.
+                let mut cell = cell.clone();
+                cell.rowspan -= 1;
+                moved.push((self.insertion_index(&grid, at + 1, column), cell));
+            }
+        }
+        self.rows.remove(at);
+        for (offset, (index, cell)) in moved.into_iter().enumerate() {
+            self.rows[at].cells.insert(index + offset, cell);
+        }
+    }
+
+    /// Inserts an empty column before grid column `at`, widening the cells spanning across it.
+    pub fn insert_column(&mut self, at: usize) {
+        let grid = self.grid();
+        let at = at.min(grid.first().map_or(0, Vec::len));
+        let mut widened = vec![];
+        let mut inserted = vec![];
+        for (r, slots) in grid.iter().enumerate() {
+            match slots.get(at).copied().flatten() {
+                Some(cell) if at > 0 && slots[at - 1] == Some(cell) => {
+                    if !widened.contains(&cell) {
+                        widened.push(cell);
+                    }
+                }
+                // The slot is covered by a cell from a row above that moves right with its row.
+                Some((origin, _)) if origin < r => {}
+                _ => {
+                    let neighbour = slots
+                        .get(at)
+                        .or(at.checked_sub(1).and_then(|c| slots.get(c)));
+                    let header = neighbour
+                        .copied()
+                        .flatten()
+                        .is_some_and(|(r, i)| self.rows[r].cells[i].header);
+                    inserted.push((r, self.insertion_index(&grid, r, at), header));
+                }
+            }
+        }
+        for (r, i) in widened {
+            self.rows[r].cells[i].colspan += 1;
+        }
+        for (r, index, header) in inserted {
+            self.rows[r]
+                .cells
+                .insert(index, TableCell::new(header, vec![]));
+        }
+    }
+
+    /// Removes grid column `at`, narrowing the cells spanning across it.
+    pub fn remove_column(&mut self, at: usize) {
+        let grid = self.grid();
+        let mut seen = vec![];
+        let mut removed = vec![];
+        for slots in &grid {
+            let Some((r, i)) = slots.get(at).copied().flatten() else {
+                continue;
+            };
+            if seen.contains(&(r, i)) {
+                continue;
+            }
+            seen.push((r, i));
+            let cell = &mut self.rows[r].cells[i];
+            if cell.colspan > 1 {
+                cell.colspan -= 1;
+            } else {
+                removed.push((r, i));
+            }
+        }
+        for (r, i) in removed.into_iter().rev() {
+            self.rows[r].cells.remove(i);
+        }
+    }
+
+    /// Merges the cells of the smallest rectangle holding slots `a` and `b` and no partial
+    /// cell into its top left cell, returning false when there is nothing to merge.
+    pub fn merge(&mut self, a: Slot, b: Slot) -> bool {
+        let grid = self.grid();
+        let height = grid.len();
+        let width = grid.first().map_or(0, Vec::len);
+        let (mut top, mut left) = (a.0.min(b.0), a.1.min(b.1));
+        let (mut bottom, mut right) = (a.0.max(b.0), a.1.max(b.1));
+        if bottom >= height || right >= width {
+            return false;
+        }
+        loop {
+            let mut grown = false;
+            for slots in &grid[top..=bottom] {
+                for cell in slots[left..=right].iter().flatten() {
+                    let Some((t, l, b, r)) = Self::extent(&grid, *cell) else {
+                        continue;
+                    };
+                    if t < top || l < left || b > bottom || r > right {
+                        (top, left) = (top.min(t), left.min(l));
+                        (bottom, right) = (bottom.max(b), right.max(r));
+                        grown = true;
+                    }
+                }
+            }
+            if !grown {
+                break;
+            }
+        }
+        let mut cells: Vec<CellIndex> = vec![];
+        for slots in &grid[top..=bottom] {
+            for cell in slots[left..=right].iter().flatten() {
+                if !cells.contains(cell) {
+                    cells.push(*cell);
+                }
+            }
+        }
+        let Some((&target, merged)) = cells.split_first() else {
+            return false;
+        };
+        if merged.is_empty() {
+            return false;
+        }
+        let mut content = vec![];
+        for &(r, i) in merged {
+            content.append(&mut self.rows[r].cells[i].content);
+        }
+        let cell = &mut self.rows[target.0].cells[target.1];
+        cell.content.append(&mut content);
+        cell.colspan = (right - left + 1) as u32;
+        cell.rowspan = (bottom - top + 1) as u32;
+        let mut merged = merged.to_vec();
+        merged.sort();
+        for (r, i) in merged.into_iter().rev() {
+            self.rows[r].cells.remove(i);
+        }
+        true
+    }
+
+    /// Splits the cell covering `slot` back into single slot cells, returning false when it
+    /// does not span several slots.
+    pub fn split(&mut self, slot: Slot) -> bool {
+        let grid = self.grid();
+        let Some(cell) = grid
+            .get(slot.0)
+            .and_then(|slots| slots.get(slot.1))
+            .copied()
+            .flatten()
+        else {
+            return false;
+        };
+        let Some((top, left, bottom, right)) = Self::extent(&grid, cell) else {
+            return false;
+        };
+        if top == bottom && left == right {
+            return false;
+        }
+        let header = self.rows[cell.0].cells[cell.1].header;
+        let mut inserted = vec![];
+        for r in top..=bottom {
+            let index = if r == top {
+                cell.1 + 1
+            } else {
+                self.insertion_index(&grid, r, left)
+            };
+            let count = if r == top {
+                right - left
+            } else {
+                right - left + 1
+            };
+            inserted.push((r, index, count));
+        }
+        let target = &mut self.rows[cell.0].cells[cell.1];
+        target.colspan = 1;
+        target.rowspan = 1;
+        for (r, index, count) in inserted {
+            for _ in 0..count {
+                self.rows[r]
+                    .cells
+                    .insert(index, TableCell::new(header, vec![]));
+            }
+        }
+        true
+    }
+}
+
+impl Node for Table {
+    fn render(&self) -> Dom {
+        let headers = self.rows.iter().take_while(|row| row.is_header()).count();
+        let (head, body) = self.rows.split_at(headers);
+        html! {
+            <table>
+                {(!head.is_empty())
+                    .then(|| {
+                        html! {
+                            <thead>
+                                {for row in head {
+                                    row.render()
+                                }}
+                            </thead>
+                        }
+                    })}
+                <tbody>
+                    {for row in body {
+                        row.render()
+                    }}
+                </tbody>
+            </table>
+        }
+    }
+}
+
+impl Node for TableRow {
+    fn render(&self) -> Dom {
+        html! {
+            <tr>
+                {for cell in &self.cells {
+                    cell.render()
+                }}
+            </tr>
+        }
+    }
+}
+
+impl Node for TableCell {
+    fn render(&self) -> Dom {
+        use hirola::prelude::DefaultAttrStr;
+        use hirola::prelude::DefaultAttributeEffect;
+        let colspan = self.colspan.to_string();
+        let rowspan = self.rowspan.to_string();
+        if self.header {
+            html! {
+                <th colspan=&colspan rowspan=&rowspan>
+                    {for node in &self.content {
+                        node.render()
+                    }}
+                </th>
+            }
+        } else {
+            html! {
+                <td colspan=&colspan rowspan=&rowspan>
+                    {for node in &self.content {
+                        node.render()
+                    }}
+                </td>
+            }
+        }
+    }
+}
+
+/// The element children of a node.
+fn child_elements(node: &web_sys::Node) -> Vec<Element> {
+    let children = node.child_nodes();
+    (0..children.length())
+        .filter_map(|i| children.get(i)?.dyn_into::<Element>().ok())
+        .collect()
+}
+
+/// The `<tr>` elements of a table, through its `thead`, `tbody` and `tfoot` sections.
+fn row_elements(table: &Element) -> Vec<Element> {
+    let mut rows = vec![];
+    for child in child_elements(table) {
+        match child.tag_name().as_str() {
+            "TR" => rows.push(child),
+            "THEAD" | "TBODY" | "TFOOT" => rows.extend(
+                child_elements(&child)
+                    .into_iter()
+                    .filter(|row| row.tag_name() == "TR"),
+            ),
+            _ => {}
+        }
+    }
+    rows
+}
+
+fn cell_elements(row: &Element) -> Vec<Element> {
+    child_elements(row)
+        .into_iter()
+        .filter(|cell| matches!(cell.tag_name().as_str(), "TD" | "TH"))
+        .collect()
+}
+
+fn span(cell: &Element, name: &str) -> u32 {
+    cell.get_attribute(name)
+        .and_then(|span| span.trim().parse().ok())
+        .filter(|span| *span > 0)
+        .unwrap_or(1)
+}
+
+/// Reads a table element, parsing each cell's content with `content`.
+fn read_table(table: &Element, content: impl Fn(&Element) -> Vec<EditorNode>) -> Table {
+    Table {
+        rows: row_elements(table)
+            .iter()
+            .map(|row| TableRow {
+                cells: cell_elements(row)
+                    .iter()
+                    .map(|cell| TableCell {
+                        header: cell.tag_name() == "TH",
+                        colspan: span(cell, "colspan"),
+                        rowspan: span(cell, "rowspan"),
+                        content: content(cell),
+                    })
+                    .collect(),
+            })
+            .collect(),
+    }
+}
+
+/// Whether the cursor is in a table cell, and not in a list nested inside it which takes
+/// Tab for itself.
+fn cell_at_cursor(state: &EditorState) -> bool {
+    state
+        .closest_at_cursor("td, th, li:not([data-type=taskItem])")
+        .is_some_and(|element| element.tag_name() != "LI")
+}
+
+pub struct TableBlock;
+
+impl Block for TableBlock {
+    type Node = EditorNode;
+    type Input = web_sys::Node;
+    type State = EditorState;
+
+    fn hook(&self, editor: &mut Editor<Self::Node, Self::State, Self::Input>) {
+        editor.bind_key("Tab", |state| {
+            let handled = cell_at_cursor(state);
+            if handled {
+                state.execute(GoToNextCell);
+            }
+            handled
+        });
+        editor.bind_key("Shift-Tab", |state| {
+            let handled = cell_at_cursor(state);
+            if handled {
+                state.execute(GoToPreviousCell);
+            }
+            handled
+        });
+        let table = Table::new(3, 3, true);
+        editor.add_slash_item(Suggestion::new("Table", EditorNode::Table(table)));
+        let insert = InsertTable {
+            rows: 3,
+            columns: 3,
+            header_row: true,
+        };
+        editor.add_toolbar_item(
+            ToolbarItem::new("table", "Table", insert)
+                .with_icon("Table")
+                .with_group("insert"),
+        );
+        let items = [
+            ToolbarItem::new("add-row", "Add row", AddRowAfter).with_icon("+Row"),
+            ToolbarItem::new("add-column", "Add column", AddColumnAfter).with_icon("+Col"),
+            ToolbarItem::new("merge-cells", "Merge cells", MergeCells).with_icon("Merge"),
+            ToolbarItem::new("split-cell", "Split cell", SplitCell).with_icon("Split"),
+        ];
+        for item in items {
+            editor.add_toolbar_item(item.with_group("table").with_enabled(cell_at_cursor));
+        }
+    }
+
+    fn accepts(&self, node: &web_sys::Node) -> bool {
+        if let Some(element) = node.dyn_ref::<Element>() {
+            element.tag_name() == "TABLE"
+        } else {
+            false
+        }
+    }
+
+    fn parse(
+        &self,
+        editor: &Editor<Self::Node, EditorState, web_sys::Node>,
+        node: &web_sys::Node,
+    ) -> EditorNode {
+        let table: &Element = node.dyn_ref().unwrap();
+        EditorNode::Table(read_table(table, |cell| {
+            let children = cell.child_nodes();
+            process_nodes(
+                editor,
+                (0..children.length()).filter_map(|i| children.get(i)),
+            )
+        }))
+    }
+}
+
+/// Inserts a table after the block at the cursor.
+pub struct InsertTable {
+    pub rows: usize,
+    pub columns: usize,
+    pub header_row: bool,
+}
+
+impl Command<EditorState> for InsertTable {
+    fn execute(&self, state: &mut EditorState) {
+        state.insert_block(Table::new(self.rows, self.columns, self.header_row));
+    }
+}
+
+/// The table holding the cursor, read with each cell's markup kept as an opaque html node.
+struct TableAtCursor {
+    element: Element,
+    table: Table,
+    /// The slot of the cell holding the start of the selection.
+    anchor: Slot,
+    /// The slot of the cell holding the end of the selection.
+    head: Slot,
+}
+
+impl TableAtCursor {
+    fn new(state: &EditorState) -> Option<Self> {
+        let cell = state.closest_at_cursor("td, th")?;
+        let element = cell.closest("table").ok()??;
+        let table = read_table(&element, |cell| {
+            let html = cell.inner_html();
+            match html.is_empty() {
+                true => vec![],
+                false => vec![EditorNode::Html(HtmlNode(html))],
+            }
+        });
+        let slot_of = |node: web_sys::Node| -> Option<Slot> {
+            let node = match node.dyn_into::<Element>() {
+                Ok(element) => element,
+                Err(node) => node.parent_element()?,
+            };
+            let cell = node.closest("td, th").ok()??;
+            let rows = row_elements(&element);
+            let index = rows.iter().enumerate().find_map(|(r, row)| {
+                let i = cell_elements(row).iter().position(|c| *c == cell)?;
+                Some((r, i))
+            })?;
+            table.slot(index)
+        };
+        let range = state.selection_range()?;
+        let anchor = slot_of(range.start_container().ok()?)?;
+        let head = slot_of(range.end_container().ok()?).unwrap_or(anchor);
+        Some(TableAtCursor {
+            element,
+            table,
+            anchor,
+            head,
+        })
+    }
+
+    /// Writes the table back and moves the cursor into the cell covering `slot`.
+    fn write(self, state: &EditorState, slot: Slot) {
+        let Self { element, table, .. } = self;
+        element.set_inner_html(&raw_html(&table));
+        let grid = table.grid();
+        let slot = (
+            slot.0.min(grid.len().saturating_sub(1)),
+            slot.1
+                .min(grid.first().map_or(0, Vec::len).saturating_sub(1)),
+        );
+        let cell = grid
+            .get(slot.0)
+            .and_then(|slots| slots.get(slot.1))
+            .copied()
+            .flatten()
+            .and_then(|(r, i)| {
+                cell_elements(row_elements(&element).get(r)?)
+                    .get(i)
+                    .cloned()
+            });
+        if let Some(cell) = cell {
+            let range = window()
+                .unwrap()
+                .document()
+                .unwrap()
+                .create_range()
+                .unwrap();
+            range.select_node_contents(&cell).unwrap();
+            range.collapse_with_to_start(true);
+            state.select_range(&range);
+        }
+    }
+}
+
+/// Writes the rows of a table whose cells hold opaque html nodes.
+fn raw_html(table: &Table) -> String {
+    let row = |row: &TableRow| {
+        let mut html = String::from("<tr>");
+        for cell in &row.cells {
+            let tag = if cell.header { "th" } else { "td" };
+            html.push_str(&format!("<{tag}"));
+            for (name, span) in [("colspan", cell.colspan), ("rowspan", cell.rowspan)] {
+                if span > 1 {
+                    html.push_str(&format!(r#" {name}="{span}""#));
+                }
+            }
+            html.push('>');
+            for node in &cell.content {
+                match node {
+                    EditorNode::Html(HtmlNode(raw)) => html.push_str(raw),
+                    node => html.push_str(&escape(&node.text())),
+                }
+            }
+            html.push_str(&format!("</{tag}>"));
+        }
+        html.push_str("</tr>");
+        html
+    };
+    let headers = table.rows.iter().take_while(|row| row.is_header()).count();
+    let (head, body) = table.rows.split_at(headers);
+    let mut html = String::new();
+    if !head.is_empty() {
+        html.push_str("<thead>");
+        html.extend(head.iter().map(row));
+        html.push_str("</thead>");
+    }
+    html.push_str("<tbody>");
+    html.extend(body.iter().map(row));
+    html.push_str("</tbody>");
+    html
+}
+
+macro_rules! table_command {
+    ($(#[$doc:meta])* $name:ident, |$table:ident, $anchor:ident, $head:ident| $body:expr) => {
+        $(#[$doc])*
+        pub struct $name;
+
+        impl Command<EditorState> for $name {
+            fn execute(&self, state: &mut EditorState) {
+                if let Some(mut at_cursor) = TableAtCursor::new(state) {
+                    let ($table, $anchor, $head) =
+                        (&mut at_cursor.table, at_cursor.anchor, at_cursor.head);
+                    let slot: Slot = $body;
+                    at_cursor.write(state, slot);
+                }
+            }
+        }
+    };
+}
+
+table_command!(
+    /// Adds a row above the cursor.
+    AddRowBefore,
+    |table, anchor, _head| {
+        table.insert_row(anchor.0);
+        anchor
+    }
+);
+
+table_command!(
+    /// Adds a row below the cursor.
+    AddRowAfter,
+    |table, anchor, _head| {
+        let grid = table.grid();
+        let bottom = grid[anchor.0][anchor.1]
+            .and_then(|cell| Table::extent(&grid, cell))
+            .map_or(anchor.0, |(_, _, bottom, _)| bottom);
+        table.insert_row(bottom + 1);
+        (bottom + 1, anchor.1)
+    }
+);
+
+table_command!(
+    /// Removes the row holding the cursor.
+    DeleteRow,
+    |table, anchor, _head| {
+        table.remove_row(anchor.0);
+        (anchor.0.saturating_sub(1), anchor.1)
+    }
+);
+
+table_command!(
+    /// Adds a column left of the cursor.
+    AddColumnBefore,
+    |table, anchor, _head| {
+        table.insert_column(anchor.1);
+        anchor
+    }
+);
+
+table_command!(
+    /// Adds a column right of the cursor.
+    AddColumnAfter,
+    |table, anchor, _head| {
+        let grid = table.grid();
+        let right = grid[anchor.0][anchor.1]
+            .and_then(|cell| Table::extent(&grid, cell))
+            .map_or(anchor.1, |(_, _, _, right)| right);
+        table.insert_column(right + 1);
+        (anchor.0, right + 1)
+    }
+);
+
+table_command!(
+    /// Removes the column holding the cursor.
+    DeleteColumn,
+    |table, anchor, _head| {
+        table.remove_column(anchor.1);
+        (anchor.0, anchor.1.saturating_sub(1))
+    }
+);
+
+table_command!(
+    /// Merges the cells between the start and the end of the selection.
+    MergeCells,
+    |table, anchor, head| {
+        table.merge(anchor, head);
+        (anchor.0.min(head.0), anchor.1.min(head.1))
+    }
+);
+
+table_command!(
+    /// Splits the merged cell at the cursor.
+    SplitCell,
+    |table, anchor, _head| {
+        table.split(anchor);
+        anchor
+    }
+);
+
+/// Moves the cursor to the next cell, adding a row when leaving the last one.
+pub struct GoToNextCell;
+
+impl Command<EditorState> for GoToNextCell {
+    fn execute(&self, state: &mut EditorState) {
+        go_to_cell(state, 1);
+    }
+}
+
+/// Moves the cursor to the previous cell.
+pub struct GoToPreviousCell;
+
+impl Command<EditorState> for GoToPreviousCell {
+    fn execute(&self, state: &mut EditorState) {
+        go_to_cell(state, -1);
+    }
+}
+
+fn go_to_cell(state: &mut EditorState, step: isize) {
+    let Some(cell) = state.closest_at_cursor("td, th") else {
+        return;
+    };
+    let Some(table) = cell.closest("table").ok().flatten() else {
+        return;
+    };
+    let cells: Vec<Element> = row_elements(&table)
+        .iter()
+        .flat_map(cell_elements)
+        .collect();
+    let Some(index) = cells.iter().position(|c| *c == cell) else {
+        return;
+    };
+    match index.checked_add_signed(step).and_then(|i| cells.get(i)) {
+        Some(next) => {
+            let range = window()
+                .unwrap()
+                .document()
+                .unwrap()
+                .create_range()
+                .unwrap();
+            range.select_node_contents(next).unwrap();
+            range.collapse_with_to_start(false);
+            state.select_range(&range);
+        }
+        None if step > 0 => {
+            if let Some(mut at_cursor) = TableAtCursor::new(state) {
+                let bottom = at_cursor.table.rows.len();
+                at_cursor.table.insert_row(bottom);
+                at_cursor.write(state, (bottom, 0));
+            }
+        }
+        None => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nodes::TextNode;
+
+    fn text(table: &Table) -> Vec<Vec<String>> {
+        table
+            .rows
+            .iter()
+            .map(|row| {
+                row.cells
+                    .iter()
+                    .map(|cell| {
+                        let text: String = cell.content.iter().map(EditorNode::text).collect();
+                        format!("{text}:{}x{}", cell.colspan, cell.rowspan)
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn table(rows: &[&[&str]]) -> Table {
+        Table {
+            rows: rows
+                .iter()
+                .map(|row| TableRow {
+                    cells: row
+                        .iter()
+                        .map(|text| {
+                            TableCell::new(
+                                false,
+                                vec![EditorNode::Text(TextNode {
+                                    text: text.to_string(),
+                                })],
+                            )
+                        })
+                        .collect(),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_table_editing() {
+        let mut t = table(&[&["a", "b", "c"], &["d", "e", "f"], &["g", "h", "i"]]);
+        assert!(t.merge((0, 0), (1, 1)));
+        assert_eq!(
+            text(&t),
+            [
+                vec!["abde:2x2", "c:1x1"],
+                vec!["f:1x1"],
+                vec!["g:1x1", "h:1x1", "i:1x1"]
+            ]
+        );
+        assert_eq!(t.grid()[1], [Some((0, 0)), Some((0, 0)), Some((1, 0))]);
+
+        // A row inserted through the merged cell stretches it.
+        t.insert_row(1);
+        assert_eq!(t.rows[0].cells[0].rowspan, 3);
+        assert_eq!(t.rows[1].cells.len(), 1);
+        t.remove_row(1);
+
+        // A column inserted through the merged cell widens it.
+        t.insert_column(1);
+        assert_eq!(t.rows[0].cells[0].colspan, 3);
+        assert_eq!(t.rows[2].cells.len(), 4);
+        t.remove_column(1);
+        assert_eq!(t.width(), 3);
+
+        // Removing the first row moves the merged cell down.
+        let mut removed = t.clone();
+        removed.remove_row(0);
+        assert_eq!(text(&removed)[0], ["abde:2x1", "f:1x1"]);
+
+        assert!(t.split((1, 1)));
+        assert_eq!(
+            text(&t),
+            [
+                vec!["abde:1x1", ":1x1", "c:1x1"],
+                vec![":1x1", ":1x1", "f:1x1"],
+                vec!["g:1x1", "h:1x1", "i:1x1"],
+            ]
+        );
+        assert!(!t.split((0, 0)));
+    }
+}
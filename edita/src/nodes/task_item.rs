@@ -1,17 +1,51 @@
-use edita_core::{Block, Editor};
+use edita_core::{Block, Command, Editor};
 use hirola::prelude::*;
-use serde::Serialize;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 use wasm_bindgen::JsCast;
-use web_sys::{Element, Node};
+use web_sys::{Element, HtmlInputElement, Node};
 
 use crate::{nodes::EditorNode, EditorState};
 
+use super::list_item::create_element;
+use super::task_list::TaskList;
+
 pub struct TaskItemBlock;
 
 impl Block for TaskItemBlock {
     type Node = EditorNode;
     type State = EditorState;
     type Input = web_sys::Node;
+
+    fn hook(&self, editor: &mut Editor<Self::Node, Self::State, Self::Input>) {
+        editor.on_click(|state, target| {
+            if target.matches(CHECKBOX).unwrap_or_default() {
+                state.execute(ToggleTask(target.closest(ITEM).ok().flatten()));
+            }
+        });
+        editor.bind_key("Mod-Enter", |state| {
+            let handled = state.closest_at_cursor(ITEM).is_some();
+            if handled {
+                state.execute(ToggleTask(None));
+            }
+            handled
+        });
+        editor.bind_key("Tab", |state| {
+            let handled = task_at_cursor(state).is_some();
+            if handled {
+                state.execute(IndentTask);
+            }
+            handled
+        });
+        editor.bind_key("Shift-Tab", |state| {
+            let handled = task_at_cursor(state).is_some();
+            if handled {
+                state.execute(OutdentTask);
+            }
+            handled
+        });
+    }
+
     fn accepts(&self, node: &Node) -> bool {
         if let Some(element) = node.dyn_ref::<Element>() {
             element.tag_name() == "LI"
@@ -28,26 +62,277 @@ impl Block for TaskItemBlock {
         _editor: &Editor<Self::Node, Self::State, web_sys::Node>,
         node: &web_sys::Node,
     ) -> EditorNode {
-        EditorNode::TaskItem(TaskItem {
-            text: node.text_content().unwrap_or_default(),
-            checked: true,
-        })
+        EditorNode::TaskItem(read_task(node.dyn_ref().unwrap()))
+    }
+}
+
+const ITEM: &str = "li[data-type=taskItem]";
+
+const CHECKBOX: &str = "li[data-type=taskItem] > label > input[type=checkbox]";
+
+const LIST: &str = "ul[data-type=taskList]";
+
+/// The task item around the cursor, unless the cursor is in a list item, table cell or
+/// code block nested inside it.
+fn task_at_cursor(state: &EditorState) -> Option<Element> {
+    state
+        .closest_at_cursor("li, td, th, pre")
+        .filter(|element| element.matches(ITEM).unwrap_or_default())
+}
+
+/// The `<div>` holding the text and subtasks of a task item, created if the item is loose
+/// markup without one.
+fn task_body(item: &Element) -> Element {
+    if let Some(body) = item.query_selector(":scope > div").ok().flatten() {
+        return body;
     }
+    let body = create_element("div");
+    item.append_child(&body).unwrap();
+    body
 }
 
-#[derive(Clone, Serialize, Debug)]
+fn checkbox(item: &Element) -> Option<HtmlInputElement> {
+    item.query_selector(":scope > label > input[type=checkbox], :scope > input[type=checkbox]")
+        .ok()??
+        .dyn_into()
+        .ok()
+}
+
+/// Reads a task item, either as rendered by [`TaskItem`] or as a plain
+/// `<li><input type="checkbox"> text</li>`.
+pub(super) fn read_task(item: &Element) -> TaskItem {
+    // The checkbox property follows clicks even when no click handler is wired.
+    let checked = match checkbox(item) {
+        Some(input) => input.checked(),
+        None => item.get_attribute("data-checked").as_deref() == Some("true"),
+    };
+    let body = item.query_selector(":scope > div").ok().flatten();
+    let mut texts = vec![];
+    let mut children = vec![];
+    let parent: &Node = body.as_deref().unwrap_or(item);
+    let nodes = parent.child_nodes();
+    for node in (0..nodes.length()).filter_map(|i| nodes.get(i)) {
+        match node.dyn_ref::<Element>().map(Element::tag_name).as_deref() {
+            Some("UL") => children.extend(read_task_list(node.unchecked_ref())),
+            Some("LABEL" | "INPUT") => {}
+            _ => texts.push(node.text_content().unwrap_or_default()),
+        }
+    }
+    let text = texts.join("");
+    TaskItem {
+        // Loose markup usually has a space between the checkbox and the text.
+        text: match body {
+            Some(_) => text,
+            None => text.trim().to_owned(),
+        },
+        checked,
+        children,
+    }
+}
+
+/// Reads the task items of a `<ul>`.
+pub(super) fn read_task_list(list: &Element) -> Vec<TaskItem> {
+    let items = list.child_nodes();
+    (0..items.length())
+        .filter_map(|i| items.get(i)?.dyn_into::<Element>().ok())
+        .filter(|item| item.tag_name() == "LI")
+        .map(|item| read_task(&item))
+        .collect()
+}
+
+#[derive(Clone, Serialize, Deserialize, JsonSchema, Default, Debug)]
 pub struct TaskItem {
-    text: String,
-    checked: bool,
+    pub text: String,
+    pub checked: bool,
+    /// The subtasks nested under this one.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<TaskItem>,
+}
+
+impl TaskItem {
+    /// The text of this task followed by the text of its subtasks, one per line.
+    pub fn plain_text(&self) -> String {
+        std::iter::once(self.text.clone())
+            .chain(self.children.iter().map(TaskItem::plain_text))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
 }
 
 impl crate::nodes::Node for TaskItem {
     fn render(&self) -> hirola::dom::Dom {
-        html! {
-            <li data-type="dataItem">
-                <input type="checkbox" checked=&self.checked.to_string()/>
-                {&self.text}
+        use hirola::prelude::DefaultAttrStr;
+        use hirola::prelude::DefaultAttributeEffect;
+        let checked = self.checked.to_string();
+        let item = html! {
+            <li data-type="taskItem" data-checked=&checked>
+                <label contenteditable="false">
+                    <input type="checkbox"/>
+                </label>
+                <div>
+                    <p>{&self.text}</p>
+                    {(!self.children.is_empty())
+                        .then(|| {
+                            TaskList {
+                                list: self.children.clone(),
+                            }
+                                .render()
+                        })}
+                </div>
             </li>
+        };
+        if self.checked {
+            let input = checkbox(item.inner_element().unchecked_ref()).unwrap();
+            input.set_checked(true);
+            input.set_default_checked(true);
+        }
+        item
+    }
+}
+
+/// Checks or unchecks a task item, the one holding the cursor unless one is given.
+///
+/// Wired to clicks on the checkbox by [`TaskItemBlock`], where the browser has already
+/// flipped the checkbox, so the new state is derived from the `data-checked` attribute.
+pub struct ToggleTask(pub Option<Element>);
+
+impl Command<EditorState> for ToggleTask {
+    fn execute(&self, state: &mut EditorState) {
+        let Some(item) = self.0.clone().or_else(|| state.closest_at_cursor(ITEM)) else {
+            return;
+        };
+        let input = checkbox(&item);
+        let was_checked = match item.get_attribute("data-checked") {
+            Some(checked) => checked == "true",
+            None => input.as_ref().is_some_and(|input| input.default_checked()),
+        };
+        item.set_attribute("data-checked", &(!was_checked).to_string())
+            .unwrap();
+        if let Some(input) = input {
+            input.set_checked(!was_checked);
+            input.set_default_checked(!was_checked);
         }
     }
 }
+
+/// Moves the task item at the cursor into the subtasks of the task above it.
+///
+/// Bound to Tab inside task items by [`TaskItemBlock`].
+pub struct IndentTask;
+
+impl Command<EditorState> for IndentTask {
+    fn execute(&self, state: &mut EditorState) {
+        let Some(item) = task_at_cursor(state) else {
+            return;
+        };
+        let Some(previous) = item.previous_element_sibling() else {
+            return;
+        };
+        state.keep_caret(|| {
+            let body = task_body(&previous);
+            let sublist = match body.last_element_child() {
+                Some(last) if last.matches(LIST).unwrap_or_default() => last,
+                _ => {
+                    let sublist = create_element("ul");
+                    sublist.set_attribute("data-type", "taskList").unwrap();
+                    body.append_child(&sublist).unwrap();
+                    sublist
+                }
+            };
+            sublist.append_child(&item).unwrap();
+        });
+    }
+}
+
+/// Moves the task item at the cursor out of the subtasks it is in, to follow their parent
+/// task, the tasks below it becoming its own subtasks. Top level tasks stay in place.
+///
+/// Bound to Shift-Tab inside task items by [`TaskItemBlock`].
+pub struct OutdentTask;
+
+impl Command<EditorState> for OutdentTask {
+    fn execute(&self, state: &mut EditorState) {
+        let Some(item) = task_at_cursor(state) else {
+            return;
+        };
+        let Some(list) = item.parent_element() else {
+            return;
+        };
+        let Some(parent) = list.closest(ITEM).ok().flatten() else {
+            return;
+        };
+        state.keep_caret(|| {
+            let following: Vec<Element> =
+                std::iter::successors(item.next_element_sibling(), Element::next_element_sibling)
+                    .collect();
+            if !following.is_empty() {
+                let rest: Element = list.clone_node().unwrap().unchecked_into();
+                for sibling in &following {
+                    rest.append_child(sibling).unwrap();
+                }
+                task_body(&item).append_child(&rest).unwrap();
+            }
+            parent.after_with_node_1(&item).unwrap();
+            if list.child_element_count() == 0 {
+                list.remove();
+            }
+        });
+    }
+}
+
+#[cfg(all(test, target_arch = "wasm32"))]
+mod tests {
+    use super::*;
+    use crate::nodes::Node as _;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    fn task(text: &str, checked: bool, children: Vec<TaskItem>) -> TaskItem {
+        TaskItem {
+            text: text.to_owned(),
+            checked,
+            children,
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn test_nested_tasks_round_trip() {
+        let list = TaskList {
+            list: vec![
+                task(
+                    "plan",
+                    true,
+                    vec![
+                        task("draft", false, vec![task("outline", true, vec![])]),
+                        task("review", true, vec![]),
+                    ],
+                ),
+                task("ship", false, vec![]),
+            ],
+        };
+        let rendered = list.render().inner_element();
+        let parsed = read_task_list(rendered.unchecked_ref());
+        assert_eq!(
+            serde_json::to_value(&parsed).unwrap(),
+            serde_json::to_value(&list.list).unwrap()
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn test_indent_and_outdent_tasks() {
+        let list = TaskList {
+            list: vec![task("one", true, vec![]), task("two", false, vec![])],
+        };
+        let html = list.render().inner_element();
+        let state = crate::testing::editor(&html.unchecked_ref::<Element>().outer_html());
+        crate::testing::select(&state, "two", "two");
+        state.execute(IndentTask);
+        let root = state.node().get().inner_element();
+        let parsed = read_task_list(&root.first_child().unwrap().unchecked_into());
+        assert_eq!(parsed.len(), 1);
+        assert_eq!(parsed[0].children[0].text, "two");
+        state.execute(OutdentTask);
+        let parsed = read_task_list(&root.first_child().unwrap().unchecked_into());
+        assert_eq!(parsed.len(), 2);
+        assert!(parsed[0].checked && !parsed[1].checked);
+    }
+}
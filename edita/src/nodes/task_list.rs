@@ -1,13 +1,13 @@
-use edita_core::{Block, Editor};
+use edita_core::{Block, Command, Editor};
 use hirola::prelude::*;
-use js_sys::Object;
-use serde::Serialize;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 use wasm_bindgen::JsCast;
 use web_sys::{Element, Node};
 
-use crate::{nodes::EditorNode, EditorState};
+use crate::{nodes::EditorNode, EditorState, Suggestion, ToolbarItem};
 
-use super::task_item::{TaskItem, TaskItemBlock};
+use super::task_item::{read_task_list, TaskItem};
 
 pub struct TaskListBlock;
 
@@ -15,6 +15,17 @@ impl Block for TaskListBlock {
     type Node = EditorNode;
     type State = EditorState;
     type Input = web_sys::Node;
+    fn hook(&self, editor: &mut Editor<Self::Node, Self::State, Self::Input>) {
+        let list = TaskList {
+            list: vec![TaskItem::default()],
+        };
+        editor.add_slash_item(Suggestion::new("Task list", EditorNode::TaskList(list)));
+        editor.add_toolbar_item(
+            ToolbarItem::new("task-list", "Task list", TaskListBlock)
+                .with_icon("Tasks")
+                .with_group("insert"),
+        );
+    }
     fn accepts(&self, node: &Node) -> bool {
         if let Some(element) = node.dyn_ref::<Element>() {
             element.tag_name() == "UL"
@@ -28,27 +39,26 @@ impl Block for TaskListBlock {
 
     fn parse(
         &self,
-        editor: &Editor<Self::Node, Self::State, web_sys::Node>,
+        _editor: &Editor<Self::Node, Self::State, web_sys::Node>,
         node: &web_sys::Node,
     ) -> EditorNode {
         EditorNode::TaskList(TaskList {
-            list: Object::entries(&node.child_nodes())
-                .into_iter()
-                .map(|item| TaskItemBlock::parse(&TaskItemBlock, editor, item.dyn_ref().unwrap()))
-                .map(|node| match node {
-                    EditorNode::TaskItem(item) => Some(item),
-                    _ => None,
-                })
-                .filter(|item| item.is_some())
-                .map(|c| c.unwrap())
-                .collect(),
+            list: read_task_list(node.dyn_ref().unwrap()),
+        })
+    }
+}
+
+impl Command<EditorState> for TaskListBlock {
+    fn execute(&self, state: &mut EditorState) {
+        state.insert_block(TaskList {
+            list: vec![TaskItem::default()],
         })
     }
 }
 
-#[derive(Clone, Serialize, Debug)]
+#[derive(Clone, Serialize, Deserialize, JsonSchema, Debug)]
 pub struct TaskList {
-    list: Vec<TaskItem>,
+    pub list: Vec<TaskItem>,
 }
 
 impl crate::nodes::Node for TaskList {
@@ -1,16 +1,21 @@
 use edita_core::{Block, Editor};
-use hirola::prelude::html;
-use serde::Serialize;
+use hirola::dom::Dom;
+use hirola::prelude::*;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 use wasm_bindgen::JsCast;
-use web_sys::{Element, Node};
+use web_sys::{Element, HtmlElement, Node};
 
-use crate::{nodes::EditorNode, EditorState};
+use crate::commands::marks::{
+    MarkElement, ToggleHighlight, ToggleMark, ToggleSubscript, ToggleSuperscript,
+};
+use crate::{nodes::EditorNode, EditorState, ToolbarItem};
 
 pub struct TextNodeBlock;
 
-#[derive(Clone, Serialize, Debug)]
+#[derive(Clone, Serialize, Deserialize, JsonSchema, Debug)]
 pub struct TextNode {
-    text: String,
+    pub text: String,
 }
 
 impl Block for TextNodeBlock {
@@ -46,6 +51,21 @@ impl Block for BoldBlock {
     type Node = EditorNode;
     type Input = web_sys::Node;
     type State = EditorState;
+    fn hook(&self, editor: &mut Editor<Self::Node, Self::State, Self::Input>) {
+        editor.bind_key("Mod-b", |state| {
+            let handled = has_selected_text(state);
+            if handled {
+                state.execute(ToggleMark::<Bold>::new());
+            }
+            handled
+        });
+        editor.add_toolbar_item(
+            ToolbarItem::new("bold", "Bold", ToggleMark::<Bold>::new())
+                .with_icon("B")
+                .with_group("marks")
+                .with_active(ToggleMark::<Bold>::is_active),
+        );
+    }
     fn accepts(&self, node: &Node) -> bool {
         if let Some(element) = node.dyn_ref::<Element>() {
             element.tag_name() == "B" || element.tag_name() == "STRONG"
@@ -65,9 +85,14 @@ impl Block for BoldBlock {
     }
 }
 
-#[derive(Clone, Serialize, Debug)]
+#[derive(Clone, Serialize, Deserialize, JsonSchema, Debug)]
 pub struct Bold {
-    text: String,
+    pub text: String,
+}
+
+impl MarkElement for Bold {
+    const TAG: &'static str = "b";
+    const SELECTORS: &'static str = "b, strong";
 }
 
 impl crate::nodes::Node for Bold {
@@ -82,6 +107,21 @@ impl Block for ItalicBlock {
     type Node = EditorNode;
     type Input = Node;
     type State = EditorState;
+    fn hook(&self, editor: &mut Editor<Self::Node, Self::State, Self::Input>) {
+        editor.bind_key("Mod-i", |state| {
+            let handled = has_selected_text(state);
+            if handled {
+                state.execute(ToggleMark::<Italic>::new());
+            }
+            handled
+        });
+        editor.add_toolbar_item(
+            ToolbarItem::new("italic", "Italic", ToggleMark::<Italic>::new())
+                .with_icon("I")
+                .with_group("marks")
+                .with_active(ToggleMark::<Italic>::is_active),
+        );
+    }
     fn accepts(&self, node: &Node) -> bool {
         if let Some(element) = node.dyn_ref::<Element>() {
             element.tag_name() == "I" || element.tag_name() == "EM"
@@ -101,9 +141,14 @@ impl Block for ItalicBlock {
     }
 }
 
-#[derive(Clone, Serialize, Debug)]
+#[derive(Clone, Serialize, Deserialize, JsonSchema, Debug)]
 pub struct Italic {
-    text: String,
+    pub text: String,
+}
+
+impl MarkElement for Italic {
+    const TAG: &'static str = "em";
+    const SELECTORS: &'static str = "i, em";
 }
 
 impl crate::nodes::Node for Italic {
@@ -118,6 +163,21 @@ impl Block for InlineCodeBlock {
     type Node = EditorNode;
     type State = EditorState;
     type Input = web_sys::Node;
+    fn hook(&self, editor: &mut Editor<Self::Node, Self::State, Self::Input>) {
+        editor.bind_key("Mod-e", |state| {
+            let handled = has_selected_text(state);
+            if handled {
+                state.execute(ToggleMark::<InlineCode>::new());
+            }
+            handled
+        });
+        editor.add_toolbar_item(
+            ToolbarItem::new("code", "Code", ToggleMark::<InlineCode>::new())
+                .with_icon("</>")
+                .with_group("marks")
+                .with_active(ToggleMark::<InlineCode>::is_active),
+        );
+    }
     fn accepts(&self, node: &Node) -> bool {
         if let Some(element) = node.dyn_ref::<Element>() {
             element.tag_name() == "CODE"
@@ -137,9 +197,14 @@ impl Block for InlineCodeBlock {
     }
 }
 
-#[derive(Clone, Serialize, Debug)]
+#[derive(Clone, Serialize, Deserialize, JsonSchema, Debug)]
 pub struct InlineCode {
-    text: String,
+    pub text: String,
+}
+
+impl MarkElement for InlineCode {
+    const TAG: &'static str = "code";
+    const SELECTORS: &'static str = "code";
 }
 
 impl crate::nodes::Node for InlineCode {
@@ -147,3 +212,298 @@ impl crate::nodes::Node for InlineCode {
         html! { <code>{&self.text}</code> }
     }
 }
+
+/// Checks a css colour, returning it trimmed when it is a hex colour, an `rgb()`, `rgba()`,
+/// `hsl()` or `hsla()` function or a colour name, so that it cannot smuggle other
+/// properties into a style attribute.
+pub fn sanitize_color(color: &str) -> Option<String> {
+    let color = color.trim();
+    let valid = if let Some(hex) = color.strip_prefix('#') {
+        matches!(hex.len(), 3 | 4 | 6 | 8) && hex.chars().all(|c| c.is_ascii_hexdigit())
+    } else if let Some((function, arguments)) = color.split_once('(') {
+        matches!(
+            function.to_ascii_lowercase().as_str(),
+            "rgb" | "rgba" | "hsl" | "hsla"
+        ) && arguments.strip_suffix(')').is_some_and(|arguments| {
+            arguments
+                .chars()
+                .all(|c| c.is_ascii_digit() || matches!(c, '.' | ',' | '%' | ' ' | '/' | '-'))
+        })
+    } else {
+        !color.is_empty() && color.chars().all(|c| c.is_ascii_alphabetic())
+    };
+    valid.then(|| color.to_owned())
+}
+
+/// The value of an inline style property of a `<span>`, if it is set.
+fn span_style(node: &Node, property: &str) -> Option<String> {
+    let element = node.dyn_ref::<HtmlElement>()?;
+    if element.tag_name() != "SPAN" {
+        return None;
+    }
+    let value = element.style().get_property_value(property).ok()?;
+    (!value.is_empty()).then_some(value)
+}
+
+/// Whether the selection holds text for a mark shortcut to apply to, letting the key
+/// through otherwise.
+fn has_selected_text(state: &EditorState) -> bool {
+    state
+        .selection_range()
+        .is_some_and(|range| !String::from(range.to_string()).trim().is_empty())
+}
+
+/// Declares an inline node holding text under a single html tag, with the block parsing
+/// it, the shortcut toggling it and its toolbar item.
+macro_rules! text_mark {
+    (
+        $(#[$doc:meta])*
+        $name:ident, $block:ident, $tag:literal,
+        accepts: |$node:ident, $tag_name:ident| $accepts:expr,
+        key: $key:literal => $command:expr,
+        item: $item:literal, $icon:literal => $selectors:literal
+    ) => {
+        $(#[$doc])*
+        #[derive(Clone, Serialize, Deserialize, JsonSchema, Debug)]
+        pub struct $name {
+            pub text: String,
+        }
+
+        impl crate::nodes::Node for $name {
+            fn render(&self) -> Dom {
+                let element = Dom::element($tag);
+                element.append_child(&html! { <>{&self.text}</> });
+                element
+            }
+        }
+
+        impl MarkElement for $name {
+            const TAG: &'static str = $tag;
+            const SELECTORS: &'static str = $selectors;
+        }
+
+        pub struct $block;
+
+        impl Block for $block {
+            type Node = EditorNode;
+            type Input = web_sys::Node;
+            type State = EditorState;
+
+            fn hook(&self, editor: &mut Editor<Self::Node, Self::State, Self::Input>) {
+                editor.bind_key($key, |state| {
+                    let handled = has_selected_text(state);
+                    if handled {
+                        state.execute($command);
+                    }
+                    handled
+                });
+                editor.add_toolbar_item(
+                    ToolbarItem::new($item, stringify!($name), $command)
+                        .with_icon($icon)
+                        .with_group("marks")
+                        .with_active(ToggleMark::<$name>::is_active),
+                );
+            }
+
+            fn accepts(&self, $node: &Node) -> bool {
+                let Some(element) = $node.dyn_ref::<Element>() else {
+                    return false;
+                };
+                let $tag_name = element.tag_name();
+                $accepts
+            }
+
+            fn parse(
+                &self,
+                _editor: &Editor<Self::Node, EditorState, web_sys::Node>,
+                node: &Node,
+            ) -> EditorNode {
+                EditorNode::$name($name {
+                    text: node.text_content().unwrap_or_default(),
+                })
+            }
+        }
+    };
+}
+
+text_mark!(
+    /// Underlined text, parsed from `<u>`, `<ins>` and underlined spans.
+    Underline, UnderlineBlock, "u",
+    accepts: |node, tag| {
+        tag == "U"
+            || tag == "INS"
+            || span_style(node, "text-decoration-line").is_some_and(|v| v.contains("underline"))
+    },
+    key: "Mod-u" => ToggleMark::<Underline>::new(),
+    item: "underline", "U" => "u, ins"
+);
+
+text_mark!(
+    /// Struck through text, parsed from `<s>`, `<strike>`, `<del>` and struck through spans.
+    Strike, StrikeBlock, "s",
+    accepts: |node, tag| {
+        matches!(tag.as_str(), "S" | "STRIKE" | "DEL")
+            || span_style(node, "text-decoration-line")
+                .is_some_and(|v| v.contains("line-through"))
+    },
+    key: "Mod-Shift-s" => ToggleMark::<Strike>::new(),
+    item: "strike", "S" => "s, strike, del"
+);
+
+text_mark!(
+    /// Subscript text, parsed from `<sub>` and spans aligned to `sub`.
+    Subscript, SubscriptBlock, "sub",
+    accepts: |node, tag| {
+        tag == "SUB" || span_style(node, "vertical-align").is_some_and(|v| v == "sub")
+    },
+    key: "Mod-," => ToggleSubscript,
+    item: "subscript", "x₂" => "sub"
+);
+
+text_mark!(
+    /// Superscript text, parsed from `<sup>` and spans aligned to `super`.
+    Superscript, SuperscriptBlock, "sup",
+    accepts: |node, tag| {
+        tag == "SUP" || span_style(node, "vertical-align").is_some_and(|v| v == "super")
+    },
+    key: "Mod-." => ToggleSuperscript,
+    item: "superscript", "x²" => "sup"
+);
+
+pub struct HighlightBlock;
+
+impl Block for HighlightBlock {
+    type Node = EditorNode;
+    type Input = web_sys::Node;
+    type State = EditorState;
+
+    fn hook(&self, editor: &mut Editor<Self::Node, Self::State, Self::Input>) {
+        editor.bind_key("Mod-Shift-h", |state| {
+            let handled = has_selected_text(state);
+            if handled {
+                state.execute(ToggleHighlight::default());
+            }
+            handled
+        });
+        editor.add_toolbar_item(
+            ToolbarItem::new("highlight", "Highlight", ToggleHighlight::default())
+                .with_icon("Mark")
+                .with_group("marks")
+                .active_inside("mark"),
+        );
+    }
+
+    fn accepts(&self, node: &Node) -> bool {
+        if let Some(element) = node.dyn_ref::<Element>() {
+            element.tag_name() == "MARK"
+                || span_style(node, "background-color").is_some_and(|v| v != "transparent")
+        } else {
+            false
+        }
+    }
+
+    fn parse(
+        &self,
+        _editor: &Editor<Self::Node, EditorState, web_sys::Node>,
+        node: &Node,
+    ) -> EditorNode {
+        let element: &Element = node.unchecked_ref();
+        let color = element
+            .get_attribute("data-color")
+            .or_else(|| span_style(node, "background-color"));
+        EditorNode::Highlight(Highlight {
+            text: node.text_content().unwrap_or_default(),
+            color: color.and_then(|color| sanitize_color(&color)),
+        })
+    }
+}
+
+/// Highlighted text, in the reader's default highlight colour unless it has one.
+#[derive(Clone, Serialize, Deserialize, JsonSchema, Debug)]
+pub struct Highlight {
+    pub text: String,
+    /// The css background colour.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+}
+
+impl crate::nodes::Node for Highlight {
+    fn render(&self) -> Dom {
+        let mark = Dom::element("mark");
+        if let Some(color) = self.color.as_deref().and_then(sanitize_color) {
+            mark.set_attribute("data-color", &color);
+            mark.set_attribute("style", &format!("background-color: {color}"));
+        }
+        mark.append_child(&html! { <>{&self.text}</> });
+        mark
+    }
+}
+
+/// Parses spans and `<font>` elements with a colour. Colours are set with
+/// [`SetTextColor`](crate::SetTextColor) and have no shortcut.
+pub struct TextColorBlock;
+
+impl Block for TextColorBlock {
+    type Node = EditorNode;
+    type Input = web_sys::Node;
+    type State = EditorState;
+
+    fn accepts(&self, node: &Node) -> bool {
+        if let Some(element) = node.dyn_ref::<Element>() {
+            (element.tag_name() == "FONT" && element.has_attribute("color"))
+                || span_style(node, "color").is_some()
+        } else {
+            false
+        }
+    }
+
+    fn parse(
+        &self,
+        _editor: &Editor<Self::Node, EditorState, web_sys::Node>,
+        node: &Node,
+    ) -> EditorNode {
+        let element: &Element = node.unchecked_ref();
+        let text = node.text_content().unwrap_or_default();
+        let color = span_style(node, "color")
+            .or_else(|| element.get_attribute("color"))
+            .and_then(|color| sanitize_color(&color));
+        match color {
+            Some(color) => EditorNode::TextColor(TextColor { text, color }),
+            None => EditorNode::Text(TextNode { text }),
+        }
+    }
+}
+
+/// Text in a colour of its own.
+#[derive(Clone, Serialize, Deserialize, JsonSchema, Debug)]
+pub struct TextColor {
+    pub text: String,
+    /// The css colour.
+    pub color: String,
+}
+
+impl crate::nodes::Node for TextColor {
+    fn render(&self) -> Dom {
+        let span = Dom::element("span");
+        if let Some(color) = sanitize_color(&self.color) {
+            span.set_attribute("style", &format!("color: {color}"));
+        }
+        span.append_child(&html! { <>{&self.text}</> });
+        span
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sanitize_color() {
+        assert_eq!(sanitize_color(" #FFAA00 "), Some("#FFAA00".to_owned()));
+        assert_eq!(sanitize_color("rgb(255, 0, 0)"), Some("rgb(255, 0, 0)".to_owned()));
+        assert_eq!(sanitize_color("rebeccapurple"), Some("rebeccapurple".to_owned()));
+        assert_eq!(sanitize_color("red; background: url(x)"), None);
+        assert_eq!(sanitize_color("#12345"), None);
+        assert_eq!(sanitize_color("expression(alert(1))"), None);
+    }
+}
@@ -1,19 +1,45 @@
+use std::{cell::RefCell, rc::Rc};
+
 use edita_core::Command;
 use hirola::{
     dom::node_ref::NodeRef,
     prelude::{Callback, GenericNode},
     signal::Mutable,
 };
+use wasm_bindgen::JsCast;
+use web_sys::{window, ClipboardEvent, DragEvent, Element, Event, FileList, KeyboardEvent, Range};
+
+use crate::bubble::BubbleToolbar;
+use crate::nodes::{Node, UpdateHeadingAnchors};
+use crate::suggestion::{SlashMenu, Suggestion, SuggestionProvider, Suggestions};
+use crate::toolbar::{ToolbarItem, ToolbarItems};
+use crate::upload::{self, Uploader};
+
+/// A key handler, returning whether it handled the key press.
+pub type KeyBinding = Rc<dyn Fn(&EditorState) -> bool>;
 
-use crate::nodes::Node;
+/// A handler run after every change typed into the editor.
+pub type InputRule = Rc<dyn Fn(&EditorState)>;
+
+/// A handler run on clicks inside the editor, given the clicked element.
+pub type ClickHandler = Rc<dyn Fn(&EditorState, &Element)>;
+
+/// A handler run on text pasted into the editor, returning whether it handled the paste.
+pub type PasteHandler = Rc<dyn Fn(&EditorState, &str) -> bool>;
 
 // Editor state, representing the current state of the editor
 #[derive(Clone)]
 pub struct EditorState {
     node: NodeRef,
     // current_selection: Mutable<Option<Selection>>,
-    // toolbar: HashMap<String, Box<dyn Command<Self>>>,
-    // shortcuts: HashMap<String, String>,
+    shortcuts: Rc<RefCell<Vec<(String, KeyBinding)>>>,
+    input_rules: Rc<RefCell<Vec<InputRule>>>,
+    click_handlers: Rc<RefCell<Vec<ClickHandler>>>,
+    paste_handlers: Rc<RefCell<Vec<PasteHandler>>>,
+    uploader: Rc<RefCell<Option<Rc<dyn Uploader>>>>,
+    suggestions: Suggestions,
+    toolbar: ToolbarItems,
+    bubble: BubbleToolbar,
     pub(crate) notify: Mutable<()>,
 }
 
@@ -22,13 +48,35 @@ impl EditorState {
         EditorState {
             node,
             // current_selection: Mutable::new(None),
-            // toolbar: HashMap::new(),
-            // shortcuts: HashMap::new(),
+            shortcuts: Default::default(),
+            input_rules: Default::default(),
+            click_handlers: Default::default(),
+            paste_handlers: Default::default(),
+            uploader: Default::default(),
+            suggestions: Default::default(),
+            toolbar: Default::default(),
+            bubble: Default::default(),
             notify: Mutable::new(()),
         }
     }
     pub fn add_node<N: Node>(&self, node: N) {
         self.node.get().append_render(node.render());
+        self.execute(UpdateHeadingAnchors);
+    }
+
+    /// Inserts a node after the block at the cursor, or at the end of the editor.
+    pub fn insert_block<N: Node>(&self, node: N) {
+        self.insert_element(&node.render().inner_element());
+    }
+
+    /// Inserts a rendered block after the block at the cursor, or at the end of the editor.
+    pub(crate) fn insert_element(&self, element: &web_sys::Node) {
+        let root = self.node.get().inner_element();
+        let next = self
+            .block_at_cursor()
+            .and_then(|block| block.next_sibling());
+        root.insert_before(element, next.as_ref()).unwrap();
+        self.execute(UpdateHeadingAnchors);
     }
 
     pub fn node(&self) -> &NodeRef {
@@ -43,6 +91,338 @@ impl EditorState {
         self.notify.replace(())
     }
 
+    /// The current selection range, if it lies inside the editor.
+    pub fn selection_range(&self) -> Option<Range> {
+        let selection = window()?.get_selection().ok()??;
+        if selection.range_count() == 0 {
+            return None;
+        }
+        let range = selection.get_range_at(0).ok()?;
+        let ancestor = range.common_ancestor_container().ok()?;
+        self.node
+            .get()
+            .inner_element()
+            .contains(Some(&ancestor))
+            .then_some(range)
+    }
+
+    /// The closest element matching `selectors` around the start of the selection.
+    pub fn closest_at_cursor(&self, selectors: &str) -> Option<Element> {
+        let container = self.selection_range()?.start_container().ok()?;
+        let element = match container.dyn_into::<Element>() {
+            Ok(element) => element,
+            Err(node) => node.parent_element()?,
+        };
+        let closest = element.closest(selectors).ok()??;
+        self.node
+            .get()
+            .inner_element()
+            .contains(Some(&closest))
+            .then_some(closest)
+    }
+
+    /// The top level node of the editor holding the cursor.
+    pub fn block_at_cursor(&self) -> Option<web_sys::Node> {
+        self.top_level(self.selection_range()?.start_container().ok()?)
+    }
+
+    /// The top level nodes of the editor the selection runs through, in document order.
+    pub fn selected_blocks(&self) -> Vec<web_sys::Node> {
+        let Some(range) = self.selection_range() else {
+            return vec![];
+        };
+        let first = range.start_container().ok().and_then(|n| self.top_level(n));
+        let last = range.end_container().ok().and_then(|n| self.top_level(n));
+        let (Some(mut node), Some(last)) = (first, last) else {
+            return vec![];
+        };
+        let mut blocks = vec![node.clone()];
+        while node != last {
+            let Some(next) = node.next_sibling() else {
+                break;
+            };
+            blocks.push(next.clone());
+            node = next;
+        }
+        blocks
+    }
+
+    fn top_level(&self, mut node: web_sys::Node) -> Option<web_sys::Node> {
+        let root = self.node.get().inner_element();
+        while let Some(parent) = node.parent_node() {
+            if parent == root {
+                return Some(node);
+            }
+            node = parent;
+        }
+        None
+    }
+
+    /// Replaces the selection with `text`, leaving the cursor after it.
+    pub fn insert_text(&self, text: &str) {
+        let (Some(range), Some(document)) = (
+            self.selection_range(),
+            window().and_then(|window| window.document()),
+        ) else {
+            return;
+        };
+        range.delete_contents().unwrap();
+        range.insert_node(&document.create_text_node(text)).unwrap();
+        range.collapse_with_to_start(false);
+        self.select_range(&range);
+    }
+
+    /// Binds a key, eg. `"Tab"`, `"Shift-Enter"` or `"Mod-b"`, to a handler.
+    ///
+    /// Bindings added last are tried first, until one of them handles the key.
+    pub fn bind_key(&self, key: &str, handler: impl Fn(&EditorState) -> bool + 'static) {
+        self.shortcuts
+            .borrow_mut()
+            .push((key.to_owned(), Rc::new(handler)));
+    }
+
+    /// Runs the bindings of a key press, preventing its default action once handled.
+    ///
+    /// Meant to run from the editor's `keydown` event.
+    pub fn handle_keydown(&self, event: &KeyboardEvent) {
+        let mut key = String::new();
+        if event.ctrl_key() || event.meta_key() {
+            key.push_str("Mod-");
+        }
+        if event.alt_key() {
+            key.push_str("Alt-");
+        }
+        if event.shift_key() {
+            key.push_str("Shift-");
+        }
+        // Letters are bound in lower case whatever the shift state.
+        match event.key() {
+            name if name.chars().count() == 1 => key.push_str(&name.to_lowercase()),
+            name => key.push_str(&name),
+        }
+        if self.suggestions.handle_key(self, &key) {
+            event.prevent_default();
+            return;
+        }
+        let bindings: Vec<KeyBinding> = self
+            .shortcuts
+            .borrow()
+            .iter()
+            .rev()
+            .filter(|(bound, _)| *bound == key)
+            .map(|(_, binding)| binding.clone())
+            .collect();
+        if bindings.into_iter().any(|binding| binding(self)) {
+            event.prevent_default();
+        }
+    }
+
+    /// Adds a rule run on every input, eg. to expand typed shortcuts.
+    pub fn add_input_rule(&self, rule: impl Fn(&EditorState) + 'static) {
+        self.input_rules.borrow_mut().push(Rc::new(rule));
+    }
+
+    /// Runs the input rules, then opens or updates the suggestions for the query typed
+    /// before the cursor.
+    ///
+    /// Meant to run from the editor's `input` event.
+    pub fn handle_input(&self) {
+        let rules: Vec<InputRule> = self.input_rules.borrow().clone();
+        for rule in rules {
+            rule(self);
+        }
+        self.suggestions.update(self);
+    }
+
+    /// Offers the suggestions of `provider` for the words typed after `trigger`, eg. `@`
+    /// for mentions, in the [`suggestion_popup`](crate::suggestion_popup).
+    ///
+    /// A provider added later for the same trigger takes the place of the earlier one.
+    pub fn add_suggestions(&self, trigger: char, provider: impl SuggestionProvider + 'static) {
+        self.suggestions.add(trigger, Rc::new(provider));
+    }
+
+    /// Adds a block or command to the [`SlashMenu`], usually from the [`Block::hook`] of
+    /// the block it inserts.
+    ///
+    /// [`Block::hook`]: edita_core::Block::hook
+    pub fn add_slash_item(&self, item: Suggestion) {
+        self.suggestions.slash_menu().add(item);
+    }
+
+    /// The menu of the items added with [`add_slash_item`](Self::add_slash_item), to add
+    /// as the provider of a trigger, usually `/`.
+    pub fn slash_menu(&self) -> SlashMenu {
+        self.suggestions.slash_menu().clone()
+    }
+
+    pub(crate) fn suggestions(&self) -> &Suggestions {
+        &self.suggestions
+    }
+
+    /// Adds an item to the [`Toolbar`](crate::Toolbar), in place of any item of the same
+    /// name.
+    pub fn add_toolbar_item(&self, item: ToolbarItem) {
+        self.toolbar.add(item);
+    }
+
+    /// Removes the toolbar item called `name`.
+    pub fn remove_toolbar_item(&self, name: &str) {
+        self.toolbar.remove(name);
+    }
+
+    /// Moves the toolbar items called `names` first, in that order, the others following
+    /// in their current order.
+    pub fn reorder_toolbar(&self, names: &[&str]) {
+        self.toolbar.reorder(names);
+    }
+
+    /// The toolbar items, in the order they are shown.
+    pub fn toolbar_items(&self) -> Vec<ToolbarItem> {
+        self.toolbar.items()
+    }
+
+    pub(crate) fn toolbar(&self) -> &ToolbarItems {
+        &self.toolbar
+    }
+
+    /// Updates the pressed and enabled state of the toolbar items, and shows the bubble
+    /// toolbar over the selected text or hides it when nothing is selected.
+    ///
+    /// Meant to run from the document's `selectionchange` event.
+    pub fn handle_selection_change(&self) {
+        self.toolbar.update(self);
+        self.bubble.update(self);
+    }
+
+    pub(crate) fn bubble(&self) -> &BubbleToolbar {
+        &self.bubble
+    }
+
+    /// Adds a handler run on every click inside the editor.
+    pub fn on_click(&self, handler: impl Fn(&EditorState, &Element) + 'static) {
+        self.click_handlers.borrow_mut().push(Rc::new(handler));
+    }
+
+    /// Runs the click handlers with the clicked element.
+    ///
+    /// Meant to run from the editor's `click` event.
+    pub fn handle_click(&self, event: &Event) {
+        // The cursor may have moved away from the query.
+        self.suggestions.close();
+        let Some(target) = event.target().and_then(|t| t.dyn_into::<Element>().ok()) else {
+            return;
+        };
+        let handlers: Vec<ClickHandler> = self.click_handlers.borrow().clone();
+        for handler in handlers {
+            handler(self, &target);
+        }
+    }
+
+    /// Sets the uploader storing the image files pasted or dropped into the editor.
+    ///
+    /// Without one, files are left to the browser.
+    pub fn set_uploader(&self, uploader: impl Uploader + 'static) {
+        *self.uploader.borrow_mut() = Some(Rc::new(uploader));
+    }
+
+    /// Uploads the image files of `files`, showing each as a pending image after the
+    /// block at the cursor until its upload completes.
+    ///
+    /// Returns whether there were image files to upload.
+    pub fn upload_files(&self, files: &FileList) -> bool {
+        let Some(uploader) = self.uploader.borrow().clone() else {
+            return false;
+        };
+        let images = upload::image_files(files);
+        if images.is_empty() {
+            return false;
+        }
+        upload::upload_files(self, uploader, images);
+        true
+    }
+
+    /// Adds a handler run on text pasted into the editor, the first one handling it
+    /// taking the place of the browser's paste.
+    pub fn on_paste(&self, handler: impl Fn(&EditorState, &str) -> bool + 'static) {
+        self.paste_handlers.borrow_mut().push(Rc::new(handler));
+    }
+
+    /// Uploads the image files pasted into the editor, or runs the paste handlers with the
+    /// pasted text.
+    ///
+    /// Meant to run from the editor's `paste` event.
+    pub fn handle_paste(&self, event: &Event) {
+        let Some(data) = event
+            .dyn_ref::<ClipboardEvent>()
+            .and_then(ClipboardEvent::clipboard_data)
+        else {
+            return;
+        };
+        if data.files().is_some_and(|files| self.upload_files(&files)) {
+            event.prevent_default();
+            return;
+        }
+        let text = data.get_data("text/plain").unwrap_or_default();
+        if text.is_empty() {
+            return;
+        }
+        let handlers: Vec<PasteHandler> = self.paste_handlers.borrow().clone();
+        if handlers.iter().any(|handler| handler(self, &text)) {
+            event.prevent_default();
+        }
+    }
+
+    /// Uploads the image files dropped into the editor.
+    ///
+    /// Meant to run from the editor's `drop` event, with [`EditorState::handle_drag_over`]
+    /// on its `dragover` event.
+    pub fn handle_drop(&self, event: &Event) {
+        let files = event
+            .dyn_ref::<DragEvent>()
+            .and_then(DragEvent::data_transfer)
+            .and_then(|data| data.files());
+        if files.is_some_and(|files| self.upload_files(&files)) {
+            event.prevent_default();
+        }
+    }
+
+    /// Lets files be dropped into the editor when there is an uploader.
+    pub fn handle_drag_over(&self, event: &Event) {
+        if self.uploader.borrow().is_some() {
+            event.prevent_default();
+        }
+    }
+
+    /// Replaces the document selection with `range`.
+    pub fn select_range(&self, range: &Range) {
+        if let Some(selection) = window().and_then(|window| window.get_selection().ok().flatten()) {
+            selection.remove_all_ranges().unwrap();
+            selection.add_range(range).unwrap();
+        }
+    }
+
+    /// Runs a change that moves the nodes around the cursor, then puts the caret back
+    /// where it was.
+    pub fn keep_caret(&self, change: impl FnOnce()) {
+        let caret = self
+            .selection_range()
+            .and_then(|range| Some((range.start_container().ok()?, range.start_offset().ok()?)));
+        change();
+        let Some((container, offset)) = caret else {
+            return;
+        };
+        let range = window()
+            .unwrap()
+            .document()
+            .unwrap()
+            .create_range()
+            .unwrap();
+        if range.set_start(&container, offset).is_ok() {
+            range.collapse_with_to_start(true);
+            self.select_range(&range);
+        }
+    }
 }
 
 impl Callback<web_sys::Event> for EditorState {}
@@ -0,0 +1,458 @@
+//! Suggestions offered while typing after a trigger character, eg. `@` for mentions or
+//! `/` for the slash menu.
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use edita_core::Command;
+use hirola::dom::Dom;
+use hirola::prelude::*;
+use hirola::signal::SignalExt;
+use wasm_bindgen::JsCast;
+use web_sys::{window, Element};
+
+use crate::nodes::{EditorNode, Node, Paragraph};
+use crate::state::EditorState;
+
+/// The most suggestions the built-in providers offer at once.
+pub(crate) const MAX_SUGGESTIONS: usize = 8;
+
+/// How wide the popup is expected to be, to keep it inside the window.
+const POPUP_WIDTH: f64 = 240.0;
+
+/// The `style` of the popup while it is closed.
+pub(crate) const HIDDEN: &str = "display: none";
+
+/// The elements of a block the cursor can be put in.
+const TEXT_BLOCKS: &str = "p, h1, h2, h3, h4, h5, h6, li, td, th, pre, summary";
+
+/// An item offered by a [`SuggestionProvider`].
+#[derive(Clone)]
+pub struct Suggestion {
+    pub label: String,
+    /// A second line shown under the label, eg. a user's email address.
+    pub detail: String,
+    pub action: SuggestionAction,
+}
+
+/// What choosing a [`Suggestion`] does with the trigger and query.
+#[derive(Clone)]
+pub enum SuggestionAction {
+    /// Replaces them with a node, eg. a [`Mention`](crate::Mention).
+    ///
+    /// Inline nodes are followed by a space. Blocks take the place of the block holding the
+    /// query when nothing else is left in it, or go after it.
+    Insert(EditorNode),
+    /// Removes them, then runs a command at the cursor.
+    Run(Rc<dyn Fn(&EditorState)>),
+}
+
+impl Suggestion {
+    /// A suggestion inserting `node`, without detail.
+    pub fn new(label: &str, node: EditorNode) -> Self {
+        Suggestion {
+            label: label.to_owned(),
+            detail: String::new(),
+            action: SuggestionAction::Insert(node),
+        }
+    }
+
+    /// A suggestion running `command`, without detail.
+    pub fn command(label: &str, command: impl Command<EditorState> + 'static) -> Self {
+        Suggestion {
+            label: label.to_owned(),
+            detail: String::new(),
+            action: SuggestionAction::Run(Rc::new(move |state| {
+                command.execute(&mut state.clone())
+            })),
+        }
+    }
+
+    pub fn with_detail(mut self, detail: &str) -> Self {
+        self.detail = detail.to_owned();
+        self
+    }
+}
+
+/// Offers the items matching the query typed after a trigger character.
+///
+/// Providers are registered with [`EditorState::add_suggestions`].
+pub trait SuggestionProvider {
+    /// The items to offer for `query`, which the popup lists in full, scrolling through
+    /// long lists.
+    fn suggest(&self, query: &str) -> Vec<Suggestion>;
+
+    /// Whether the trigger only opens suggestions when it is alone in its block with the
+    /// query, as for the [`SlashMenu`].
+    fn needs_empty_block(&self) -> bool {
+        false
+    }
+}
+
+impl<F> SuggestionProvider for F
+where
+    F: Fn(&str) -> Vec<Suggestion>,
+{
+    fn suggest(&self, query: &str) -> Vec<Suggestion> {
+        self(query)
+    }
+}
+
+/// The blocks and commands registered with [`EditorState::add_slash_item`], offered when
+/// `/` is typed in an empty block once added as a provider:
+///
+/// ```ignore
+/// state.add_suggestions('/', state.slash_menu());
+/// ```
+///
+/// Items whose label contains the query are suggested, in the order they were added.
+#[derive(Clone, Default)]
+pub struct SlashMenu {
+    items: Rc<RefCell<Vec<Suggestion>>>,
+}
+
+impl SlashMenu {
+    pub(crate) fn add(&self, item: Suggestion) {
+        self.items.borrow_mut().push(item);
+    }
+}
+
+impl SuggestionProvider for SlashMenu {
+    fn suggest(&self, query: &str) -> Vec<Suggestion> {
+        let query = query.to_lowercase();
+        self.items
+            .borrow()
+            .iter()
+            .filter(|item| item.label.to_lowercase().contains(&query))
+            .cloned()
+            .collect()
+    }
+
+    fn needs_empty_block(&self) -> bool {
+        true
+    }
+}
+
+/// Finds the query being typed at the end of `text` after one of `triggers`, returning
+/// the byte offset of the trigger, the trigger and the query.
+///
+/// The trigger must start a word, so that the `@` of an email address opens nothing, and
+/// the query ends at the first whitespace.
+pub fn trailing_query<'a>(text: &'a str, triggers: &[char]) -> Option<(usize, char, &'a str)> {
+    let (start, trigger) = text
+        .char_indices()
+        .rev()
+        .take_while(|(_, c)| !c.is_whitespace())
+        .find(|(_, c)| triggers.contains(c))?;
+    let before = text[..start].chars().last();
+    if before.is_some_and(|c| !c.is_whitespace() && !matches!(c, '(' | '[' | '"' | '\'')) {
+        return None;
+    }
+    Some((start, trigger, &text[start + trigger.len_utf8()..]))
+}
+
+/// The providers registered for each trigger character.
+type Providers = Rc<RefCell<Vec<(char, Rc<dyn SuggestionProvider>)>>>;
+
+/// The trigger and query being typed, as UTF-16 offsets into their text node.
+#[derive(Clone)]
+struct Query {
+    node: web_sys::Node,
+    start: u32,
+    end: u32,
+}
+
+impl Query {
+    fn range(&self) -> Option<web_sys::Range> {
+        let range = window()?.document()?.create_range().ok()?;
+        range.set_start(&self.node, self.start).ok()?;
+        range.set_end(&self.node, self.end).ok()?;
+        Some(range)
+    }
+}
+
+/// The registered providers and the popup listing the current suggestions.
+#[derive(Clone)]
+pub(crate) struct Suggestions {
+    providers: Providers,
+    slash_menu: SlashMenu,
+    query: Rc<RefCell<Option<Query>>>,
+    items: MutableVec<(usize, Suggestion)>,
+    selected: Mutable<usize>,
+    style: Mutable<String>,
+}
+
+impl Default for Suggestions {
+    fn default() -> Self {
+        Suggestions {
+            providers: Default::default(),
+            slash_menu: Default::default(),
+            query: Default::default(),
+            items: MutableVec::new(),
+            selected: Mutable::new(0),
+            style: Mutable::new(HIDDEN.to_owned()),
+        }
+    }
+}
+
+impl Suggestions {
+    pub(crate) fn add(&self, trigger: char, provider: Rc<dyn SuggestionProvider>) {
+        self.providers.borrow_mut().push((trigger, provider));
+    }
+
+    pub(crate) fn slash_menu(&self) -> &SlashMenu {
+        &self.slash_menu
+    }
+
+    /// Opens the popup on the query typed before the cursor, or closes it when there is
+    /// none or nothing matches it.
+    pub(crate) fn update(&self, state: &EditorState) {
+        match self.find(state) {
+            Some((query, items)) => {
+                self.style.set(popup_style(&query));
+                *self.query.borrow_mut() = Some(query);
+                self.items
+                    .lock_mut()
+                    .replace_cloned(items.into_iter().enumerate().collect());
+                self.selected.set(0);
+            }
+            None => self.close(),
+        }
+    }
+
+    fn find(&self, state: &EditorState) -> Option<(Query, Vec<Suggestion>)> {
+        let providers = self.providers.borrow().clone();
+        if providers.is_empty() {
+            return None;
+        }
+        let range = state.selection_range().filter(|range| range.collapsed())?;
+        let node = range.start_container().ok()?;
+        if node.node_type() != web_sys::Node::TEXT_NODE
+            || state.closest_at_cursor("a, code, pre").is_some()
+        {
+            return None;
+        }
+        let text = node.text_content().unwrap_or_default();
+        // DOM offsets count UTF-16 code units.
+        let end = range.start_offset().ok()?;
+        let before: Vec<u16> = text.encode_utf16().take(end as usize).collect();
+        let before = String::from_utf16_lossy(&before);
+        let triggers: Vec<char> = providers.iter().map(|(trigger, _)| *trigger).collect();
+        let (start, trigger, query) = trailing_query(&before, &triggers)?;
+        // The provider registered last for a trigger takes precedence.
+        let (_, provider) = providers.iter().rev().find(|(t, _)| *t == trigger)?;
+        if provider.needs_empty_block() {
+            // The innermost block, so that a slash in an empty list item or table cell
+            // still opens the menu.
+            let block = match state.closest_at_cursor(TEXT_BLOCKS) {
+                Some(block) => block.text_content(),
+                None => state.block_at_cursor()?.text_content(),
+            }
+            .unwrap_or_default();
+            if block.trim() != &before[start..] {
+                return None;
+            }
+        }
+        let items = provider.suggest(query);
+        if items.is_empty() {
+            return None;
+        }
+        let start = before[..start].encode_utf16().count() as u32;
+        Some((Query { node, start, end }, items))
+    }
+
+    pub(crate) fn is_open(&self) -> bool {
+        self.query.borrow().is_some()
+    }
+
+    pub(crate) fn close(&self) {
+        if self.query.borrow_mut().take().is_some() {
+            self.items.lock_mut().clear();
+            self.style.set(HIDDEN.to_owned());
+        }
+    }
+
+    /// Moves through the open popup with the arrow keys, chooses with `Enter` or `Tab`
+    /// and closes it with `Escape`, returning whether the key was used.
+    pub(crate) fn handle_key(&self, state: &EditorState, key: &str) -> bool {
+        let count = self.items.lock_ref().len();
+        if !self.is_open() || count == 0 {
+            return false;
+        }
+        let selected = self.selected.get();
+        match key {
+            "ArrowDown" => self.selected.set((selected + 1) % count),
+            "ArrowUp" => self.selected.set((selected + count - 1) % count),
+            "Enter" | "Tab" => self.choose(state, selected),
+            "Escape" => self.close(),
+            _ => return false,
+        }
+        true
+    }
+
+    /// Carries out the [`SuggestionAction`] of the suggestion at `index`.
+    pub(crate) fn choose(&self, state: &EditorState, index: usize) {
+        let item = self
+            .items
+            .lock_ref()
+            .iter()
+            .find(|(i, _)| *i == index)
+            .map(|(_, item)| item.clone());
+        let query = self.query.borrow().clone();
+        self.close();
+        let (Some(item), Some(range)) = (item, query.as_ref().and_then(Query::range)) else {
+            return;
+        };
+        range.delete_contents().unwrap();
+        state.select_range(&range);
+        match item.action {
+            SuggestionAction::Insert(node) if node.is_inline() => {
+                let space = window().unwrap().document().unwrap().create_text_node(" ");
+                range.insert_node(&space).unwrap();
+                range.insert_node(&node.render().inner_element()).unwrap();
+                range.set_start(&space, 1).unwrap();
+                range.collapse_with_to_start(true);
+                state.select_range(&range);
+            }
+            SuggestionAction::Insert(node) => insert_block(state, &node),
+            SuggestionAction::Run(command) => command(state),
+        }
+    }
+}
+
+/// Puts a block in place of the block at the cursor when that is empty, or after it, and
+/// moves the cursor into its first line of text, or into a new paragraph after blocks
+/// without text such as rules.
+fn insert_block(state: &EditorState, node: &EditorNode) {
+    let element: Element = node.render().inner_element().unchecked_into();
+    match state.block_at_cursor() {
+        Some(block) if block.text_content().unwrap_or_default().trim().is_empty() => {
+            let parent = block.parent_node().unwrap();
+            parent.replace_child(&element, &block).unwrap();
+        }
+        _ => state.insert_element(&element),
+    }
+    let mut text = match element.matches(TEXT_BLOCKS).unwrap() {
+        true => Some(element.clone()),
+        false => element.query_selector(TEXT_BLOCKS).unwrap(),
+    };
+    // The innermost one, eg. the paragraph of a task item rather than the item.
+    while let Some(inner) = text
+        .as_ref()
+        .and_then(|t| t.query_selector(TEXT_BLOCKS).unwrap())
+    {
+        text = Some(inner);
+    }
+    let text = text.unwrap_or_else(|| {
+        let paragraph: Element = Paragraph::default()
+            .render()
+            .inner_element()
+            .unchecked_into();
+        element.after_with_node_1(&paragraph).unwrap();
+        paragraph
+    });
+    let range = window()
+        .unwrap()
+        .document()
+        .unwrap()
+        .create_range()
+        .unwrap();
+    range.select_node_contents(&text).unwrap();
+    range.collapse_with_to_start(true);
+    state.select_range(&range);
+}
+
+/// Places the popup under the query, kept inside the width of the window.
+fn popup_style(query: &Query) -> String {
+    let Some(rect) = query.range().map(|range| range.get_bounding_client_rect()) else {
+        return HIDDEN.to_owned();
+    };
+    let width = window()
+        .and_then(|window| window.inner_width().ok())
+        .and_then(|width| width.as_f64())
+        .unwrap_or(f64::MAX);
+    let left = rect.left().min(width - POPUP_WIDTH).max(0.0);
+    format!(
+        "position: fixed; left: {left}px; top: {}px; min-width: {POPUP_WIDTH}px; \
+         max-height: 20em; overflow-y: auto",
+        rect.bottom() + 4.0
+    )
+}
+
+/// The popup listing the suggestions for the query being typed, under the query.
+///
+/// It opens as a query is typed after a trigger registered with
+/// [`EditorState::add_suggestions`]. The arrow keys move through it, `Enter` or `Tab`
+/// choose and `Escape` closes it. The selected item has `aria-selected="true"`.
+pub fn suggestion_popup(state: &EditorState) -> Dom {
+    use hirola::dom::effects::attr_on::*;
+    use hirola::dom::effects::attr_use::{attr_signal, Signal, UseEffect};
+    let suggestions = state.suggestions().clone();
+    let style = suggestions.style.signal_cloned();
+    let state = state.clone();
+    html! {
+        <ul class="suggestions" role="listbox" use:signal=attr_signal("style", style)>
+            {suggestions
+                .items
+                .signal_vec_cloned()
+                .map_render(move |(index, item)| {
+                    let state = state.clone();
+                    let selected = state
+                        .suggestions()
+                        .selected
+                        .signal()
+                        .map(move |selected| selected == index);
+                    html! {
+                        <li
+                            role="option"
+                            use:signal=attr_signal("aria-selected", selected)
+                            on:mouse-down=move |event: web_sys::Event| {
+                                // Keeps the focus, and the cursor, in the editor.
+                                event.prevent_default();
+                                state.suggestions().choose(&state, index);
+                            }
+                        >
+                            <span>{item.label.clone()}</span>
+                            <small>{item.detail.clone()}</small>
+                        </li>
+                    }
+                })}
+        </ul>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trailing_query() {
+        let triggers = ['@', ':'];
+        assert_eq!(trailing_query("hi @ad", &triggers), Some((3, '@', "ad")));
+        assert_eq!(trailing_query("@", &triggers), Some((0, '@', "")));
+        assert_eq!(trailing_query("(:tad", &triggers), Some((1, ':', "tad")));
+        assert_eq!(trailing_query("mail ada@a.io", &triggers), None);
+        assert_eq!(trailing_query("@ada lovelace", &triggers), None);
+        assert_eq!(trailing_query("at 10:30", &triggers), None);
+        assert_eq!(trailing_query("née @zoë", &triggers), Some((5, '@', "zoë")));
+    }
+
+    #[test]
+    fn test_slash_menu_filters_labels() {
+        let menu = SlashMenu::default();
+        for label in ["Heading 1", "Bullet list", "Numbered list"] {
+            menu.add(Suggestion::new(
+                label,
+                EditorNode::Paragraph(Default::default()),
+            ));
+        }
+        let labels = |query| -> Vec<String> {
+            menu.suggest(query)
+                .into_iter()
+                .map(|item| item.label)
+                .collect()
+        };
+        assert_eq!(labels("").len(), 3);
+        assert_eq!(labels("LIST"), ["Bullet list", "Numbered list"]);
+        assert!(labels("quote").is_empty());
+    }
+}
@@ -0,0 +1,68 @@
+//! Helpers for the tests that need a DOM, which only run in a browser.
+use hirola::dom::{node_ref::NodeRef, Dom};
+use hirola::prelude::NodeReference;
+use wasm_bindgen::JsCast;
+use web_sys::{window, Element, Node, Text};
+
+use crate::state::EditorState;
+
+wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+/// An editor holding `html`, attached to the document so that its content can be selected.
+pub(crate) fn editor(html: &str) -> EditorState {
+    let document = window().unwrap().document().unwrap();
+    let root = document.create_element("div").unwrap();
+    root.set_attribute("contenteditable", "true").unwrap();
+    root.set_inner_html(html);
+    document.body().unwrap().append_child(&root).unwrap();
+    let node = NodeRef::new();
+    node.set(Dom::new_from_node(&root));
+    EditorState::new(node)
+}
+
+/// The content of the editor as html.
+pub(crate) fn content(state: &EditorState) -> String {
+    let root: Element = state.node().get().inner_element().unchecked_into();
+    root.inner_html()
+}
+
+fn text_nodes(node: &Node, texts: &mut Vec<Text>) {
+    match node.dyn_ref::<Text>() {
+        Some(text) => texts.push(text.clone()),
+        None => {
+            let children = node.child_nodes();
+            for child in (0..children.length()).filter_map(|i| children.get(i)) {
+                text_nodes(&child, texts);
+            }
+        }
+    }
+}
+
+/// The text node holding `needle` and the offset of `needle` in it, the needle being
+/// ascii so that byte and UTF-16 offsets agree.
+fn find(state: &EditorState, needle: &str) -> (Text, u32) {
+    let mut texts = vec![];
+    text_nodes(&state.node().get().inner_element(), &mut texts);
+    texts
+        .into_iter()
+        .find_map(|text| {
+            let offset = text.data().find(needle)?;
+            Some((text, offset as u32))
+        })
+        .unwrap_or_else(|| panic!("no text node holds {needle:?}"))
+}
+
+/// Selects from the start of the text `from` to the end of the text `to`.
+pub(crate) fn select(state: &EditorState, from: &str, to: &str) {
+    let (start, start_offset) = find(state, from);
+    let (end, end_offset) = find(state, to);
+    let range = window()
+        .unwrap()
+        .document()
+        .unwrap()
+        .create_range()
+        .unwrap();
+    range.set_start(&start, start_offset).unwrap();
+    range.set_end(&end, end_offset + to.len() as u32).unwrap();
+    state.select_range(&range);
+}
@@ -0,0 +1,339 @@
+//! Toolbar buttons registered by blocks and the host app, and the toolbar rendering them.
+use std::rc::Rc;
+
+use edita_core::Command;
+use hirola::dom::Dom;
+use hirola::prelude::*;
+use hirola::signal::SignalExt;
+use hirola::signal_vec::SignalVecExt;
+
+use crate::state::EditorState;
+
+/// Tells whether a [`ToolbarItem`] is active or enabled at the current selection.
+pub type ToolbarCheck = Rc<dyn Fn(&EditorState) -> bool>;
+
+/// A command shown as a button of the [`Toolbar`].
+///
+/// Blocks register theirs from their [`Block::hook`](edita_core::Block::hook), in the
+/// `marks`, `blocks`, `insert` and `table` groups, and the host app adds, replaces, removes
+/// and reorders items with [`EditorState::add_toolbar_item`],
+/// [`EditorState::remove_toolbar_item`] and [`EditorState::reorder_toolbar`].
+#[derive(Clone)]
+pub struct ToolbarItem {
+    /// The name the item is referred to by, eg. `bold`.
+    pub name: String,
+    /// The accessible name of the button, also shown as its tooltip.
+    pub label: String,
+    /// The text shown on the button, eg. a glyph or an icon font ligature, the label being
+    /// shown when it is empty.
+    pub icon: String,
+    /// The group the button is shown in, items of a group being rendered together.
+    pub group: String,
+    command: Rc<dyn Fn(&EditorState)>,
+    active: Option<ToolbarCheck>,
+    enabled: Option<ToolbarCheck>,
+    is_active: Mutable<bool>,
+    is_enabled: Mutable<bool>,
+}
+
+impl ToolbarItem {
+    /// An item running `command`, always enabled and never active.
+    pub fn new(name: &str, label: &str, command: impl Command<EditorState> + 'static) -> Self {
+        ToolbarItem {
+            name: name.to_owned(),
+            label: label.to_owned(),
+            icon: String::new(),
+            group: String::new(),
+            command: Rc::new(move |state| command.execute(&mut state.clone())),
+            active: None,
+            enabled: None,
+            is_active: Mutable::new(false),
+            is_enabled: Mutable::new(true),
+        }
+    }
+
+    pub fn with_icon(mut self, icon: &str) -> Self {
+        self.icon = icon.to_owned();
+        self
+    }
+
+    pub fn with_group(mut self, group: &str) -> Self {
+        self.group = group.to_owned();
+        self
+    }
+
+    /// Shows the button as pressed while `active` holds, eg. while the selection is bold.
+    pub fn with_active(mut self, active: impl Fn(&EditorState) -> bool + 'static) -> Self {
+        self.active = Some(Rc::new(active));
+        self
+    }
+
+    /// Shows the button as pressed while the selection starts inside an element matching
+    /// `selectors`.
+    pub fn active_inside(self, selectors: &str) -> Self {
+        let selectors = selectors.to_owned();
+        self.with_active(move |state| state.closest_at_cursor(&selectors).is_some())
+    }
+
+    /// Only lets the button be pressed while `enabled` holds, eg. inside a table.
+    pub fn with_enabled(mut self, enabled: impl Fn(&EditorState) -> bool + 'static) -> Self {
+        self.enabled = Some(Rc::new(enabled));
+        self
+    }
+
+    fn update(&self, state: &EditorState) {
+        let check = |check: &Option<ToolbarCheck>, default| match check {
+            Some(check) => check(state),
+            None => default,
+        };
+        self.is_active.set_neq(check(&self.active, false));
+        self.is_enabled.set_neq(check(&self.enabled, true));
+    }
+
+    fn press(&self, state: &EditorState) {
+        if self.is_enabled.get() {
+            (self.command)(state);
+            state.toolbar().update(state);
+        }
+    }
+}
+
+/// The registered toolbar items, in the order they are shown.
+#[derive(Clone, Default)]
+pub(crate) struct ToolbarItems {
+    items: MutableVec<ToolbarItem>,
+}
+
+impl ToolbarItems {
+    pub(crate) fn add(&self, item: ToolbarItem) {
+        let mut items = self.items.lock_mut();
+        match items.iter().position(|other| other.name == item.name) {
+            Some(index) => items.set_cloned(index, item),
+            None => items.push_cloned(item),
+        }
+    }
+
+    pub(crate) fn remove(&self, name: &str) {
+        self.items.lock_mut().retain(|item| item.name != name);
+    }
+
+    pub(crate) fn reorder(&self, names: &[&str]) {
+        let mut items = self.items.lock_mut();
+        let mut sorted = items.to_vec();
+        // The sort is stable, so the unnamed items keep their order.
+        sorted.sort_by_key(|item| {
+            names
+                .iter()
+                .position(|name| *name == item.name)
+                .unwrap_or(names.len())
+        });
+        items.replace_cloned(sorted);
+    }
+
+    pub(crate) fn items(&self) -> Vec<ToolbarItem> {
+        self.items.lock_ref().to_vec()
+    }
+
+    /// Updates the pressed and enabled state of every item.
+    pub(crate) fn update(&self, state: &EditorState) {
+        for item in self.items.lock_ref().iter() {
+            item.update(state);
+        }
+    }
+}
+
+/// Renders the registered [`ToolbarItem`]s, or those of some groups, as buttons.
+///
+/// Groups are rendered as `role="group"` elements in the order of their first item. Buttons
+/// have `aria-pressed` and `aria-disabled` following the item's state, which is updated by
+/// [`EditorState::handle_selection_change`]. The toolbar follows the items as they are
+/// added, removed and reordered after it is rendered.
+///
+/// ```ignore
+/// html! { <div>{Toolbar::new(&state).with_groups(&["marks", "blocks"])}</div> }
+/// ```
+pub struct Toolbar {
+    state: EditorState,
+    groups: Option<Vec<String>>,
+}
+
+impl Toolbar {
+    pub fn new(state: &EditorState) -> Self {
+        Toolbar {
+            state: state.clone(),
+            groups: None,
+        }
+    }
+
+    /// Only shows the items of `groups`.
+    pub fn with_groups(mut self, groups: &[&str]) -> Self {
+        self.groups = Some(groups.iter().map(|group| group.to_string()).collect());
+        self
+    }
+
+    fn render_item(state: &EditorState, item: ToolbarItem) -> Dom {
+        use hirola::dom::effects::attr_on::*;
+        use hirola::dom::effects::attr_use::{attr_signal, Signal, UseEffect};
+        use hirola::prelude::DefaultAttrStr;
+        use hirola::prelude::DefaultAttributeEffect;
+        let state = state.clone();
+        let pressed = item.is_active.signal();
+        let disabled = item.is_enabled.signal().map(|enabled| !enabled);
+        let text = match item.icon.is_empty() {
+            true => item.label.clone(),
+            false => item.icon.clone(),
+        };
+        html! {
+            <button
+                type="button"
+                title=&item.label
+                aria-label=&item.label
+                data-name=&item.name
+                use:signal=attr_signal("aria-pressed", pressed)
+                use:signal=attr_signal("aria-disabled", disabled)
+                on:mouse-down=move |event: web_sys::Event| {
+                    // Keeps the selection the command applies to.
+                    event.prevent_default();
+                    item.press(&state);
+                }
+            >
+                {text}
+            </button>
+        }
+    }
+}
+
+type ToolbarGroups = Vec<(String, Vec<ToolbarItem>)>;
+
+/// Sorts the items of the shown groups by group, groups coming in the order of their first
+/// item.
+fn group_items(items: &[ToolbarItem], shown: Option<&[String]>) -> ToolbarGroups {
+    let mut groups: ToolbarGroups = vec![];
+    for item in items {
+        if !shown.is_none_or(|shown| shown.contains(&item.group)) {
+            continue;
+        }
+        match groups.iter_mut().find(|(group, _)| *group == item.group) {
+            Some((_, items)) => items.push(item.clone()),
+            None => groups.push((item.group.clone(), vec![item.clone()])),
+        }
+    }
+    groups
+}
+
+impl Render<Dom> for Toolbar {
+    fn render_into(self: Box<Self>, parent: &Dom) -> Result<(), Error> {
+        use hirola::prelude::DefaultAttrStr;
+        use hirola::prelude::DefaultAttributeEffect;
+        let Toolbar {
+            state,
+            groups: shown,
+        } = *self;
+        let groups: MutableVec<(String, Vec<ToolbarItem>)> = MutableVec::new();
+        let regroup = state
+            .toolbar()
+            .items
+            .signal_vec_cloned()
+            .to_signal_cloned()
+            .for_each({
+                let groups = groups.clone();
+                move |items| {
+                    let items = group_items(&items, shown.as_deref());
+                    groups.lock_mut().replace_cloned(items);
+                    async {}
+                }
+            });
+        let toolbar = html! {
+            <div class="toolbar" role="toolbar">
+                {groups
+                    .signal_vec_cloned()
+                    .map_render(move |(group, items)| {
+                        html! {
+                            <div role="group" data-group=&group>
+                                {for item in items {
+                                    Toolbar::render_item(&state, item)
+                                }}
+                            </div>
+                        }
+                    })}
+            </div>
+        };
+        toolbar.effect(regroup);
+        parent.append_child(&toolbar);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::marks::ToggleMark;
+    use crate::nodes::{Bold, Italic};
+
+    #[test]
+    fn test_toolbar_items_order() {
+        let toolbar = ToolbarItems::default();
+        toolbar.add(ToolbarItem::new("bold", "Bold", ToggleMark::<Bold>::new()));
+        toolbar.add(ToolbarItem::new(
+            "italic",
+            "Italic",
+            ToggleMark::<Italic>::new(),
+        ));
+        toolbar.add(ToolbarItem::new("link", "Link", ToggleMark::<Bold>::new()));
+        toolbar.add(ToolbarItem::new("bold", "Strong", ToggleMark::<Bold>::new()).with_icon("S"));
+        let names = || -> Vec<String> { toolbar.items().into_iter().map(|i| i.name).collect() };
+        assert_eq!(names(), ["bold", "italic", "link"]);
+        assert_eq!(toolbar.items()[0].icon, "S");
+        toolbar.reorder(&["link", "italic"]);
+        assert_eq!(names(), ["link", "italic", "bold"]);
+        toolbar.remove("italic");
+        assert_eq!(names(), ["link", "bold"]);
+    }
+}
+
+#[cfg(all(test, target_arch = "wasm32"))]
+mod dom_tests {
+    use super::*;
+    use crate::commands::marks::ToggleMark;
+    use crate::nodes::{Bold, Italic};
+    use wasm_bindgen::JsCast;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    /// Waits for a timeout, letting the spawned signal effects run first.
+    async fn tick() {
+        let timeout = js_sys::Promise::new(&mut |resolve, _| {
+            let window = web_sys::window().unwrap();
+            window.set_timeout_with_callback(&resolve).unwrap();
+        });
+        wasm_bindgen_futures::JsFuture::from(timeout).await.unwrap();
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_toolbar_follows_items_after_render() {
+        let state = crate::testing::editor("<p>text</p>");
+        state.add_toolbar_item(ToolbarItem::new("bold", "Bold", ToggleMark::<Bold>::new()));
+        let parent = Dom::element("div");
+        Box::new(Toolbar::new(&state)).render_into(&parent).unwrap();
+        let names = || {
+            let root: web_sys::Element = parent.inner_element().unchecked_into();
+            let buttons = root.query_selector_all("button").unwrap();
+            (0..buttons.length())
+                .filter_map(|i| buttons.get(i)?.dyn_into::<web_sys::Element>().ok())
+                .filter_map(|button| button.get_attribute("data-name"))
+                .collect::<Vec<_>>()
+        };
+        tick().await;
+        assert_eq!(names(), ["bold"]);
+        state.add_toolbar_item(ToolbarItem::new(
+            "italic",
+            "Italic",
+            ToggleMark::<Italic>::new(),
+        ));
+        state.reorder_toolbar(&["italic", "bold"]);
+        tick().await;
+        assert_eq!(names(), ["italic", "bold"]);
+        state.remove_toolbar_item("italic");
+        tick().await;
+        assert_eq!(names(), ["bold"]);
+    }
+}
@@ -0,0 +1,238 @@
+//! Uploading of the image files pasted or dropped into the editor.
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use base64::Engine;
+use wasm_bindgen::{closure::Closure, JsCast};
+use web_sys::{File, FileList, FileReader, Url};
+
+use crate::nodes::{Image, Node};
+use crate::state::EditorState;
+
+/// The attribute marking an image whose file is still uploading.
+const PENDING: &str = "data-upload-id";
+
+/// Ids of the uploads in progress, unique for the page.
+static NEXT_UPLOAD: AtomicU32 = AtomicU32::new(0);
+
+/// A file handed to an [`Uploader`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UploadFile {
+    pub name: String,
+    /// The media type of the file, eg. `image/png`.
+    pub content_type: String,
+    pub bytes: Vec<u8>,
+}
+
+/// Errors reported by an [`Uploader`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum UploadError {
+    /// The uploader does not accept the file, eg. because it is too large.
+    Rejected(String),
+    /// Storing the file failed.
+    Failed(String),
+}
+
+impl fmt::Display for UploadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UploadError::Rejected(reason) => write!(f, "upload rejected: {reason}"),
+            UploadError::Failed(reason) => write!(f, "upload failed: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for UploadError {}
+
+/// Called once an upload completes, with the url of the stored file.
+pub type UploadDone = Box<dyn FnOnce(Result<String, UploadError>)>;
+
+/// Stores the image files pasted or dropped into the editor.
+///
+/// The editor shows the image from a local preview while the upload runs, and swaps in
+/// the url passed to `done` once it completes, or removes the image if it fails.
+pub trait Uploader {
+    fn upload(&self, file: UploadFile, done: UploadDone);
+}
+
+/// Inlines images in the document as base64 `data:` urls, completing right away.
+#[derive(Clone, Debug, Default)]
+pub struct DataUrlUploader {
+    /// Files larger than this many bytes are rejected.
+    pub max_bytes: Option<usize>,
+}
+
+impl Uploader for DataUrlUploader {
+    fn upload(&self, file: UploadFile, done: UploadDone) {
+        if let Some(max) = self.max_bytes.filter(|max| file.bytes.len() > *max) {
+            return done(Err(UploadError::Rejected(format!(
+                "{} is larger than {max} bytes",
+                file.name
+            ))));
+        }
+        done(Ok(format!(
+            "data:{};base64,{}",
+            file.content_type,
+            base64::engine::general_purpose::STANDARD.encode(&file.bytes)
+        )))
+    }
+}
+
+/// Keeps uploads in memory until they are completed by hand, to test how the editor
+/// behaves while uploads are pending.
+#[derive(Clone, Default)]
+pub struct MockUploader {
+    pending: Rc<RefCell<Vec<(UploadFile, UploadDone)>>>,
+    stored: Rc<RefCell<Vec<UploadFile>>>,
+}
+
+impl MockUploader {
+    /// The files waiting to be completed.
+    pub fn pending(&self) -> Vec<UploadFile> {
+        self.pending
+            .borrow()
+            .iter()
+            .map(|(file, _)| file.clone())
+            .collect()
+    }
+
+    /// The files stored by successful uploads.
+    pub fn stored(&self) -> Vec<UploadFile> {
+        self.stored.borrow().clone()
+    }
+
+    /// Stores every pending file under a `memory://` url.
+    pub fn complete_all(&self) {
+        let pending = std::mem::take(&mut *self.pending.borrow_mut());
+        for (file, done) in pending {
+            let url = format!("memory://{}/{}", self.stored.borrow().len(), file.name);
+            self.stored.borrow_mut().push(file);
+            done(Ok(url));
+        }
+    }
+
+    /// Fails every pending upload with `error`.
+    pub fn fail_all(&self, error: UploadError) {
+        let pending = std::mem::take(&mut *self.pending.borrow_mut());
+        for (_, done) in pending {
+            done(Err(error.clone()));
+        }
+    }
+}
+
+impl Uploader for MockUploader {
+    fn upload(&self, file: UploadFile, done: UploadDone) {
+        self.pending.borrow_mut().push((file, done));
+    }
+}
+
+/// The image files of a file list.
+pub(crate) fn image_files(files: &FileList) -> Vec<File> {
+    (0..files.length())
+        .filter_map(|i| files.get(i))
+        .filter(|file| file.type_().starts_with("image/"))
+        .collect()
+}
+
+/// Inserts a pending image for each file, previewed from the local file, then reads and
+/// uploads the file.
+pub(crate) fn upload_files(state: &EditorState, uploader: Rc<dyn Uploader>, files: Vec<File>) {
+    for file in files {
+        let id = NEXT_UPLOAD.fetch_add(1, Ordering::Relaxed).to_string();
+        let preview = Url::create_object_url_with_blob(&file).unwrap_or_default();
+        let image = Image::new(&preview, &file.name()).render().inner_element();
+        let element: &web_sys::Element = image.unchecked_ref();
+        element.set_attribute(PENDING, &id).unwrap();
+        state.insert_element(&image);
+
+        let reader = FileReader::new().unwrap();
+        let (state, uploader, read, read_file) = (
+            state.clone(),
+            uploader.clone(),
+            reader.clone(),
+            file.clone(),
+        );
+        let onload = Closure::once_into_js(move || {
+            let bytes = js_sys::Uint8Array::new(&read.result().unwrap()).to_vec();
+            let upload = UploadFile {
+                name: read_file.name(),
+                content_type: read_file.type_(),
+                bytes,
+            };
+            uploader.upload(
+                upload,
+                Box::new(move |result| {
+                    finish_upload(&state, &id, result);
+                    Url::revoke_object_url(&preview).ok();
+                }),
+            );
+        });
+        reader.set_onload(Some(onload.unchecked_ref()));
+        reader.read_as_array_buffer(&file).unwrap();
+    }
+}
+
+/// Points the pending image of an upload at its final url, or removes it if the upload
+/// failed.
+fn finish_upload(state: &EditorState, id: &str, result: Result<String, UploadError>) {
+    let root = state.node().get().inner_element();
+    let root: &web_sys::Element = root.unchecked_ref();
+    let Some(image) = root
+        .query_selector(&format!("img[{PENDING}=\"{id}\"]"))
+        .unwrap()
+    else {
+        return;
+    };
+    match result {
+        Ok(url) => {
+            image.set_attribute("src", &url).unwrap();
+            image.remove_attribute(PENDING).unwrap();
+        }
+        Err(error) => {
+            web_sys::console::warn_1(&error.to_string().into());
+            image.remove();
+        }
+    }
+    state.notify();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(name: &str, bytes: &[u8]) -> UploadFile {
+        UploadFile {
+            name: name.to_owned(),
+            content_type: "image/png".to_owned(),
+            bytes: bytes.to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_uploaders_complete() {
+        let urls = Rc::new(RefCell::new(vec![]));
+        let record = |urls: &Rc<RefCell<Vec<Result<String, UploadError>>>>| -> UploadDone {
+            let urls = urls.clone();
+            Box::new(move |result| urls.borrow_mut().push(result))
+        };
+        let data_urls = DataUrlUploader { max_bytes: Some(3) };
+        data_urls.upload(file("a.png", b"abc"), record(&urls));
+        data_urls.upload(file("b.png", b"abcd"), record(&urls));
+        assert_eq!(
+            urls.borrow()[0],
+            Ok("data:image/png;base64,YWJj".to_owned())
+        );
+        assert!(matches!(urls.borrow()[1], Err(UploadError::Rejected(_))));
+
+        let mock = MockUploader::default();
+        mock.upload(file("c.png", b"c"), record(&urls));
+        assert_eq!(urls.borrow().len(), 2);
+        assert_eq!(mock.pending().len(), 1);
+        mock.complete_all();
+        assert_eq!(urls.borrow()[2], Ok("memory://0/c.png".to_owned()));
+        assert_eq!(mock.stored(), vec![file("c.png", b"c")]);
+        assert!(mock.pending().is_empty());
+    }
+}
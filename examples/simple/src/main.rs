@@ -1,4 +1,3 @@
-use edita::core::Command;
 use edita::core::*;
 use edita::*;
 use hirola::dom::node_ref::NodeRef;
@@ -17,8 +16,20 @@ fn main() {
 fn app() -> Dom {
     let node = NodeRef::new();
     let state = EditorState::new(node.clone());
+    state.set_uploader(DataUrlUploader::default());
+    state.add_suggestions('@', |query: &str| {
+        ["ada", "grace", "linus"]
+            .into_iter()
+            .filter(|name| name.starts_with(&query.to_lowercase()))
+            .map(|name| Suggestion::new(name, EditorNode::Mention(Mention::new(name, name))))
+            .collect()
+    });
+    state.add_suggestions(':', EmojiSuggestions);
+    state.add_suggestions('/', state.slash_menu());
 
     let notifier = state.clone();
+    let paster = state.clone();
+    let selector = state.clone();
 
     let mut editor = Editor::new(state.clone());
     editor.add_block(HeaderBlock);
@@ -27,7 +38,34 @@ fn app() -> Dom {
     editor.add_block(InlineCodeBlock);
     editor.add_block(BoldBlock);
     editor.add_block(ItalicBlock);
+    editor.add_block(UnderlineBlock);
+    editor.add_block(StrikeBlock);
+    editor.add_block(SubscriptBlock);
+    editor.add_block(SuperscriptBlock);
+    editor.add_block(HighlightBlock);
+    editor.add_block(TextColorBlock);
     editor.add_block(ImageBlock);
+    editor.add_block(EmojiBlock);
+    editor.add_block(MentionBlock);
+    editor.add_block(CodeBlockBlock);
+    editor.add_block(HorizontalRuleBlock);
+    editor.add_block(HardBreakBlock);
+    editor.add_block(TableBlock);
+    editor.add_block(DetailsBlock);
+    editor.add_block(TaskListBlock);
+    editor.add_block(TaskItemBlock);
+    editor.add_block(BulletListBlock);
+    editor.add_block(OrderedListBlock);
+    editor.add_block(ListItemBlock);
+    editor.add_block(BlockQuoteBlock);
+    editor.add_block(LinkBlock);
+    state.add_toolbar_item(
+        ToolbarItem::new("number-headings", "Number headings", NumberHeadings(true))
+            .with_icon("1.2")
+            .with_group("blocks")
+            .active_inside("[data-numbered-headings]"),
+    );
+    state.reorder_toolbar(&["heading-1", "heading-2", "paragraph"]);
 
     let parent = node.clone();
 
@@ -35,20 +73,39 @@ fn app() -> Dom {
         let callback: Closure<dyn FnMut(_)> =
             Closure::new(move |_: js_sys::Array| notifier.notify());
         let observer = web_sys::MutationObserver::new(callback.as_ref().unchecked_ref()).unwrap();
-        let mut options = web_sys::MutationObserverInit::new();
-        options.attributes(true);
-        options.subtree(true);
-        options.character_data(true);
+        let options = web_sys::MutationObserverInit::new();
+        options.set_attributes(true);
+        options.set_subtree(true);
+        options.set_character_data(true);
         observer
             .observe_with_options(&parent.get().inner_element(), &options)
             .unwrap();
         callback.forget();
+        // Hirola has no paste event, so the listener is added by hand.
+        let paste: Closure<dyn FnMut(_)> =
+            Closure::new(move |event: web_sys::Event| paster.handle_paste(&event));
+        parent
+            .get()
+            .inner_element()
+            .add_event_listener_with_callback("paste", paste.as_ref().unchecked_ref())
+            .unwrap();
+        paste.forget();
+        let selection: Closure<dyn FnMut(_)> =
+            Closure::new(move |_: web_sys::Event| selector.handle_selection_change());
+        web_sys::window()
+            .unwrap()
+            .document()
+            .unwrap()
+            .add_event_listener_with_callback("selectionchange", selection.as_ref().unchecked_ref())
+            .unwrap();
+        selection.forget();
     };
     let sig = async move {
         editor
             .signal()
             .map(|nodes| {
                 log::info!("{}", serde_json::to_string(&nodes).unwrap());
+                log::info!("{:?}", table_of_contents(&nodes));
             })
             .to_future()
             .await;
@@ -56,33 +113,58 @@ fn app() -> Dom {
 
     html! {
         <div id="holder">
+            <style>{NUMBERED_HEADINGS_CSS}</style>
             <div id="menubar">
-                <button on:click=state
-                    .callback_with(|state, _| state.execute(HeaderBlock))>"H"</button>
-                <button on:click=state
-                    .callback_with(|state, _| state.execute(ParagraphBlock))>"P"</button>
-                // <button on:click=state.callback_with(|state, _| RemoveBold.execute(&mut state.clone()))>
-                //     "RB"
-                // </button>
-                <button on:click=state
-                    .callback_with(|state, _| {
-                        state
-                            .add_node(
-                                Image {
-                                    src: "https://placehold.co/600x400".to_owned(),
-                                    alt: "Placeholder".to_owned(),
-                                },
-                            )
-                    })>"I"</button>
-                <div></div>
-
-                <button on:click=state
-                    .callback_with(|state, _| state.execute(MakeBold))>"B"</button>
+                {Toolbar::new(&state)}
+                <input
+                    type="file"
+                    accept="image/*"
+                    on:change=state
+                        .callback_with(|state, event: Event| {
+                            let input: web_sys::HtmlInputElement = event
+                                .target()
+                                .unwrap()
+                                .unchecked_into();
+                            if let Some(files) = input.files() {
+                                state.upload_files(&files);
+                            }
+                            input.set_value("");
+                        })
+                />
+                <input
+                    type="color"
+                    on:change=state
+                        .callback_with(|state, event: Event| {
+                            let input: web_sys::HtmlInputElement = event
+                                .target()
+                                .unwrap()
+                                .unchecked_into();
+                            state.execute(SetTextColor(Some(input.value())));
+                        })
+                />
+                {emoji_picker(&state)}
             </div>
-            <div bind:ref=node use:future=fut use:future=sig contenteditable="true" class="prose">
+            <div
+                bind:ref=node
+                use:future=fut
+                use:future=sig
+                on:input=state.callback_with(|state, _| state.handle_input())
+                on:click=state.callback_with(|state, event: Event| state.handle_click(&event))
+                on:drop=state.callback_with(|state, event: Event| state.handle_drop(&event))
+                on:drag-over=state
+                    .callback_with(|state, event: Event| state.handle_drag_over(&event))
+                on:key-down=state
+                    .callback_with(|state, event: Event| {
+                        state.handle_keydown(event.unchecked_ref())
+                    })
+                contenteditable="true"
+                class="prose"
+            >
                 <h1>"A simple Editor"</h1>
                 {Paragraph::default().render()}
             </div>
+            {suggestion_popup(&state)}
+            {bubble_toolbar(&state)}
 
         </div>
     }